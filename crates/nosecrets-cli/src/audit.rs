@@ -0,0 +1,441 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use globset::GlobMatcher;
+use serde::Serialize;
+
+use nosecrets_core::{collect_files, discover_repo_root, Detector, ScanOptions, WalkOptions};
+use nosecrets_filter::{
+    normalize_path, path_glob, relative_path, AllowRule, Config, Filter, IgnoreEntry,
+    FILE_IGNORE_DIRECTIVE, FILE_IGNORE_LINES, IGNORE_END_MARKER, IGNORE_START_MARKER,
+};
+use nosecrets_report::{mask_secret, Finding, SuppressionReason};
+
+use crate::{build_detector, resolve_targets, EXIT_CLEAN};
+
+const EXIT_STALE: i32 = 1;
+
+#[derive(Parser, Debug)]
+pub(crate) struct AuditArgs {
+    /// Files or directories to audit (default: the whole repository)
+    paths: Vec<PathBuf>,
+    /// Print the suppressions as JSON
+    #[arg(long)]
+    json: bool,
+    /// Exit with 1 when a suppression is stale or expired
+    #[arg(long)]
+    fail_on_stale: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Active,
+    Stale,
+    Expired,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Active => "active",
+            Status::Stale => "stale",
+            Status::Expired => "expired",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AuditEntry {
+    kind: SuppressionReason,
+    // Where the suppression is written: a file and line, or a config key.
+    source: String,
+    // What it suppresses: a fingerprint, rule, value, pattern or path glob.
+    target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    status: Status,
+    // Findings it hides in the audited files.
+    suppressed: usize,
+}
+
+// A suppression and how to tell whether a kept finding was hidden by it.
+enum Matcher<'a> {
+    Line {
+        path: String,
+        line: usize,
+    },
+    Block {
+        path: String,
+        lines: RangeInclusive<usize>,
+    },
+    File {
+        path: String,
+    },
+    Entry(&'a IgnoreEntry),
+    AllowRule(AllowRule),
+    Allowed(&'a str),
+    Path(GlobMatcher),
+    Expired,
+}
+
+impl Matcher<'_> {
+    fn suppresses(&self, filter: &Filter, finding: &Finding) -> bool {
+        let path = Path::new(&finding.path);
+        match (self, finding.suppressed) {
+            (Matcher::Line { path, line }, Some(SuppressionReason::InlineIgnore)) => {
+                *path == finding.path && *line == finding.line
+            }
+            (Matcher::Block { path, lines }, Some(SuppressionReason::IgnoreBlock)) => {
+                *path == finding.path && lines.contains(&finding.line)
+            }
+            (Matcher::File { path }, Some(SuppressionReason::FileIgnore)) => *path == finding.path,
+            (
+                Matcher::Entry(entry),
+                Some(SuppressionReason::FingerprintIgnore | SuppressionReason::Baseline),
+            ) => filter
+                .fingerprint_ignore_entry(&finding.fingerprint, path)
+                .is_some_and(|found| ptr::eq(found, *entry)),
+            (Matcher::Entry(entry), Some(SuppressionReason::RuleIgnore)) => filter
+                .rule_ignore_entry(&finding.rule_id, path)
+                .is_some_and(|found| ptr::eq(found, *entry)),
+            (Matcher::AllowRule(allowed), Some(SuppressionReason::AllowRule)) => filter
+                .rule_allowance(&finding.rule_id, path)
+                .is_some_and(|found| found == *allowed),
+            (Matcher::Allowed(allowed), Some(SuppressionReason::AllowPattern)) => {
+                finding.suppressed_by.as_deref() == Some(*allowed)
+            }
+            (Matcher::Path(matcher), Some(SuppressionReason::IgnoredPath)) => {
+                matcher.is_match(&finding.path)
+            }
+            _ => false,
+        }
+    }
+}
+
+struct Suppression<'a> {
+    entry: AuditEntry,
+    matcher: Matcher<'a>,
+}
+
+impl<'a> Suppression<'a> {
+    fn new(
+        kind: SuppressionReason,
+        source: String,
+        target: String,
+        reason: Option<String>,
+        matcher: Matcher<'a>,
+    ) -> Self {
+        let status = match matcher {
+            Matcher::Expired => Status::Expired,
+            _ => Status::Stale,
+        };
+        Self {
+            entry: AuditEntry {
+                kind,
+                source,
+                target,
+                reason,
+                status,
+                suppressed: 0,
+            },
+            matcher,
+        }
+    }
+}
+
+// Re-scans with suppressed findings kept and counts, for every inline
+// marker, ignore entry and allow setting, the findings it hides. One that
+// hides nothing is stale and can be removed.
+pub(crate) fn run_audit(args: AuditArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or_else(|| cwd.clone());
+    let config = Config::load(&root)?;
+    let walk_options = WalkOptions::from_config(config.as_ref());
+    let detector = build_detector(&root, config.clone())?;
+    let options = ScanOptions {
+        keep_suppressed: true,
+        ..detector.options().clone()
+    };
+    let detector = detector.with_options(options);
+
+    let targets = resolve_targets(&cwd, &root, &args.paths)?;
+    let files = collect_files(&root, &targets.paths, &walk_options)?;
+    let outcome = detector.scan_paths(&root, &files);
+    for failure in &outcome.failures {
+        tracing::warn!("{}: {}", failure.path.display(), failure.error);
+    }
+
+    let mut suppressions = Vec::new();
+    for file in &files {
+        // Binary and unreadable files have no markers to audit.
+        let Ok(text) = fs::read_to_string(file) else {
+            continue;
+        };
+        let path = normalize_path(&relative_path(file, &root));
+        suppressions.extend(file_suppressions(&path, &text));
+    }
+    suppressions.extend(config_suppressions(&detector, config.as_ref())?);
+
+    for suppression in &mut suppressions {
+        let suppressed = outcome
+            .findings
+            .iter()
+            .filter(|finding| suppression.matcher.suppresses(detector.filter(), finding))
+            .count();
+        let entry = &mut suppression.entry;
+        entry.suppressed = suppressed;
+        if suppressed > 0 {
+            entry.status = Status::Active;
+        }
+    }
+    let entries: Vec<AuditEntry> = suppressions
+        .into_iter()
+        .map(|suppression| suppression.entry)
+        .collect();
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        print_audit(&entries, outcome.files_scanned);
+    }
+    let stale = entries.iter().any(|entry| entry.status != Status::Active);
+    Ok(if args.fail_on_stale && stale {
+        EXIT_STALE
+    } else {
+        EXIT_CLEAN
+    })
+}
+
+// Inline markers, ignore blocks and the `@nosecrets-ignore-file` directive
+// of one file.
+fn file_suppressions(path: &str, text: &str) -> Vec<Suppression<'static>> {
+    let mut suppressions = Vec::new();
+    if let Some(ignore) = Filter::file_ignore(text) {
+        let line = text
+            .lines()
+            .take(FILE_IGNORE_LINES)
+            .position(|line| line.contains(FILE_IGNORE_DIRECTIVE))
+            .map_or(1, |idx| idx + 1);
+        let target = if ignore.rules.is_empty() {
+            "all rules".to_string()
+        } else {
+            ignore.rules.join(",")
+        };
+        suppressions.push(Suppression::new(
+            SuppressionReason::FileIgnore,
+            format!("{path}:{line}"),
+            target,
+            Some(ignore.reason),
+            Matcher::File {
+                path: path.to_string(),
+            },
+        ));
+    }
+    for lines in Filter::ignored_regions(text) {
+        suppressions.push(Suppression::new(
+            SuppressionReason::IgnoreBlock,
+            format!("{path}:{}", lines.start()),
+            format!("lines {}-{}", lines.start(), lines.end()),
+            None,
+            Matcher::Block {
+                path: path.to_string(),
+                lines,
+            },
+        ));
+    }
+    for (idx, line) in text.lines().enumerate() {
+        let directive = [
+            FILE_IGNORE_DIRECTIVE,
+            IGNORE_START_MARKER,
+            IGNORE_END_MARKER,
+        ]
+        .iter()
+        .any(|marker| line.contains(marker));
+        if directive || !Filter::is_inline_ignored(line) {
+            continue;
+        }
+        suppressions.push(Suppression::new(
+            SuppressionReason::InlineIgnore,
+            format!("{path}:{}", idx + 1),
+            format!("line {}", idx + 1),
+            None,
+            Matcher::Line {
+                path: path.to_string(),
+                line: idx + 1,
+            },
+        ));
+    }
+    suppressions
+}
+
+// Entries of .nosecretsignore and the `[ignore]` and `[allow]` settings.
+fn config_suppressions<'a>(
+    detector: &'a Detector,
+    config: Option<&'a Config>,
+) -> Result<Vec<Suppression<'a>>> {
+    let filter = detector.filter();
+    let mut suppressions = Vec::new();
+    let entries = filter
+        .ignore_entries()
+        .iter()
+        .map(|entry| (entry, Matcher::Entry(entry)))
+        .chain(
+            filter
+                .expired_ignore_entries()
+                .iter()
+                .map(|entry| (entry, Matcher::Expired)),
+        );
+    for (entry, matcher) in entries {
+        let kind = if entry.rule.is_some() {
+            SuppressionReason::RuleIgnore
+        } else if entry.is_baseline() {
+            SuppressionReason::Baseline
+        } else {
+            SuppressionReason::FingerprintIgnore
+        };
+        let mut target = entry.fingerprint.clone();
+        if let Some(path) = &entry.path {
+            target.push(':');
+            target.push_str(path);
+        }
+        suppressions.push(Suppression::new(
+            kind,
+            ".nosecretsignore".to_string(),
+            target,
+            entry.reason.clone(),
+            matcher,
+        ));
+    }
+    let allow_rules = filter
+        .allow_rules()
+        .into_iter()
+        .map(|allowed| (allowed.clone(), Matcher::AllowRule(allowed)))
+        .chain(
+            filter
+                .expired_allow_rules()
+                .into_iter()
+                .map(|allowed| (allowed, Matcher::Expired)),
+        );
+    for (allowed, matcher) in allow_rules {
+        // Without paths the rule is disabled for the whole repository.
+        let target = if allowed.paths.is_empty() {
+            allowed.rule.clone()
+        } else {
+            format!("{} in {}", allowed.rule, allowed.paths.join(", "))
+        };
+        suppressions.push(Suppression::new(
+            SuppressionReason::AllowRule,
+            "allow.rules".to_string(),
+            target,
+            None,
+            matcher,
+        ));
+    }
+    let Some(config) = config else {
+        return Ok(suppressions);
+    };
+    for value in &config.allow.values {
+        suppressions.push(Suppression::new(
+            SuppressionReason::AllowPattern,
+            "allow.values".to_string(),
+            mask_secret(value),
+            None,
+            Matcher::Allowed(value),
+        ));
+    }
+    for pattern in &config.allow.patterns {
+        suppressions.push(Suppression::new(
+            SuppressionReason::AllowPattern,
+            "allow.patterns".to_string(),
+            pattern.clone(),
+            None,
+            Matcher::Allowed(pattern),
+        ));
+    }
+    for pattern in &config.ignore.paths {
+        let matcher = path_glob(pattern)
+            .with_context(|| format!("invalid ignore path {pattern}"))?
+            .compile_matcher();
+        suppressions.push(Suppression::new(
+            SuppressionReason::IgnoredPath,
+            "ignore.paths".to_string(),
+            pattern.clone(),
+            None,
+            Matcher::Path(matcher),
+        ));
+    }
+    Ok(suppressions)
+}
+
+fn print_audit(entries: &[AuditEntry], files_scanned: usize) {
+    if entries.is_empty() {
+        println!("No suppressions found in {files_scanned} scanned file(s)");
+        return;
+    }
+    let count = |status| {
+        entries
+            .iter()
+            .filter(|entry| entry.status == status)
+            .count()
+    };
+    println!(
+        "{} suppression(s) in {} scanned file(s): {} active, {} stale, {} expired",
+        entries.len(),
+        files_scanned,
+        count(Status::Active),
+        count(Status::Stale),
+        count(Status::Expired)
+    );
+    println!();
+    println!(
+        "{:<8}  {:>10}  {:<18}  {:<28}  target",
+        "status", "suppressed", "kind", "source"
+    );
+    for entry in entries {
+        let mut target = entry.target.clone();
+        if let Some(reason) = &entry.reason {
+            target.push_str(&format!(" (reason: {reason})"));
+        }
+        println!(
+            "{:<8}  {:>10}  {:<18}  {:<28}  {}",
+            entry.status.as_str(),
+            entry.suppressed,
+            entry.kind.as_str(),
+            entry.source,
+            target
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_suppressions_find_each_marker_kind() {
+        let text = "# @nosecrets-ignore-file reason=\"fixtures\" rules=aws-access-key\n\
+                    key = 1 # @nosecrets-ignore\n\
+                    # @nosecrets-ignore-start\n\
+                    key = 2\n\
+                    # @nosecrets-ignore-end\n\
+                    key = 3 # @nsi\n";
+        let kinds: Vec<(SuppressionReason, String)> = file_suppressions("a.py", text)
+            .into_iter()
+            .map(|suppression| (suppression.entry.kind, suppression.entry.source))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (SuppressionReason::FileIgnore, "a.py:1".to_string()),
+                (SuppressionReason::IgnoreBlock, "a.py:3".to_string()),
+                (SuppressionReason::InlineIgnore, "a.py:2".to_string()),
+                (SuppressionReason::InlineIgnore, "a.py:6".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+
+use nosecrets_report::{parse_findings, Finding, ReportDiff};
+
+#[derive(Parser, Debug)]
+pub(crate) struct DiffArgs {
+    /// Earlier report from `nosecrets scan --format json` (or ndjson)
+    old: PathBuf,
+    /// Later report to compare against it
+    new: PathBuf,
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: DiffFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum DiffFormat {
+    Text,
+    Json,
+}
+
+// Exits with 1 only when `new` has findings that `old` did not.
+pub(crate) fn run_diff(args: DiffArgs) -> Result<i32> {
+    let diff = ReportDiff::new(read_report(&args.old)?, read_report(&args.new)?);
+    match args.format {
+        DiffFormat::Text => diff.print_terminal()?,
+        DiffFormat::Json => diff.print_json()?,
+    }
+    Ok(diff.exit_code())
+}
+
+fn read_report(path: &Path) -> Result<Vec<Finding>> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_findings(&text).with_context(|| format!("failed to parse report {}", path.display()))
+}
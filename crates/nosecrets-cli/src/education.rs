@@ -0,0 +1,202 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use nosecrets_report::{Finding, Report};
+use nosecrets_rules::Severity;
+
+use crate::lsp::comment_prefix;
+use crate::shell_quote;
+
+const SHOWN_MARKER: &str = "first-finding-shown";
+
+// Explains a blocked commit once per user. Later blocks only print the
+// regular report. Failing to read or write the state never fails the scan.
+pub(crate) fn explain_first_block(report: &Report, fail_on: Severity) {
+    let Some(dir) = state_dir() else {
+        return;
+    };
+    if show_once(&dir) {
+        eprint!("{}", guide(report, fail_on));
+    }
+}
+
+// Printed after every blocked commit: the commands that get it through,
+// filled in with the blocking findings.
+pub(crate) fn print_remediation(report: &Report, fail_on: Severity) {
+    eprint!("{}", remediation(report, fail_on));
+}
+
+fn remediation(report: &Report, fail_on: Severity) -> String {
+    let blocking: Vec<&Finding> = report
+        .findings()
+        .iter()
+        .filter(|finding| finding.severity.blocks_at(fail_on))
+        .collect();
+    let Some(first) = blocking.first() else {
+        return String::new();
+    };
+    let files: BTreeSet<&str> = blocking
+        .iter()
+        .map(|finding| finding.path.as_str())
+        .collect();
+    let files: Vec<String> = files.into_iter().map(shell_quote).collect();
+    let mut out = String::from("\nTo unblock this commit:\n");
+    out.push_str("  Remove the secrets, or unstage the files and commit the rest:\n");
+    out.push_str(&format!("    git restore --staged {}\n", files.join(" ")));
+    let commands: BTreeSet<&str> = blocking
+        .iter()
+        .filter_map(|finding| finding.store_command.as_deref())
+        .collect();
+    if !commands.is_empty() {
+        out.push_str("  Store them in the secret manager, pasting each value on stdin:\n");
+        for command in commands {
+            out.push_str(&format!("    {command}\n"));
+        }
+    }
+    out.push_str("  If a finding is not a secret, ignore it in .nosecretsignore:\n");
+    let mut seen = BTreeSet::new();
+    for finding in &blocking {
+        if seen.insert((&finding.fingerprint, &finding.path)) {
+            out.push_str(&format!(
+                "    nosecrets ignore {} --path {}\n",
+                finding.fingerprint,
+                shell_quote(&finding.path)
+            ));
+        }
+    }
+    out.push_str(&format!(
+        "  or end the line with an inline marker, e.g. line {} of {}:\n    {} @nosecrets-ignore\n",
+        first.line,
+        first.path,
+        comment_prefix(Path::new(&first.path))
+    ));
+    out
+}
+
+fn show_once(dir: &Path) -> bool {
+    let marker = dir.join(SHOWN_MARKER);
+    if marker.exists() {
+        return false;
+    }
+    if fs::create_dir_all(dir).is_err() {
+        return true;
+    }
+    let _ = fs::write(&marker, "");
+    true
+}
+
+fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("NOSECRETS_STATE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_STATE_HOME") {
+        return Some(PathBuf::from(dir).join("nosecrets"));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = env::var_os("LOCALAPPDATA") {
+            return Some(PathBuf::from(dir).join("nosecrets"));
+        }
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state/nosecrets"))
+}
+
+fn guide(report: &Report, fail_on: Severity) -> String {
+    let rules: BTreeSet<&str> = report
+        .findings()
+        .iter()
+        .filter(|finding| finding.severity.blocks_at(fail_on))
+        .map(|finding| &*finding.rule_name)
+        .collect();
+    let example = report
+        .findings()
+        .iter()
+        .find(|finding| finding.severity.blocks_at(fail_on))
+        .map(|finding| finding.fingerprint.as_str())
+        .unwrap_or("nsi_<fingerprint>");
+    let found = rules.into_iter().collect::<Vec<_>>().join(", ");
+    format!(
+        "
+nosecrets blocked this commit because it looks like it contains a secret
+({found}).
+
+Why this matters: once a secret is committed it stays in the git history,
+even if a later commit removes it. Anyone with access to a clone can use it.
+
+How to continue:
+  1. Remove the secret and load it from an environment variable or secret
+     manager instead. `nosecrets fix --staged` can replace it for you.
+     If the secret was ever pushed, rotate it.
+  2. If it is not a real secret, mark the line with an inline
+     `@nosecrets-ignore` comment or run `nosecrets ignore {example}`.
+  3. If it is a test fixture, allow the rule for that path in
+     .nosecrets.toml, e.g. `rules = [{{ rule = \"...\", paths = [\"tests/**\"] }}]`
+     under [allow].
+
+This explanation is shown once.
+"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nosecrets_report::Finding;
+    use nosecrets_rules::Severity;
+    use tempfile::tempdir;
+
+    #[test]
+    fn guide_is_shown_once() {
+        let dir = tempdir().expect("tempdir");
+        let state = dir.path().join("nested/state");
+        assert!(show_once(&state));
+        assert!(!show_once(&state));
+    }
+
+    #[test]
+    fn guide_names_blocking_rules() {
+        let report = Report::new(vec![Finding {
+            path: "src/main.rs".to_string(),
+            line: 1,
+            end_line: 1,
+            column: 1,
+            end_column: 21,
+            byte_offset: 0,
+            match_length: 20,
+            rule_id: "aws-access-key".into(),
+            rule_name: "AWS Access Key ID".into(),
+            severity: Severity::Critical,
+            fingerprint: "nsi_0123456789ab".to_string(),
+            preview: "AKIA...WXYZ".to_string(),
+            entropy: 3.5,
+            confidence: 0.8,
+            sampled: false,
+            cell: None,
+            message: None,
+            package: None,
+            owners: Vec::new(),
+            expired_ignore: None,
+            suppressed: None,
+            suppressed_by: None,
+            suggestion: None,
+            store_command: Some("vault kv put secret/AWS_KEY value=-".to_string()),
+            blame: None,
+            jwt: None,
+            description: None,
+            docs_url: None,
+            remediation: None,
+        }]);
+        let text = guide(&report, Severity::Medium);
+        assert!(text.contains("(AWS Access Key ID)"));
+        assert!(text.contains("nosecrets ignore nsi_0123456789ab"));
+
+        let text = remediation(&report, Severity::Medium);
+        assert!(text.contains("git restore --staged src/main.rs\n"));
+        assert!(text.contains("on stdin:\n    vault kv put secret/AWS_KEY value=-\n"));
+        assert!(text.contains("nosecrets ignore nsi_0123456789ab --path src/main.rs\n"));
+        assert!(text.contains("line 1 of src/main.rs:\n    // @nosecrets-ignore\n"));
+        assert!(!remediation(&report, Severity::Critical).is_empty());
+        assert!(remediation(&Report::new(Vec::new()), Severity::Low).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+
+use nosecrets_core::{discover_repo_root, git_user_name};
+use nosecrets_filter::{is_valid_date, normalize_path, today};
+
+use crate::ignore::metadata_value;
+use crate::{append_ignore, EXIT_CLEAN};
+
+const EXCEPTIONS_DIR: &str = ".nosecrets/exceptions";
+const AUDIT_LOG: &str = "audit.jsonl";
+
+#[derive(Parser, Debug)]
+pub(crate) struct ExceptionArgs {
+    #[command(subcommand)]
+    command: ExceptionCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ExceptionCommand {
+    /// Write an exception request file for code review
+    Request(RequestArgs),
+    /// Turn exception requests into expiring ignore entries
+    Apply(ApplyArgs),
+}
+
+#[derive(Parser, Debug)]
+struct RequestArgs {
+    /// Fingerprint of the finding (nsi_...)
+    finding_id: String,
+    /// Why the finding should be suppressed
+    #[arg(long)]
+    reason: String,
+    /// Last day the exception is valid
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    expires: String,
+    /// Path glob the exception is limited to
+    #[arg(long)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct ApplyArgs {
+    /// Requests to apply (default: all pending requests)
+    finding_ids: Vec<String>,
+    /// Name recorded as approver (default: git user.name)
+    #[arg(long)]
+    approver: Option<String>,
+    /// Override .nosecretsignore location
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExceptionRequest {
+    fingerprint: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    reason: String,
+    expires: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    requested_by: Option<String>,
+    requested_on: String,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    #[serde(flatten)]
+    request: &'a ExceptionRequest,
+    approved_by: Option<String>,
+    approved_on: String,
+    entry: String,
+}
+
+pub(crate) fn run_exception(args: ExceptionArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
+    match args.command {
+        ExceptionCommand::Request(args) => request(&root, args)?,
+        ExceptionCommand::Apply(args) => apply(&root, args)?,
+    }
+    Ok(EXIT_CLEAN)
+}
+
+fn request(root: &Path, args: RequestArgs) -> Result<()> {
+    if !args.finding_id.starts_with("nsi_") {
+        bail!(
+            "invalid finding id {}: expected nsi_<hash>",
+            args.finding_id
+        );
+    }
+    if !is_valid_date(&args.expires) {
+        bail!("invalid --expires {}: expected YYYY-MM-DD", args.expires);
+    }
+    let today = today();
+    if args.expires < today {
+        bail!("--expires {} is in the past", args.expires);
+    }
+    let request = ExceptionRequest {
+        fingerprint: args.finding_id,
+        path: args.path.as_deref().map(normalize_path),
+        reason: args.reason,
+        expires: args.expires,
+        requested_by: git_user_name(root),
+        requested_on: today,
+    };
+    let dir = root.join(EXCEPTIONS_DIR);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.toml", request.fingerprint));
+    let content = toml::to_string(&request).context("failed to serialize request")?;
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    println!("Wrote exception request {}", path.display());
+    println!("Commit it for review; an approver runs `nosecrets exception apply`.");
+    Ok(())
+}
+
+fn apply(root: &Path, args: ApplyArgs) -> Result<()> {
+    let dir = root.join(EXCEPTIONS_DIR);
+    let ignore_path = args.file.unwrap_or_else(|| root.join(".nosecretsignore"));
+    let approver = args.approver.or_else(|| git_user_name(root));
+    let today = today();
+
+    let mut applied = 0;
+    for path in request_files(&dir)? {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let request: ExceptionRequest = toml::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        if !args.finding_ids.is_empty() && !args.finding_ids.contains(&request.fingerprint) {
+            continue;
+        }
+        if request.expires < today {
+            eprintln!(
+                "nosecrets: skipping {}: expired on {}",
+                request.fingerprint, request.expires
+            );
+            continue;
+        }
+        let entry = ignore_entry(&request, approver.as_deref(), &today);
+        append_ignore(&ignore_path, &entry)?;
+        append_audit(
+            &dir.join(AUDIT_LOG),
+            &AuditRecord {
+                request: &request,
+                approved_by: approver.clone(),
+                approved_on: today.clone(),
+                entry,
+            },
+        )?;
+        fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        println!(
+            "Applied exception {} (expires {})",
+            request.fingerprint, request.expires
+        );
+        applied += 1;
+    }
+    println!(
+        "Applied {applied} exception(s) to {}",
+        ignore_path.display()
+    );
+    Ok(())
+}
+
+fn request_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn append_audit(path: &Path, record: &AuditRecord<'_>) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+fn ignore_entry(request: &ExceptionRequest, approver: Option<&str>, today: &str) -> String {
+    let mut entry = request.fingerprint.clone();
+    if let Some(path) = &request.path {
+        entry.push(':');
+        entry.push_str(path);
+    }
+    entry.push_str(&format!(" # reason={}", metadata_value(&request.reason)));
+    if let Some(approver) = approver {
+        entry.push_str(&format!(" by={}", metadata_value(approver)));
+    }
+    entry.push_str(&format!(" on={today} expires={}", request.expires));
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nosecrets_filter::{load_ignore_file, parse_metadata};
+    use tempfile::tempdir;
+
+    #[test]
+    fn ignore_entry_round_trips_through_ignore_file() {
+        let request = ExceptionRequest {
+            fingerprint: "nsi_0123456789ab".to_string(),
+            path: Some("tests/**".to_string()),
+            reason: "fixture for \"parser\" tests".to_string(),
+            expires: "2999-01-31".to_string(),
+            requested_by: Some("Sam".to_string()),
+            requested_on: "2024-05-01".to_string(),
+        };
+        let entry = ignore_entry(&request, Some("Robin Roe"), "2024-05-02");
+        assert_eq!(
+            entry,
+            "nsi_0123456789ab:tests/** # reason=\"fixture for 'parser' tests\" \
+             by=\"Robin Roe\" on=2024-05-02 expires=2999-01-31"
+        );
+        let metadata = parse_metadata(entry.split_once(" #").expect("metadata").1);
+        assert_eq!(metadata[1], ("by".to_string(), "Robin Roe".to_string()));
+
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join(".nosecretsignore");
+        fs::write(&path, format!("{entry}\n")).expect("write");
+        let entries = load_ignore_file(&path).expect("load");
+        assert_eq!(entries[0].fingerprint, "nsi_0123456789ab");
+        assert_eq!(entries[0].expires.as_deref(), Some("2999-01-31"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use nosecrets_core::{collect_files, discover_repo_root, Detector, ScanOptions, WalkOptions};
+use nosecrets_filter::{Config, IgnoreEntry};
+use nosecrets_report::{location, Finding, SuppressionReason};
+
+use crate::{build_detector, resolve_targets, EXIT_CLEAN};
+
+const EXIT_NOT_FOUND: i32 = 1;
+
+#[derive(Parser, Debug)]
+pub(crate) struct ExplainArgs {
+    /// Fingerprint of a finding (nsi_...), or a prefix of it
+    fingerprint: String,
+    /// Files or directories to search (default: the whole repository)
+    paths: Vec<PathBuf>,
+    /// Print the matching findings as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+// Re-scans with suppressed findings kept, so a fingerprint can be traced
+// whether it is reported or hidden by an ignore or allow mechanism.
+pub(crate) fn run_explain(args: ExplainArgs) -> Result<i32> {
+    if args.fingerprint.trim().is_empty() {
+        bail!("fingerprint must not be empty");
+    }
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or_else(|| cwd.clone());
+    let config = Config::load(&root)?;
+    let walk_options = WalkOptions::from_config(config.as_ref());
+    let detector = build_detector(&root, config)?;
+    let options = ScanOptions {
+        keep_suppressed: true,
+        ..detector.options().clone()
+    };
+    let detector = detector.with_options(options);
+
+    let targets = resolve_targets(&cwd, &root, &args.paths)?;
+    let files = collect_files(&root, &targets.paths, &walk_options)?;
+    let outcome = detector.scan_paths(&root, &files);
+    for failure in &outcome.failures {
+        tracing::warn!("{}: {}", failure.path.display(), failure.error);
+    }
+    let mut findings: Vec<Finding> = outcome
+        .findings
+        .into_iter()
+        .filter(|finding| finding.fingerprint.starts_with(args.fingerprint.trim()))
+        .collect();
+    findings.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else if findings.is_empty() {
+        println!(
+            "{} not found in {} scanned file(s)",
+            args.fingerprint.trim(),
+            outcome.files_scanned
+        );
+    } else {
+        print_explanation(&detector, &findings);
+    }
+    Ok(if findings.is_empty() {
+        EXIT_NOT_FOUND
+    } else {
+        EXIT_CLEAN
+    })
+}
+
+fn print_explanation(detector: &Detector, findings: &[Finding]) {
+    let reported = findings
+        .iter()
+        .filter(|finding| finding.suppressed.is_none())
+        .count();
+    println!(
+        "Found {} time(s): {} reported, {} suppressed",
+        findings.len(),
+        reported,
+        findings.len() - reported
+    );
+    for finding in findings {
+        println!();
+        println!("{}", location(finding));
+        println!(
+            "  Rule:        {} ({}), {}",
+            finding.rule_name,
+            finding.rule_id,
+            finding.severity.as_str()
+        );
+        println!("  Fingerprint: {}", finding.fingerprint);
+        match finding.suppressed {
+            None => println!("  Status:      reported"),
+            Some(reason) => {
+                println!(
+                    "  Status:      suppressed by {}: {}",
+                    reason.as_str(),
+                    reason.describe()
+                );
+                if let Some(entry) = ignore_entry(detector, finding, reason) {
+                    println!("  Entry:       {}", describe_entry(entry));
+                }
+            }
+        }
+        if let Some(expires) = &finding.expired_ignore {
+            println!("  Note:        a suppression for it expired on {expires}");
+        }
+    }
+}
+
+// The .nosecretsignore entry behind a suppression, for its reason and owner.
+fn ignore_entry<'a>(
+    detector: &'a Detector,
+    finding: &Finding,
+    reason: SuppressionReason,
+) -> Option<&'a IgnoreEntry> {
+    let path = Path::new(&finding.path);
+    match reason {
+        SuppressionReason::FingerprintIgnore | SuppressionReason::Baseline => detector
+            .filter()
+            .fingerprint_ignore_entry(&finding.fingerprint, path),
+        SuppressionReason::RuleIgnore => {
+            detector.filter().rule_ignore_entry(&finding.rule_id, path)
+        }
+        _ => None,
+    }
+}
+
+fn describe_entry(entry: &IgnoreEntry) -> String {
+    let mut text = entry.fingerprint.clone();
+    if let Some(path) = &entry.path {
+        text.push(':');
+        text.push_str(path);
+    }
+    let details: Vec<String> = [
+        ("reason", &entry.reason),
+        ("by", &entry.by),
+        ("on", &entry.on),
+        ("expires", &entry.expires),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.as_ref().map(|value| format!("{key}={value}")))
+    .collect();
+    if !details.is_empty() {
+        text.push_str(&format!(" ({})", details.join(", ")));
+    }
+    text
+}
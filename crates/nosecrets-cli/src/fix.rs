@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+
+use nosecrets_core::{
+    collect_files, collect_staged_files, discover_repo_root, GitError, SecretSpan, WalkOptions,
+};
+use nosecrets_filter::Config;
+
+use crate::{build_detector, shell_quote, EXIT_CLEAN};
+
+// Backups hold the secrets, so they go where git does not look.
+const BACKUP_DIR: &str = ".nosecrets/backup";
+
+#[derive(Parser, Debug)]
+pub(crate) struct FixArgs {
+    /// Fix staged files
+    #[arg(long)]
+    staged: bool,
+    /// Show the replacements without writing any file
+    #[arg(long)]
+    dry_run: bool,
+    /// Confirm each replacement
+    #[arg(long)]
+    interactive: bool,
+    /// Do not keep a copy of rewritten files in .nosecrets/backup
+    #[arg(long)]
+    no_backup: bool,
+    /// What to put in place of the secret
+    #[arg(long, value_enum, default_value = "redacted")]
+    replace_with: Placeholder,
+    /// Files or directories to fix
+    paths: Vec<PathBuf>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Placeholder {
+    /// <REDACTED>
+    Redacted,
+    /// ${RULE_ID} environment variable reference
+    Env,
+}
+
+impl Placeholder {
+    fn render(self, rule_id: &str) -> String {
+        match self {
+            Placeholder::Redacted => "<REDACTED>".to_string(),
+            Placeholder::Env => format!("${{{}}}", env_var_name(rule_id)),
+        }
+    }
+}
+
+pub(crate) fn run_fix(args: FixArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let repo_root = discover_repo_root(&cwd)?;
+    let root = repo_root.clone().unwrap_or(cwd);
+
+    let config = Config::load(&root)?;
+    let walk_options = WalkOptions::from_config(config.as_ref());
+    let detector = build_detector(&root, config)?;
+
+    let files = if args.staged {
+        let Some(repo_root) = repo_root else {
+            return Err(GitError::RepositoryRequired("--staged").into());
+        };
+        collect_staged_files(&repo_root)?
+    } else {
+        collect_files(&root, &args.paths, &walk_options)?
+    };
+
+    let mut replaced = 0;
+    let mut files_changed = Vec::new();
+    for path in files {
+        let rel_path = path.strip_prefix(&root).unwrap_or(&path).to_path_buf();
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let spans = non_overlapping(detector.scan_spans(&rel_path, &text));
+        let mut replacements = Vec::new();
+        for span in spans {
+            let placeholder = args.replace_with.render(&span.finding.rule_id);
+            let finding = &span.finding;
+            println!(
+                "{}:{}:{} {} ({}) {} -> {}",
+                finding.path,
+                finding.line,
+                finding.column,
+                finding.rule_name,
+                finding.rule_id,
+                finding.preview,
+                placeholder
+            );
+            if args.interactive && !args.dry_run && !confirm("Replace this secret?")? {
+                continue;
+            }
+            replacements.push((span.range, placeholder));
+        }
+        if replacements.is_empty() {
+            continue;
+        }
+        replaced += replacements.len();
+        files_changed.push(rel_path.clone());
+        if args.dry_run {
+            continue;
+        }
+        if !args.no_backup {
+            write_backup(&root, &rel_path, &path)?;
+        }
+        let fixed = apply_replacements(&text, &replacements);
+        fs::write(&path, fixed).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    let count = files_changed.len();
+    if args.dry_run {
+        println!("Would replace {replaced} secret(s) in {count} file(s)");
+    } else {
+        println!("Replaced {replaced} secret(s) in {count} file(s)");
+        if replaced > 0 {
+            if !args.no_backup {
+                println!(
+                    "Backups in {BACKUP_DIR} still contain the secrets: delete them once verified."
+                );
+            }
+            // Only the working tree was fixed; the index still has the secrets.
+            if args.staged {
+                let paths: Vec<String> = files_changed
+                    .iter()
+                    .map(|path| shell_quote(&path.to_string_lossy()))
+                    .collect();
+                println!("Stage the fixed files before committing:");
+                println!("  git add -- {}", paths.join(" "));
+            }
+            println!("Rotate these secrets: earlier commits may still contain them.");
+        }
+    }
+    Ok(EXIT_CLEAN)
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt} [y/N] ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let answer = input.trim().to_lowercase();
+    Ok(answer == "y" || answer == "yes")
+}
+
+// Several rules can report the same secret; keep the first span at each
+// position and drop anything that overlaps an earlier one.
+fn non_overlapping(mut spans: Vec<SecretSpan>) -> Vec<SecretSpan> {
+    spans.sort_by_key(|span| (span.range.start, std::cmp::Reverse(span.range.end)));
+    let mut kept: Vec<SecretSpan> = Vec::new();
+    for span in spans {
+        if kept
+            .last()
+            .is_some_and(|last| span.range.start < last.range.end)
+        {
+            continue;
+        }
+        kept.push(span);
+    }
+    kept
+}
+
+fn apply_replacements(text: &str, replacements: &[(Range<usize>, String)]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (range, replacement) in replacements {
+        output.push_str(&text[cursor..range.start]);
+        output.push_str(replacement);
+        cursor = range.end;
+    }
+    output.push_str(&text[cursor..]);
+    output
+}
+
+fn env_var_name(rule_id: &str) -> String {
+    rule_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn backup_path(root: &Path, rel_path: &Path) -> PathBuf {
+    let mut name = root.join(BACKUP_DIR).join(rel_path).into_os_string();
+    name.push(".nosecrets.bak");
+    PathBuf::from(name)
+}
+
+fn write_backup(root: &Path, rel_path: &Path, path: &Path) -> Result<()> {
+    let dir = root.join(BACKUP_DIR);
+    let backup = backup_path(root, rel_path);
+    if let Some(parent) = backup.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(&ignore, "*\n")
+            .with_context(|| format!("failed to write {}", ignore.display()))?;
+    }
+    fs::copy(path, &backup)
+        .with_context(|| format!("failed to write backup {}", backup.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_ranges_in_order() {
+        let text = "a=SECRET1 b=SECRET2";
+        let fixed = apply_replacements(
+            text,
+            &[
+                (2..9, "<REDACTED>".to_string()),
+                (12..19, "${B}".to_string()),
+            ],
+        );
+        assert_eq!(fixed, "a=<REDACTED> b=${B}");
+    }
+
+    #[test]
+    fn backups_are_kept_out_of_git() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).expect("mkdir");
+        fs::write(root.join("src/app.env"), "KEY=secret\n").expect("write");
+        write_backup(root, Path::new("src/app.env"), &root.join("src/app.env")).expect("backup");
+        let backup = root.join(".nosecrets/backup/src/app.env.nosecrets.bak");
+        assert_eq!(fs::read_to_string(backup).expect("read"), "KEY=secret\n");
+        assert_eq!(
+            fs::read_to_string(root.join(".nosecrets/backup/.gitignore")).expect("read"),
+            "*\n"
+        );
+        assert!(!root.join("src/app.env.nosecrets.bak").exists());
+    }
+
+    #[test]
+    fn placeholder_env_uses_rule_id() {
+        assert_eq!(Placeholder::Env.render("github-pat"), "${GITHUB_PAT}");
+        assert_eq!(Placeholder::Redacted.render("github-pat"), "<REDACTED>");
+    }
+}
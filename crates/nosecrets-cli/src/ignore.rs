@@ -0,0 +1,402 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+
+use nosecrets_core::{collect_files, discover_repo_root, git_user_name, ScanOptions, WalkOptions};
+use nosecrets_filter::{
+    normalize_path, read_ignore_file, today, Config, IgnoreEntry, RULE_ENTRY_PREFIX,
+};
+use nosecrets_report::Finding;
+
+use crate::{append_ignore, build_detector, exit_code_for, EXIT_CLEAN, EXIT_ERROR};
+
+#[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub(crate) struct IgnoreArgs {
+    #[command(subcommand)]
+    command: Option<IgnoreCommand>,
+    /// Fingerprint to ignore (nsi_...)
+    #[arg(required_unless_present = "rule", conflicts_with = "rule")]
+    fingerprint: Option<String>,
+    /// Ignore every finding of this rule id instead of one fingerprint
+    #[arg(long, value_name = "RULE_ID")]
+    rule: Option<String>,
+    /// Optional path glob to scope the ignore
+    #[arg(long)]
+    path: Option<PathBuf>,
+    /// Why the finding is suppressed
+    #[arg(long)]
+    reason: Option<String>,
+    /// Name recorded as author (default: git user.name)
+    #[arg(long)]
+    by: Option<String>,
+    /// Override .nosecretsignore location
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum IgnoreCommand {
+    /// List ignore entries with their reason, author and dates
+    List(ListArgs),
+    /// Remove entries for secrets no longer in the tree, merge duplicates and
+    /// sort the file
+    Prune(PruneArgs),
+}
+
+#[derive(Parser, Debug)]
+struct PruneArgs {
+    /// Print what would change without writing the file
+    #[arg(long)]
+    dry_run: bool,
+    /// Override .nosecretsignore location
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct ListArgs {
+    /// Print the entries as a JSON array
+    #[arg(long)]
+    json: bool,
+    /// Override .nosecretsignore location
+    #[arg(long)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListedEntry<'a> {
+    fingerprint: &'a str,
+    path: Option<&'a str>,
+    reason: Option<&'a str>,
+    by: Option<&'a str>,
+    on: Option<&'a str>,
+    expires: Option<&'a str>,
+    expired: bool,
+}
+
+pub(crate) fn run_ignore(args: IgnoreArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
+    match args.command {
+        Some(IgnoreCommand::List(list_args)) => return list(&root, list_args),
+        Some(IgnoreCommand::Prune(prune_args)) => return prune(&root, prune_args),
+        None => {}
+    }
+    let ignore_path = args.file.unwrap_or_else(|| root.join(".nosecretsignore"));
+    let Some(mut entry) = args
+        .fingerprint
+        .or_else(|| args.rule.map(|rule| format!("{RULE_ENTRY_PREFIX}{rule}")))
+    else {
+        return Ok(EXIT_CLEAN);
+    };
+    if let Some(path) = args.path {
+        entry.push(':');
+        entry.push_str(&normalize_path(&path));
+    }
+    entry.push_str(" #");
+    if let Some(reason) = args.reason {
+        entry.push_str(&format!(" reason={}", metadata_value(&reason)));
+    }
+    if let Some(by) = args.by.or_else(|| git_user_name(&root)) {
+        entry.push_str(&format!(" by={}", metadata_value(&by)));
+    }
+    entry.push_str(&format!(" on={}", today()));
+    append_ignore(&ignore_path, &entry)?;
+    println!("Added ignore entry to {}", ignore_path.display());
+    Ok(EXIT_CLEAN)
+}
+
+fn list(root: &Path, args: ListArgs) -> Result<i32> {
+    let ignore_path = args.file.unwrap_or_else(|| root.join(".nosecretsignore"));
+    let entries = read_ignore_file(&ignore_path)?;
+    let today = today();
+    let listed: Vec<ListedEntry<'_>> = entries
+        .iter()
+        .map(|entry| listed_entry(entry, &today))
+        .collect();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&listed)?);
+        return Ok(EXIT_CLEAN);
+    }
+    if listed.is_empty() {
+        println!("No ignore entries in {}", ignore_path.display());
+        return Ok(EXIT_CLEAN);
+    }
+    for entry in &listed {
+        print!("{}", entry.fingerprint);
+        if let Some(path) = entry.path {
+            print!(":{path}");
+        }
+        if entry.expired {
+            print!("  (expired)");
+        }
+        println!();
+        println!("  reason:  {}", entry.reason.unwrap_or("-"));
+        println!(
+            "  added:   {} by {}",
+            entry.on.unwrap_or("-"),
+            entry.by.unwrap_or("-")
+        );
+        if let Some(expires) = entry.expires {
+            println!("  expires: {expires}");
+        }
+    }
+    let without_reason = listed.iter().filter(|entry| entry.reason.is_none()).count();
+    println!(
+        "{} entr{}, {} without a reason",
+        listed.len(),
+        if listed.len() == 1 { "y" } else { "ies" },
+        without_reason
+    );
+    Ok(EXIT_CLEAN)
+}
+
+// Scans the repository with suppressed findings kept; a fingerprint entry
+// is stale when no finding it could match is left. `rule:` entries are only
+// merged and sorted.
+fn prune(root: &Path, args: PruneArgs) -> Result<i32> {
+    let ignore_path = args.file.unwrap_or_else(|| root.join(".nosecretsignore"));
+    let entries = read_ignore_file(&ignore_path)?;
+    if entries.is_empty() {
+        println!("No ignore entries in {}", ignore_path.display());
+        return Ok(EXIT_CLEAN);
+    }
+    let config = Config::load(root)?;
+    let walk_options = WalkOptions::from_config(config.as_ref());
+    let detector = build_detector(root, config)?;
+    // Entries are only stale if every finding was looked for.
+    let options = ScanOptions {
+        keep_suppressed: true,
+        max_findings_per_file: None,
+        file_timeout: None,
+        max_files: None,
+        ..detector.options().clone()
+    };
+    let detector = detector.with_options(options);
+    let files = collect_files(root, &[root.to_path_buf()], &walk_options)?;
+    let outcome = detector.scan_paths(root, &files);
+    if !outcome.failures.is_empty() || !outcome.warnings.is_empty() {
+        for failure in &outcome.failures {
+            eprintln!("{}: {}", failure.path.display(), failure.error);
+        }
+        for warning in &outcome.warnings {
+            match &warning.path {
+                Some(path) => eprintln!("{path}: {}", warning.message),
+                None => eprintln!("{}", warning.message),
+            }
+        }
+        eprintln!(
+            "nosecrets: {} left unchanged, not every file was scanned completely",
+            ignore_path.display()
+        );
+        return Ok(outcome
+            .failures
+            .first()
+            .map_or(EXIT_ERROR, |failure| exit_code_for(failure.error.kind())));
+    }
+
+    let pruned = prune_entries(entries, |entry| is_live(entry, &outcome.findings));
+    for entry in &pruned.removed {
+        println!("stale:  {}", ignore_line(entry));
+    }
+    for entry in &pruned.merged {
+        println!("merged: {}", ignore_line(entry));
+    }
+    let verb = if args.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    println!(
+        "{verb} {} stale entr{} and {} duplicate{}; {} left",
+        pruned.removed.len(),
+        if pruned.removed.len() == 1 {
+            "y"
+        } else {
+            "ies"
+        },
+        pruned.merged.len(),
+        if pruned.merged.len() == 1 { "" } else { "s" },
+        pruned.kept.len()
+    );
+    if args.dry_run {
+        return Ok(EXIT_CLEAN);
+    }
+    // Whole-line comments are kept at the top; entries follow, sorted.
+    let content = fs::read_to_string(&ignore_path)
+        .with_context(|| format!("failed to read {}", ignore_path.display()))?;
+    let mut out = String::new();
+    for line in content
+        .lines()
+        .filter(|line| line.trim_start().starts_with('#'))
+    {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for entry in &pruned.kept {
+        out.push_str(&ignore_line(entry));
+        out.push('\n');
+    }
+    fs::write(&ignore_path, out)
+        .with_context(|| format!("failed to write {}", ignore_path.display()))?;
+    Ok(EXIT_CLEAN)
+}
+
+fn is_live(entry: &IgnoreEntry, findings: &[Finding]) -> bool {
+    entry.rule.is_some()
+        || findings.iter().any(|finding| {
+            finding.fingerprint == entry.fingerprint && entry.matches_path(&finding.path)
+        })
+}
+
+#[derive(Debug, Default)]
+struct Pruned {
+    kept: Vec<IgnoreEntry>,
+    removed: Vec<IgnoreEntry>,
+    // Duplicates folded into an earlier entry for the same target and path.
+    merged: Vec<IgnoreEntry>,
+}
+
+// A merged entry keeps the first reason, author and date, adds differing
+// reasons, and expires with the longest-lived duplicate.
+fn prune_entries(entries: Vec<IgnoreEntry>, is_live: impl Fn(&IgnoreEntry) -> bool) -> Pruned {
+    let mut pruned = Pruned::default();
+    for entry in entries {
+        if !is_live(&entry) {
+            pruned.removed.push(entry);
+            continue;
+        }
+        let Some(kept) = pruned
+            .kept
+            .iter_mut()
+            .find(|kept| kept.fingerprint == entry.fingerprint && kept.path == entry.path)
+        else {
+            pruned.kept.push(entry);
+            continue;
+        };
+        match (&mut kept.reason, &entry.reason) {
+            (Some(reason), Some(other)) if !reason.contains(other.as_str()) => {
+                reason.push_str("; ");
+                reason.push_str(other);
+            }
+            (reason @ None, Some(other)) => *reason = Some(other.clone()),
+            _ => {}
+        }
+        if kept.by.is_none() && kept.on.is_none() {
+            kept.by.clone_from(&entry.by);
+            kept.on.clone_from(&entry.on);
+        }
+        kept.expires = match (kept.expires.take(), &entry.expires) {
+            (Some(date), Some(other)) => Some(date.max(other.clone())),
+            _ => None,
+        };
+        pruned.merged.push(entry);
+    }
+    pruned.kept.sort_by(|a, b| {
+        (a.rule.is_none(), &a.fingerprint, &a.path).cmp(&(
+            b.rule.is_none(),
+            &b.fingerprint,
+            &b.path,
+        ))
+    });
+    pruned
+}
+
+fn listed_entry<'a>(entry: &'a IgnoreEntry, today: &str) -> ListedEntry<'a> {
+    ListedEntry {
+        fingerprint: &entry.fingerprint,
+        path: entry.path.as_deref(),
+        reason: entry.reason.as_deref(),
+        by: entry.by.as_deref(),
+        on: entry.on.as_deref(),
+        expires: entry.expires.as_deref(),
+        expired: entry.is_expired(today),
+    }
+}
+
+// An entry as a .nosecretsignore line, metadata included.
+pub(crate) fn ignore_line(entry: &IgnoreEntry) -> String {
+    let mut line = entry.fingerprint.clone();
+    if let Some(path) = &entry.path {
+        line.push(':');
+        line.push_str(path);
+    }
+    let metadata = [
+        ("reason", &entry.reason),
+        ("by", &entry.by),
+        ("on", &entry.on),
+        ("expires", &entry.expires),
+    ];
+    let mut separator = " #";
+    for (key, value) in metadata {
+        if let Some(value) = value {
+            line.push_str(&format!("{separator} {key}={}", metadata_value(value)));
+            separator = "";
+        }
+    }
+    line
+}
+
+// Quotes values that would not survive `parse_metadata` unquoted.
+pub(crate) fn metadata_value(value: &str) -> String {
+    let value = value.replace('"', "'");
+    if value.contains(char::is_whitespace) || value.contains('=') || value.is_empty() {
+        format!("\"{value}\"")
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        fingerprint: &str,
+        path: Option<&str>,
+        reason: &str,
+        expires: Option<&str>,
+    ) -> IgnoreEntry {
+        IgnoreEntry {
+            fingerprint: fingerprint.to_string(),
+            rule: fingerprint
+                .strip_prefix(RULE_ENTRY_PREFIX)
+                .map(str::to_string),
+            path: path.map(str::to_string),
+            matcher: None,
+            reason: Some(reason.to_string()),
+            by: None,
+            on: None,
+            expires: expires.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn prune_drops_stale_entries_and_merges_duplicates() {
+        let entries = vec![
+            entry("nsi_bbbbbbbbbbbb", None, "fixture", Some("2030-01-01")),
+            entry("nsi_cccccccccccc", None, "gone", None),
+            entry("rule:generic-api-key", Some("tests/**"), "tests", None),
+            entry("nsi_aaaaaaaaaaaa", None, "docs", None),
+            entry("nsi_bbbbbbbbbbbb", None, "fixture", Some("2031-01-01")),
+            entry("nsi_bbbbbbbbbbbb", Some("a.py"), "scoped", None),
+        ];
+        let pruned = prune_entries(entries, |entry| entry.fingerprint != "nsi_cccccccccccc");
+        assert_eq!(pruned.removed.len(), 1);
+        assert_eq!(pruned.merged.len(), 1);
+        let kept: Vec<String> = pruned.kept.iter().map(ignore_line).collect();
+        assert_eq!(
+            kept,
+            [
+                "rule:generic-api-key:tests/** # reason=tests",
+                "nsi_aaaaaaaaaaaa # reason=docs",
+                "nsi_bbbbbbbbbbbb # reason=fixture expires=2031-01-01",
+                "nsi_bbbbbbbbbbbb:a.py # reason=scoped",
+            ]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+
+use nosecrets_core::discover_repo_root;
+
+use crate::EXIT_CLEAN;
+
+const HOOK_COMMAND: &str = "nosecrets scan --staged";
+
+const CONFIG_TEMPLATE: &str = r#"# nosecrets configuration; every key is described at
+# https://github.com/casoon/nosecrets#configuration
+
+[ignore]
+# Globs of paths that are never scanned
+paths = [
+  "vendor/",
+  "node_modules/",
+  "*.min.js",
+]
+
+[allow]
+# Values that are never reported, e.g. documented test keys
+values = []
+
+[scan]
+max_file_size = 5242880 # skip files above 5 MB
+
+[hook]
+education = true        # explain the first blocked commit once per user
+"#;
+
+const IGNORE_TEMPLATE: &str = "\
+# Findings nosecrets does not report, one per line: nsi_<hash> or
+# nsi_<hash>:<path-glob>, or rule:<rule-id>[:<path-glob>] for a whole rule.
+# `nosecrets ignore` and `nosecrets scan --interactive` add entries here.
+";
+
+#[derive(Parser, Debug)]
+pub(crate) struct InitArgs {
+    /// Create .nosecrets.toml with common defaults and an empty .nosecretsignore
+    #[arg(long)]
+    config: bool,
+    /// Generate the hook configuration for this hook manager
+    #[arg(long, value_enum)]
+    hook_manager: Option<HookManager>,
+    /// Print the configuration instead of writing it
+    #[arg(long)]
+    print: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum HookManager {
+    /// .pre-commit-config.yaml (pre-commit.com)
+    PreCommit,
+    /// lefthook.yml
+    Lefthook,
+    /// .husky/pre-commit
+    Husky,
+    /// .git/hooks/pre-commit without a hook manager
+    Git,
+}
+
+// How the generated stanza is merged into an existing file.
+enum Merge {
+    // The file is only written when it does not exist yet.
+    Create,
+    // Appended when the existing file has no `key` at the top level.
+    AppendWithoutKey(&'static str),
+    // Appended below the existing `key` list when it is the last top-level key.
+    AppendToList(&'static str),
+    // Appended as a shell command.
+    AppendLine,
+}
+
+struct HookConfig {
+    path: PathBuf,
+    new_file: String,
+    stanza: String,
+    merge: Merge,
+    executable: bool,
+}
+
+pub(crate) fn run_init(args: InitArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
+    // Without flags a terminal user is asked what to set up.
+    let (config, manager) = if args.config || args.hook_manager.is_some() {
+        (args.config, args.hook_manager)
+    } else if io::stdin().is_terminal() && !args.print {
+        ask(&root)?
+    } else {
+        bail!("nothing to initialize; pass --config and/or --hook-manager pre-commit|lefthook|husky|git");
+    };
+    let files = if config {
+        scaffold_files(&root)
+    } else {
+        Vec::new()
+    };
+    let hook = manager.map(|manager| hook_config(&root, manager));
+    if args.print {
+        for (_, content) in &files {
+            print!("{content}");
+        }
+        if let Some(hook) = &hook {
+            print!("{}", hook.new_file);
+        }
+        return Ok(EXIT_CLEAN);
+    }
+    for (path, content) in &files {
+        create(path, content)?;
+    }
+    if let Some(hook) = &hook {
+        install(hook)?;
+    }
+    Ok(EXIT_CLEAN)
+}
+
+fn ask(root: &Path) -> Result<(bool, Option<HookManager>)> {
+    let config = prompt("Create .nosecrets.toml and .nosecretsignore? [Y/n]")?;
+    let config = !matches!(config.as_str(), "n" | "no");
+    let detected = detect_hook_manager(root);
+    let default = detected.to_possible_value().expect("no skipped variants");
+    loop {
+        let answer = prompt(&format!(
+            "Install a pre-commit hook with pre-commit, lefthook, husky, git or none? [{}]",
+            default.get_name()
+        ))?;
+        match answer.as_str() {
+            "" => return Ok((config, Some(detected))),
+            "none" | "n" | "no" => return Ok((config, None)),
+            answer => match HookManager::from_str(answer, true) {
+                Ok(manager) => return Ok((config, Some(manager))),
+                Err(_) => println!("unknown hook manager {answer}"),
+            },
+        }
+    }
+}
+
+fn prompt(question: &str) -> Result<String> {
+    print!("{question} ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase())
+}
+
+// The hook manager the repository already uses; plain git hooks otherwise.
+fn detect_hook_manager(root: &Path) -> HookManager {
+    if root.join(".pre-commit-config.yaml").exists() {
+        HookManager::PreCommit
+    } else if root.join("lefthook.yml").exists() {
+        HookManager::Lefthook
+    } else if root.join(".husky").is_dir() {
+        HookManager::Husky
+    } else {
+        HookManager::Git
+    }
+}
+
+fn scaffold_files(root: &Path) -> Vec<(PathBuf, &'static str)> {
+    vec![
+        (root.join(".nosecrets.toml"), CONFIG_TEMPLATE),
+        (root.join(".nosecretsignore"), IGNORE_TEMPLATE),
+    ]
+}
+
+// Existing files are left alone: they may already hold ignore entries.
+fn create(path: &Path, content: &str) -> Result<()> {
+    if path.exists() {
+        println!("{} exists, left unchanged", path.display());
+        return Ok(());
+    }
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn hook_config(root: &Path, manager: HookManager) -> HookConfig {
+    match manager {
+        // pass_filenames is off: nosecrets reads the staged blobs itself, and
+        // file arguments would scan the working tree instead.
+        HookManager::PreCommit => {
+            let hook = format!(
+                "  - repo: local\n    hooks:\n      - id: nosecrets\n        name: nosecrets\n        \
+                 entry: {HOOK_COMMAND}\n        language: system\n        pass_filenames: false\n        \
+                 stages: [pre-commit]\n"
+            );
+            HookConfig {
+                path: root.join(".pre-commit-config.yaml"),
+                new_file: format!("repos:\n{hook}"),
+                stanza: hook,
+                merge: Merge::AppendToList("repos"),
+                executable: false,
+            }
+        }
+        HookManager::Lefthook => {
+            let hook =
+                format!("pre-commit:\n  commands:\n    nosecrets:\n      run: {HOOK_COMMAND}\n");
+            HookConfig {
+                path: root.join("lefthook.yml"),
+                new_file: hook.clone(),
+                stanza: hook,
+                merge: Merge::AppendWithoutKey("pre-commit"),
+                executable: false,
+            }
+        }
+        HookManager::Husky => HookConfig {
+            path: root.join(".husky/pre-commit"),
+            new_file: format!("{HOOK_COMMAND}\n"),
+            stanza: format!("{HOOK_COMMAND}\n"),
+            merge: Merge::AppendLine,
+            executable: true,
+        },
+        HookManager::Git => HookConfig {
+            path: root.join(".git/hooks/pre-commit"),
+            new_file: format!("#!/bin/sh\nexec {HOOK_COMMAND}\n"),
+            stanza: String::new(),
+            merge: Merge::Create,
+            executable: true,
+        },
+    }
+}
+
+fn install(config: &HookConfig) -> Result<()> {
+    let path = &config.path;
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        fs::write(path, &config.new_file)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        make_executable(path, config.executable)?;
+        println!("Wrote {}", path.display());
+        return Ok(());
+    }
+    let existing =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    if existing.contains(HOOK_COMMAND) {
+        println!("{} already runs nosecrets", path.display());
+        return Ok(());
+    }
+    let mergeable = match config.merge {
+        Merge::Create => false,
+        Merge::AppendWithoutKey(key) => top_level_keys(&existing).all(|line| line != key),
+        Merge::AppendToList(key) => top_level_keys(&existing).last() == Some(key),
+        Merge::AppendLine => true,
+    };
+    if !mergeable {
+        println!(
+            "{} exists and cannot be updated automatically; add this:\n\n{}",
+            path.display(),
+            if config.stanza.is_empty() {
+                &config.new_file
+            } else {
+                &config.stanza
+            }
+        );
+        return Ok(());
+    }
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&config.stanza);
+    fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))?;
+    println!("Updated {}", path.display());
+    Ok(())
+}
+
+// Keys of a YAML mapping at column zero, e.g. `repos` for `repos:`.
+fn top_level_keys(yaml: &str) -> impl Iterator<Item = &str> {
+    yaml.lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-']))
+        .filter_map(|line| line.split_once(':').map(|(key, _)| key.trim()))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    if executable {
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("failed to make {} executable", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn merges_into_existing_hook_manager_configs() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+
+        let pre_commit = hook_config(root, HookManager::PreCommit);
+        fs::write(
+            &pre_commit.path,
+            "repos:\n  - repo: https://github.com/pre-commit/pre-commit-hooks\n    rev: v4.6.0\n    hooks:\n      - id: trailing-whitespace\n",
+        )
+        .expect("write");
+        install(&pre_commit).expect("install");
+        let content = fs::read_to_string(&pre_commit.path).expect("read");
+        assert!(content.ends_with("      - id: nosecrets\n        name: nosecrets\n        entry: nosecrets scan --staged\n        language: system\n        pass_filenames: false\n        stages: [pre-commit]\n"));
+        install(&pre_commit).expect("install twice");
+        assert_eq!(fs::read_to_string(&pre_commit.path).expect("read"), content);
+
+        let lefthook = hook_config(root, HookManager::Lefthook);
+        let existing = "pre-commit:\n  commands:\n    lint:\n      run: npm run lint\n";
+        fs::write(&lefthook.path, existing).expect("write");
+        install(&lefthook).expect("install");
+        assert_eq!(fs::read_to_string(&lefthook.path).expect("read"), existing);
+
+        let husky = hook_config(root, HookManager::Husky);
+        fs::create_dir_all(root.join(".husky")).expect("create .husky");
+        fs::write(&husky.path, "npx lint-staged").expect("write");
+        install(&husky).expect("install");
+        assert_eq!(
+            fs::read_to_string(&husky.path).expect("read"),
+            "npx lint-staged\nnosecrets scan --staged\n"
+        );
+    }
+
+    #[test]
+    fn scaffolds_config_without_overwriting() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for (path, content) in scaffold_files(root) {
+            create(&path, content).expect("create");
+        }
+        let config = nosecrets_filter::Config::load_from_dir(root)
+            .expect("valid config")
+            .expect("config");
+        assert!(config.ignore.paths.contains(&"node_modules/".to_string()));
+
+        let ignore = root.join(".nosecretsignore");
+        fs::write(&ignore, "nsi_a1b2c3d4e5f6\n").expect("write");
+        create(&ignore, IGNORE_TEMPLATE).expect("create twice");
+        assert_eq!(
+            fs::read_to_string(&ignore).expect("read"),
+            "nsi_a1b2c3d4e5f6\n"
+        );
+
+        assert_eq!(detect_hook_manager(root), HookManager::Git);
+        fs::create_dir(root.join(".husky")).expect("create .husky");
+        assert_eq!(detect_hook_manager(root), HookManager::Husky);
+    }
+}
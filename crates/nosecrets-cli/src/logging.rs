@@ -0,0 +1,203 @@
+use std::fmt;
+use std::io;
+
+use clap::{Args, ValueEnum};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+// Overrides the level chosen by -q/-v, e.g. `NOSECRETS_LOG=nosecrets_core=debug`.
+const LOG_ENV: &str = "NOSECRETS_LOG";
+
+#[derive(Args, Debug, Clone, Copy)]
+pub(crate) struct LogArgs {
+    /// Only print findings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub(crate) quiet: bool,
+    /// Log per-file progress (-v) and rule timings (-vv) to stderr
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Format of log lines on stderr
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+pub(crate) fn init(args: LogArgs) {
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    // -v and -vv only raise the level of nosecrets' own crates.
+    let dependencies = if args.quiet { "error" } else { "warn" };
+    let filter = EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| {
+        EnvFilter::new(format!(
+            "{dependencies},nosecrets={}",
+            level.as_str().to_ascii_lowercase()
+        ))
+    });
+    // Fails only if a subscriber is already set, e.g. in tests.
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .event_format(EventFormat {
+            json: args.log_format == LogFormat::Json,
+        })
+        .try_init();
+}
+
+struct EventFormat {
+    json: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for EventFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let level = level_name(*event.metadata().level());
+        if self.json {
+            let mut timestamp = String::new();
+            SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+            let mut record = Map::new();
+            record.insert("timestamp".to_string(), Value::String(timestamp));
+            record.insert("level".to_string(), Value::String(level.to_string()));
+            record.insert("message".to_string(), Value::String(fields.message));
+            record.extend(fields.values);
+            return writeln!(writer, "{}", Value::Object(record));
+        }
+        write!(writer, "nosecrets: {level}: {}", fields.message)?;
+        for (name, value) in &fields.values {
+            match value {
+                Value::String(text) => write!(writer, " {name}={text}")?,
+                other => write!(writer, " {name}={other}")?,
+            }
+        }
+        writeln!(writer)
+    }
+}
+
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::ERROR => "error",
+        Level::WARN => "warning",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    values: Vec<(String, Value)>,
+}
+
+impl Fields {
+    fn insert(&mut self, field: &Field, value: Value) {
+        self.values.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.insert(field, Value::String(format!("{value:?}")));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.insert(field, Value::String(value.to_string()));
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().expect("buffer").extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(json: bool) -> String {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .event_format(EventFormat { json })
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(path = "a.env", bytes = 42u64, "skipped");
+        });
+        let bytes = buffer.0.lock().expect("buffer").clone();
+        String::from_utf8(bytes).expect("utf8")
+    }
+
+    #[test]
+    fn formats_events_as_text_and_json() {
+        assert_eq!(
+            capture(false),
+            "nosecrets: warning: skipped path=a.env bytes=42\n"
+        );
+        let record: Value = serde_json::from_str(&capture(true)).expect("json");
+        assert_eq!(record["level"], "warning");
+        assert_eq!(record["message"], "skipped");
+        assert_eq!(record["path"], "a.env");
+        assert_eq!(record["bytes"], 42);
+        assert!(record["timestamp"].is_string());
+    }
+}
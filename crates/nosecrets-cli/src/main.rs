@@ -1,28 +1,104 @@
-use std::fs::OpenOptions;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 
-use nosecrets_core::{collect_files, collect_staged_files, discover_repo_root, Detector};
-use nosecrets_filter::{load_ignore_file, normalize_path, Config, Filter};
-use nosecrets_report::Report;
-use nosecrets_rules::load_builtin_rules;
+use nosecrets_core::{
+    blame_lines, collect_files, collect_staged_files, diff_added_lines, discover_packages,
+    discover_repo_root, error_message, head_commit, package_for, ChunkOptions, CodeOwners,
+    Detector, ErrorKind, GitError, Package, SampleOptions, ScanCache, ScanOptions, WalkOptions,
+    CACHE_FILE,
+};
+use nosecrets_filter::{
+    normalize_path, read_ignore_file, timestamp, Config, Filter, FilterError, IgnoreEntry, Policy,
+    ReportFormat, SymlinkPolicy, RULE_ENTRY_PREFIX,
+};
+use nosecrets_report::{
+    append_history, location, ErrorRecord, Finding, FingerprintDisplay, GroupBy, HistoryRecord,
+    MaskStyle, MetricsFormat, NdjsonRecord, Report, ReportError, SortKey, Template,
+    TerminalOptions, DEFAULT_COMMENT_LIMIT, HISTORY_FILE,
+};
+use nosecrets_rules::{load_builtin_rules, Severity};
+
+mod audit;
+mod baseline;
+mod diff;
+mod education;
+mod exception;
+mod explain;
+mod fix;
+mod ignore;
+mod init;
+mod logging;
+mod lsp;
+mod manifest;
+mod paths;
+mod pre_receive;
+mod rules;
+mod serve;
+mod trends;
+
+use audit::{run_audit, AuditArgs};
+use baseline::{run_baseline, BaselineArgs};
+use diff::{run_diff, DiffArgs};
+use exception::{run_exception, ExceptionArgs};
+use explain::{run_explain, ExplainArgs};
+use fix::{run_fix, FixArgs};
+use ignore::{run_ignore, IgnoreArgs};
+use init::{run_init, InitArgs};
+use logging::LogArgs;
+use lsp::run_lsp;
+use manifest::{build_manifest, verify_manifest};
+use paths::{hook_files, read_file_list, resolve_targets, scan_stdin};
+use pre_receive::{run_pre_receive, PreReceiveArgs};
+use rules::{run_rules, RulesArgs};
+use serve::{run_serve, ServeArgs};
+use trends::{run_trends, TrendsArgs};
 
 #[derive(Parser, Debug)]
 #[command(name = "nosecrets", version, about = "Fast offline secret scanner")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Scan files or staged changes
     Scan(ScanArgs),
-    /// Add an ignore entry to .nosecretsignore
+    /// Add or list entries in .nosecretsignore
     Ignore(IgnoreArgs),
+    /// Replace detected secrets in files with placeholders
+    Fix(FixArgs),
+    /// Run a Language Server Protocol server on stdio
+    Lsp,
+    /// Serve scans over a JSON-RPC API on a TCP address or Unix socket
+    Serve(ServeArgs),
+    /// Request and apply reviewed, expiring exceptions for findings
+    Exception(ExceptionArgs),
+    /// Inspect and test detection rules
+    Rules(RulesArgs),
+    /// Set up nosecrets as a git hook
+    Init(InitArgs),
+    /// Import or export detect-secrets baselines
+    Baseline(BaselineArgs),
+    /// Compare two JSON reports: new, resolved and persisting findings
+    Diff(DiffArgs),
+    /// Scan pushed commits in a server-side pre-receive hook
+    PreReceive(PreReceiveArgs),
+    /// Show where a fingerprint appears and why it is or is not reported
+    Explain(ExplainArgs),
+    /// Show finding counts over the scans recorded with `scan --record`
+    Trends(TrendsArgs),
+    /// List inline markers, ignore entries and allow settings, and whether each still suppresses a finding
+    Audit(AuditArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -30,111 +106,951 @@ struct ScanArgs {
     /// Scan staged files
     #[arg(long)]
     staged: bool,
+    /// Scan exactly the files given as arguments, as a hook manager passes them; missing files are skipped without a warning
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with_all = ["staged", "commits", "stash", "rev", "files_from", "files_from0"])]
+    hook_mode: Option<HookModeArg>,
+    /// Scan only lines added in a commit range (e.g. origin/main..HEAD)
+    #[arg(long, value_name = "RANGE", conflicts_with = "staged")]
+    commits: Option<String>,
+    /// Also scan the commit messages, tag annotations and git notes of the --commits range
+    #[arg(long, requires = "commits")]
+    messages: bool,
+    /// Scan changes saved with git stash, including untracked files stashed with -u
+    #[arg(long, conflicts_with_all = ["staged", "commits", "paths", "packages"])]
+    stash: bool,
+    /// Scan the tree of a commit, tag or branch without checking it out (e.g. v1.2.0)
+    #[arg(
+        long,
+        value_name = "REV",
+        conflicts_with_all = ["staged", "commits", "stash", "paths", "packages", "files_from", "files_from0"]
+    )]
+    rev: Option<String>,
+    /// Also scan files excluded by .gitignore, e.g. local .env files
+    #[arg(long, conflicts_with_all = ["staged", "commits", "stash"])]
+    include_ignored: bool,
+    /// Also scan git submodules; other nested repositories are always skipped
+    #[arg(long, conflicts_with_all = ["staged", "commits", "stash"])]
+    recurse_submodules: bool,
+    /// Walk the filesystem inside a git repository instead of scanning only the files git tracks or has staged
+    #[arg(long, conflicts_with_all = ["staged", "commits", "stash", "rev"])]
+    all_files: bool,
+    /// Only scan this workspace package (Cargo, npm/yarn/pnpm or go.work); repeatable
+    #[arg(long = "package", value_name = "NAME")]
+    packages: Vec<String>,
     /// Ask to ignore findings interactively
     #[arg(long)]
     interactive: bool,
-    /// Show findings but always exit with code 0
+    /// Lowest severity that fails the scan with exit code 1 [default: medium, or fail_on from .nosecrets.policy.toml]
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    fail_on: Option<SeverityArg>,
+    /// Drop findings scoring below this confidence (0 to 1)
+    #[arg(long, value_name = "SCORE", value_parser = parse_confidence)]
+    min_confidence: Option<f64>,
+    /// Run only rules at or above --fail-on and stop scanning a file at its first blocking finding, e.g. in a pre-commit hook
+    #[arg(long)]
+    fast: bool,
+    /// Show findings but exit with code 0 even when secrets are found
     #[arg(long)]
     dry_run: bool,
-    /// Output format
-    #[arg(long, value_enum, default_value = "text")]
-    format: OutputFormat,
-    /// Files or directories to scan
+    /// Print a scan summary to stderr (JSON when --format json)
+    #[arg(long)]
+    summary: bool,
+    /// Print per-rule timings, prefilter hit rates and the slowest files to stderr
+    #[arg(long)]
+    stats: bool,
+    /// Output format [default: text, or report.format from the config]
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Write the report in --format to this file and show text output and a summary in the terminal [default: report.output from the config]
+    #[arg(long, short = 'o', value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Write aggregate counts (findings per rule and severity, files, duration) for dashboards, without paths or secrets
+    #[arg(long, value_name = "FILE")]
+    metrics: Option<PathBuf>,
+    /// Format of the --metrics file
+    #[arg(long, value_enum, default_value = "prometheus", requires = "metrics")]
+    metrics_format: MetricsFormatArg,
+    /// How to show fingerprints in text output
+    #[arg(long, value_enum, default_value = "full")]
+    fingerprints: FingerprintsArg,
+    /// Group text output under a header per file, rule, severity or owner
+    #[arg(long, value_enum)]
+    group_by: Option<GroupByArg>,
+    /// Order of findings (default: path and line)
+    #[arg(long, value_enum, default_value = "path")]
+    sort: SortArg,
+    /// Print a `nosecrets ignore` command for each finding
+    #[arg(long)]
+    print_ignore_commands: bool,
+    /// List suppressed findings in JSON and NDJSON output with the reason in `suppressed` (e.g. inline-ignore, allow-pattern, baseline); they never fail the scan
+    #[arg(long)]
+    show_suppressed: bool,
+    /// Show the commit, author and date that last changed each finding's line
+    #[arg(long)]
+    blame: bool,
+    /// Scan with at most this many threads (default: one per core)
+    #[arg(long, short = 'j', value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+    /// Run at lower CPU priority on half the cores, e.g. in a pre-commit hook
+    #[arg(long)]
+    low_priority: bool,
+    /// Scan every staged file again instead of reusing the findings of blobs an earlier --staged run scanned
+    #[arg(long, requires = "staged")]
+    no_cache: bool,
+    /// Show secrets unmasked in the text report, for local use
+    #[arg(long)]
+    reveal: bool,
+    /// Show secrets unmasked in every format, including JSON, CSV and Markdown
+    #[arg(long)]
+    unsafe_reveal: bool,
+    /// Maximum size of Markdown output; lowest-severity groups are dropped first
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_COMMENT_LIMIT)]
+    comment_limit: usize,
+    /// Skip files larger than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    max_file_size: Option<u64>,
+    /// What to do with symbolic links: skip them, follow them, or report those pointing outside the repository
+    #[arg(long, value_enum, value_name = "POLICY")]
+    symlinks: Option<SymlinksArg>,
+    /// Follow symbolic links while walking directories; same as `--symlinks follow`
+    #[arg(long, conflicts_with = "symlinks")]
+    follow_symlinks: bool,
+    /// Scan sampled windows of files above the size limit instead of skipping them
+    #[arg(long)]
+    sample_large_files: bool,
+    /// Write a manifest of everything that affects the results to this file
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Warn when any input differs from a previously written manifest
+    #[arg(long, value_name = "PATH")]
+    verify_manifest: Option<PathBuf>,
+    /// Append the summary and finding fingerprints to .nosecrets/history.jsonl
+    #[arg(long)]
+    record: bool,
+    /// Also scan the files listed in FILE, one per line; `-` reads the list from stdin
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["staged", "commits", "stash", "files_from0"])]
+    files_from: Option<PathBuf>,
+    /// Like --files-from with NUL-separated names, e.g. from `git ls-files -z` or `fd -0`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["staged", "commits", "stash"])]
+    files_from0: Option<PathBuf>,
+    /// Files or directories to scan; `-` reads from stdin
     paths: Vec<PathBuf>,
 }
 
-#[derive(Parser, Debug)]
-struct IgnoreArgs {
-    /// Fingerprint to ignore (nsi_...)
-    fingerprint: String,
-    /// Optional path glob to scope the ignore
-    #[arg(long)]
-    path: Option<PathBuf>,
-    /// Override .nosecretsignore location
-    #[arg(long)]
-    file: Option<PathBuf>,
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum HookModeArg {
+    /// The pre-commit framework, with `pass_filenames: true`
+    PreCommit,
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
 enum OutputFormat {
     Text,
     Json,
+    /// Collapsible Markdown for pull request comments
+    Markdown,
+    /// One comma-separated row per finding
+    Csv,
+    /// One tab-separated row per finding
+    Tsv,
+    /// One JSON object per line as findings are found, then a summary
+    Ndjson,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SeverityArg {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl From<SeverityArg> for Severity {
+    fn from(arg: SeverityArg) -> Self {
+        match arg {
+            SeverityArg::Critical => Severity::Critical,
+            SeverityArg::High => Severity::High,
+            SeverityArg::Medium => Severity::Medium,
+            SeverityArg::Low => Severity::Low,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SymlinksArg {
+    Skip,
+    Follow,
+    Report,
+}
+
+impl From<SymlinksArg> for SymlinkPolicy {
+    fn from(arg: SymlinksArg) -> Self {
+        match arg {
+            SymlinksArg::Skip => SymlinkPolicy::Skip,
+            SymlinksArg::Follow => SymlinkPolicy::Follow,
+            SymlinksArg::Report => SymlinkPolicy::Report,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum FingerprintsArg {
+    Full,
+    Short,
+    Hidden,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum GroupByArg {
+    File,
+    Rule,
+    Severity,
+    /// Owners from CODEOWNERS
+    Owner,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum MetricsFormatArg {
+    /// Prometheus text format, e.g. for the node_exporter textfile collector
+    Prometheus,
+    #[value(name = "openmetrics")]
+    OpenMetrics,
 }
 
-fn main() -> Result<()> {
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SortArg {
+    Path,
+    Severity,
+    Rule,
+    Confidence,
+}
+
+pub(crate) const EXIT_CLEAN: i32 = 0;
+pub(crate) const EXIT_ERROR: i32 = 2;
+const EXIT_CONFIG_ERROR: i32 = 3;
+const EXIT_RULES_ERROR: i32 = 4;
+const EXIT_GIT_ERROR: i32 = 5;
+const EXIT_REPORT_ERROR: i32 = 6;
+const EXIT_POLICY_ERROR: i32 = 7;
+const DEFAULT_SAMPLE_THRESHOLD: u64 = 10 * 1024 * 1024;
+
+fn main() {
     let cli = Cli::parse();
-    match cli.command {
-        Commands::Scan(args) => run_scan(args),
+    logging::init(cli.log);
+    let error_format = match &cli.command {
+        Commands::Scan(args) => args.format,
+        _ => None,
+    };
+    let result = match cli.command {
+        Commands::Scan(args) => run_scan(args, cli.log.quiet),
         Commands::Ignore(args) => run_ignore(args),
+        Commands::Fix(args) => run_fix(args),
+        Commands::Lsp => run_lsp(),
+        Commands::Serve(args) => run_serve(args),
+        Commands::Exception(args) => run_exception(args),
+        Commands::Rules(args) => run_rules(args),
+        Commands::Init(args) => run_init(args),
+        Commands::Baseline(args) => run_baseline(args),
+        Commands::Diff(args) => run_diff(args),
+        Commands::PreReceive(args) => run_pre_receive(args),
+        Commands::Explain(args) => run_explain(args),
+        Commands::Trends(args) => run_trends(args),
+        Commands::Audit(args) => run_audit(args),
+    };
+    let exit_code = match result {
+        Ok(code) => code,
+        Err(error) => {
+            let kind = ErrorKind::of(error.as_ref());
+            let record = ErrorRecord {
+                kind: kind.as_str().to_string(),
+                path: None,
+                message: error_message(error.as_ref()),
+            };
+            let printed = match error_format {
+                Some(OutputFormat::Json) => record.print_json().is_ok(),
+                Some(OutputFormat::Ndjson) => NdjsonRecord::Error(&record).print().is_ok(),
+                _ => false,
+            };
+            if !printed {
+                eprintln!("nosecrets: {}", record.message);
+            }
+            exit_code_for(kind)
+        }
+    };
+    std::process::exit(exit_code);
+}
+
+pub(crate) fn exit_code_for(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::Config => EXIT_CONFIG_ERROR,
+        ErrorKind::Rules => EXIT_RULES_ERROR,
+        ErrorKind::Git => EXIT_GIT_ERROR,
+        ErrorKind::Report => EXIT_REPORT_ERROR,
+        ErrorKind::Policy => EXIT_POLICY_ERROR,
+        ErrorKind::Io | ErrorKind::Other => EXIT_ERROR,
     }
 }
 
-fn run_scan(args: ScanArgs) -> Result<()> {
+fn run_scan(args: ScanArgs, quiet: bool) -> Result<i32> {
     let cwd = std::env::current_dir().context("failed to read current dir")?;
     let repo_root = discover_repo_root(&cwd)?;
-    let root = repo_root.clone().unwrap_or(cwd);
+    let root = repo_root.clone().unwrap_or_else(|| cwd.clone());
+    if args.blame && repo_root.is_none() {
+        return Err(GitError::RepositoryRequired("--blame").into());
+    }
 
-    let config = Config::load_from_dir(&root)?;
-    let ignore_entries = load_ignore_file(&root.join(".nosecretsignore"))?;
-    let filter = Filter::from_config(config, ignore_entries)?;
-    let rules = load_builtin_rules()?;
-    let detector = Detector::new(rules, filter)?;
+    let config = Config::load(&root)?;
+    let fail_on = Policy::fail_on(
+        Policy::load(&root)?.as_ref(),
+        args.fail_on.map(Severity::from),
+        args.dry_run,
+    )?;
+    // `[hook]` settings apply to --staged and --hook-mode runs.
+    let hook = args.staged || args.hook_mode.is_some();
+    let low_priority = args.low_priority
+        || (hook
+            && config
+                .as_ref()
+                .and_then(|config| config.hook.low_priority)
+                .unwrap_or(false));
+    if low_priority {
+        lower_priority();
+    }
+    let jobs = jobs(&args, config.as_ref(), low_priority);
+    let mut walk_options = walk_options(&args, config.as_ref());
+    walk_options.threads = jobs;
+    // Ignored files are untracked, so --include-ignored needs the walk too.
+    walk_options.tracked_only = repo_root.is_some() && !args.all_files && !args.include_ignored;
+    let mut scan_options = scan_options(&args, config.as_ref(), &mut walk_options)?;
+    scan_options.jobs = jobs;
+    let fast = args.fast
+        || (hook
+            && config
+                .as_ref()
+                .and_then(|config| config.hook.fast)
+                .unwrap_or(false));
+    if fast {
+        // Rules below fail_on cannot block, so only those are skipped.
+        scan_options.rule_severity = Some(fail_on);
+        scan_options.stop_at = Some(fail_on);
+    }
+    let education = config
+        .as_ref()
+        .and_then(|config| config.hook.education)
+        .unwrap_or(true);
+    let remediation = config
+        .as_ref()
+        .and_then(|config| config.hook.remediation)
+        .unwrap_or(true);
+    let hook_cache = config
+        .as_ref()
+        .and_then(|config| config.hook.cache)
+        .unwrap_or(true);
+    let format = args.format.unwrap_or_else(|| {
+        match config.as_ref().and_then(|config| config.report.format) {
+            Some(ReportFormat::Json) => OutputFormat::Json,
+            Some(ReportFormat::Markdown) => OutputFormat::Markdown,
+            Some(ReportFormat::Csv) => OutputFormat::Csv,
+            Some(ReportFormat::Tsv) => OutputFormat::Tsv,
+            Some(ReportFormat::Ndjson) => OutputFormat::Ndjson,
+            Some(ReportFormat::Text) | None => OutputFormat::Text,
+        }
+    });
+    if args.unsafe_reveal || (args.reveal && matches!(format, OutputFormat::Text)) {
+        scan_options.mask = MaskStyle::Reveal;
+    } else if args.reveal {
+        tracing::warn!(
+            "--reveal only applies to text output; pass --unsafe-reveal to reveal secrets in other formats"
+        );
+    }
+    if args.show_suppressed {
+        if matches!(format, OutputFormat::Json | OutputFormat::Ndjson) {
+            scan_options.keep_suppressed = true;
+        } else {
+            tracing::warn!("--show-suppressed only applies to JSON and NDJSON output");
+        }
+    }
+    // Relative `report.output` paths start at the repository root.
+    let output = args.output.as_ref().map(|path| cwd.join(path)).or_else(|| {
+        config
+            .as_ref()
+            .and_then(|config| config.report.output.as_ref())
+            .map(|path| root.join(path))
+    });
+    let template = report_template(&root, config.as_ref())?;
+    let detector = build_detector(&root, config)?.with_options(scan_options.clone());
+    let wants_manifest = args.manifest.is_some() || args.verify_manifest.is_some();
+    let packages = discover_packages(&root);
+    let scope = select_packages(&packages, &args.packages)?;
+    let in_scope = |path: &Path| scope.is_empty() || scope.iter().any(|p| p.contains(path));
+    let code_owners = CodeOwners::discover(&root);
+    let tag_finding = |finding: &mut Finding| {
+        if finding.message.is_some() {
+            return;
+        }
+        finding.package =
+            package_for(&packages, &root.join(&finding.path)).map(|package| package.name.clone());
+        if let Some(code_owners) = &code_owners {
+            finding.owners = code_owners.owners_for(&finding.path).to_vec();
+        }
+    };
+    // NDJSON findings are written while the scan runs unless they still have
+    // to be confirmed interactively or blamed, or go to a file.
+    let stream = matches!(format, OutputFormat::Ndjson)
+        && !args.interactive
+        && !args.blame
+        && output.is_none();
+    let mut streamed = false;
+    let cache = (args.staged && !args.no_cache && hook_cache)
+        .then(|| ScanCache::load(&root.join(CACHE_FILE), &detector.cache_key()));
 
-    let files = if args.staged {
+    let started = Instant::now();
+    let mut scanned_files = Vec::new();
+    let outcome = if args.stash {
+        let Some(repo_root) = repo_root else {
+            return Err(GitError::RepositoryRequired("--stash").into());
+        };
+        detector.scan_stash(&repo_root)?
+    } else if let Some(rev) = &args.rev {
         let Some(repo_root) = repo_root else {
-            return Err(anyhow::anyhow!("--staged requires a git repository"));
+            return Err(GitError::RepositoryRequired("--rev").into());
         };
-        collect_staged_files(&repo_root)?
+        detector.scan_rev(&repo_root, rev, &walk_options)?
+    } else if let Some(range) = &args.commits {
+        let Some(repo_root) = repo_root else {
+            return Err(GitError::RepositoryRequired("--commits").into());
+        };
+        if wants_manifest {
+            scanned_files = diff_added_lines(&repo_root, range)?
+                .into_iter()
+                .map(|added| repo_root.join(added.path))
+                .collect();
+        }
+        let mut outcome = detector.scan_commit_range(&repo_root, range)?;
+        outcome
+            .findings
+            .retain(|finding| in_scope(&repo_root.join(&finding.path)));
+        if args.messages {
+            outcome.extend(detector.scan_messages(&repo_root, range)?);
+        }
+        outcome
     } else {
-        collect_files(&root, &args.paths)?
+        let mut stdin = false;
+        let files = if args.staged {
+            let Some(repo_root) = repo_root else {
+                return Err(GitError::RepositoryRequired("--staged").into());
+            };
+            collect_staged_files(&repo_root)?
+        } else if args.hook_mode.is_some() {
+            hook_files(&cwd, &args.paths)
+        } else {
+            let mut targets = resolve_targets(&cwd, &root, &args.paths)?;
+            stdin = targets.stdin;
+            let file_list = match (&args.files_from, &args.files_from0) {
+                (Some(source), _) => Some((source, b'\n')),
+                (None, Some(source)) => Some((source, 0)),
+                (None, None) => None,
+            };
+            if let Some((source, separator)) = file_list {
+                if stdin && source.as_os_str() == "-" {
+                    bail!("stdin cannot hold both the file list and content to scan");
+                }
+                targets
+                    .paths
+                    .extend(read_file_list(&cwd, source, separator)?);
+            }
+            if targets.paths.is_empty() && !scope.is_empty() {
+                for package in &scope {
+                    targets.paths.push(package.dir.clone());
+                    targets.paths.extend(package.include.iter().cloned());
+                }
+            }
+            // An empty file list scans nothing rather than the repository.
+            if (stdin || file_list.is_some()) && targets.paths.is_empty() {
+                Vec::new()
+            } else {
+                collect_files(&root, &targets.paths, &walk_options)?
+            }
+        };
+        // A report from an earlier run is not scanned.
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|file| in_scope(file) && output.as_ref() != Some(file))
+            .collect();
+        let mut outcome = if let Some(mut cache) = cache {
+            let outcome = detector.scan_paths_cached(&root, &files, &mut cache);
+            if let Err(error) = cache.save() {
+                tracing::warn!("failed to save the scan cache: {}", error_message(&error));
+            }
+            outcome
+        } else if stream {
+            let found = Mutex::new(Vec::new());
+            let write_error = Mutex::new(None);
+            let mut outcome = detector.scan_paths_with(&root, &files, |mut finding| {
+                tag_finding(&mut finding);
+                if let Err(error) = NdjsonRecord::Finding(&finding).print() {
+                    write_error
+                        .lock()
+                        .expect("ndjson lock")
+                        .get_or_insert(error);
+                }
+                found.lock().expect("ndjson lock").push(finding);
+            });
+            if let Some(error) = write_error.into_inner().expect("ndjson lock") {
+                return Err(error.into());
+            }
+            outcome.findings = found.into_inner().expect("ndjson lock");
+            streamed = true;
+            outcome
+        } else {
+            detector.scan_paths(&root, &files)
+        };
+        if stdin {
+            outcome.findings.extend(scan_stdin(&detector)?);
+            outcome.files_scanned += 1;
+        }
+        scanned_files = files;
+        outcome
     };
-
-    let findings = detector.scan_files(&root, &files)?;
-    let findings = if args.interactive {
+    let duration = started.elapsed();
+    if wants_manifest {
+        let manifest = build_manifest(
+            &root,
+            &args,
+            fail_on,
+            &walk_options,
+            &scan_options,
+            &scanned_files,
+        )?;
+        if let Some(previous) = &args.verify_manifest {
+            verify_manifest(previous, &manifest)?;
+        }
+        if let Some(path) = &args.manifest {
+            manifest
+                .write(path)
+                .with_context(|| format!("failed to write manifest {}", path.display()))?;
+        }
+    }
+    for failure in &outcome.failures {
+        match format {
+            OutputFormat::Json | OutputFormat::Ndjson => ErrorRecord {
+                kind: failure.error.kind().as_str().to_string(),
+                path: Some(normalize_path(&failure.path)),
+                message: error_message(&failure.error),
+            }
+            .eprint_json()?,
+            _ => tracing::error!(
+                path = %failure.path.display(),
+                "failed to scan: {}",
+                error_message(&failure.error)
+            ),
+        }
+    }
+    let (mut suppressed, findings): (Vec<Finding>, Vec<Finding>) = outcome
+        .findings
+        .into_iter()
+        .partition(|finding| finding.suppressed.is_some());
+    let mut findings = if args.interactive {
         interactive_filter(&root, findings)?
     } else {
         findings
     };
+    findings.iter_mut().for_each(tag_finding);
+    suppressed.iter_mut().for_each(tag_finding);
+    if args.blame {
+        attach_blame(&root, &mut findings);
+    }
 
-    let report = Report::new(findings);
-    match args.format {
-        OutputFormat::Text => report.print_terminal()?,
-        OutputFormat::Json => report.print_json()?,
+    let mut report = Report::new(findings)
+        .with_suppressed(suppressed)
+        .with_fixture_findings(outcome.fixture_findings);
+    report.sort(match args.sort {
+        SortArg::Path => SortKey::Path,
+        SortArg::Severity => SortKey::Severity,
+        SortArg::Rule => SortKey::Rule,
+        SortArg::Confidence => SortKey::Confidence,
+    });
+    let terminal = TerminalOptions {
+        quiet,
+        fingerprints: match args.fingerprints {
+            FingerprintsArg::Full => FingerprintDisplay::Full,
+            FingerprintsArg::Short => FingerprintDisplay::Short,
+            FingerprintsArg::Hidden => FingerprintDisplay::Hidden,
+        },
+        group_by: args.group_by.map(|group_by| match group_by {
+            GroupByArg::File => GroupBy::File,
+            GroupByArg::Rule => GroupBy::Rule,
+            GroupByArg::Severity => GroupBy::Severity,
+            GroupByArg::Owner => GroupBy::Owner,
+        }),
+        template: Arc::new(template),
+    };
+    // With --output the report is collected here and the terminal shows text.
+    let mut report_file = Vec::new();
+    match &output {
+        Some(_) => {
+            write_report(
+                &report,
+                format,
+                &args,
+                terminal.clone(),
+                &mut report_file,
+                true,
+            )?;
+            report.print_terminal_with(terminal)?;
+        }
+        None if streamed => {}
+        None => write_report(&report, format, &args, terminal, &mut io::stdout(), false)?,
     }
-    let exit_code = if args.dry_run { 0 } else { report.exit_code() };
-    std::process::exit(exit_code);
+    let text_terminal = output.is_some() || matches!(format, OutputFormat::Text);
+    if args.print_ignore_commands {
+        print_ignore_commands(&report, text_terminal)?;
+    }
+    let blocked = !args.dry_run && report.exit_code_at(fail_on) != EXIT_CLEAN;
+    if blocked && hook && !quiet && text_terminal {
+        if remediation {
+            education::print_remediation(&report, fail_on);
+        }
+        if education {
+            education::explain_first_block(&report, fail_on);
+        }
+    }
+    if args.summary || output.is_some() || matches!(format, OutputFormat::Ndjson) {
+        let mut summary = report.summary(outcome.files_scanned, outcome.failures.len(), duration);
+        summary.skipped = outcome.skipped.clone();
+        summary.warnings = outcome.warnings.clone();
+        summary.fingerprint = scan_options.fingerprint.info();
+        match (format, &output) {
+            (OutputFormat::Ndjson, Some(_)) => {
+                NdjsonRecord::Summary(&summary).write(&mut report_file)?;
+                summary.print_text()?;
+            }
+            (_, Some(_)) => summary.print_text()?,
+            (OutputFormat::Json, None) => summary.print_json()?,
+            (OutputFormat::Ndjson, None) => NdjsonRecord::Summary(&summary).print()?,
+            _ => summary.print_text()?,
+        }
+    }
+    if let Some(path) = &output {
+        write_report_file(path, &report_file)?;
+    }
+    if let Some(path) = &args.metrics {
+        let summary = report.summary(outcome.files_scanned, outcome.failures.len(), duration);
+        let format = match args.metrics_format {
+            MetricsFormatArg::Prometheus => MetricsFormat::Prometheus,
+            MetricsFormatArg::OpenMetrics => MetricsFormat::OpenMetrics,
+        };
+        summary
+            .write_metrics(path, format)
+            .with_context(|| format!("failed to write metrics {}", path.display()))?;
+    }
+    if args.record {
+        let summary = report.summary(outcome.files_scanned, outcome.failures.len(), duration);
+        let record =
+            HistoryRecord::new(timestamp(), head_commit(&root), &summary, report.findings());
+        let path = root.join(HISTORY_FILE);
+        append_history(&path, &record)
+            .with_context(|| format!("failed to record scan in {}", path.display()))?;
+    }
+    if let Some(stats) = detector.stats() {
+        match format {
+            OutputFormat::Json | OutputFormat::Ndjson => stats.print_json()?,
+            _ => stats.print_text()?,
+        }
+    }
+    if let Some(failure) = outcome.failures.first() {
+        return Ok(exit_code_for(failure.error.kind()));
+    }
+    Ok(if args.dry_run {
+        EXIT_CLEAN
+    } else {
+        report.exit_code_at(fail_on)
+    })
 }
 
-fn run_ignore(args: IgnoreArgs) -> Result<()> {
-    let cwd = std::env::current_dir().context("failed to read current dir")?;
-    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
-    let ignore_path = args.file.unwrap_or_else(|| root.join(".nosecretsignore"));
-    let entry = if let Some(path) = args.path {
-        format!("{}:{}", args.fingerprint, normalize_path(&path))
+fn select_packages<'a>(packages: &'a [Package], names: &[String]) -> Result<Vec<&'a Package>> {
+    let mut selected = Vec::new();
+    for name in names {
+        let before = selected.len();
+        selected.extend(packages.iter().filter(|package| &package.name == name));
+        if selected.len() == before {
+            let mut known: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+            known.dedup();
+            bail!(
+                "unknown package {name}; workspace packages: {}",
+                if known.is_empty() {
+                    "none found".to_string()
+                } else {
+                    known.join(", ")
+                }
+            );
+        }
+    }
+    Ok(selected)
+}
+
+// Blames each file once for all its finding lines. Files git cannot blame,
+// such as untracked ones, keep findings without blame.
+fn attach_blame(repo_root: &Path, findings: &mut [Finding]) {
+    let mut lines: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    // Notebook findings count lines within a cell; commit messages have no
+    // file to blame.
+    let in_file = |finding: &Finding| finding.cell.is_none() && finding.message.is_none();
+    for finding in findings.iter().filter(|finding| in_file(finding)) {
+        lines
+            .entry(finding.path.clone())
+            .or_default()
+            .push(finding.line);
+    }
+    for (path, mut file_lines) in lines {
+        file_lines.sort_unstable();
+        file_lines.dedup();
+        let blames = match blame_lines(repo_root, Path::new(&path), &file_lines) {
+            Ok(blames) => blames,
+            Err(error) => {
+                tracing::debug!(path, "not blamed: {}", error_message(&error));
+                continue;
+            }
+        };
+        for finding in findings
+            .iter_mut()
+            .filter(|finding| finding.path == path && in_file(finding))
+        {
+            finding.blame = blames.get(&finding.line).cloned();
+        }
+    }
+}
+
+// Writes the report in `format`; `plain` leaves colors out of text output.
+fn write_report(
+    report: &Report,
+    format: OutputFormat,
+    args: &ScanArgs,
+    terminal: TerminalOptions,
+    out: &mut impl Write,
+    plain: bool,
+) -> Result<(), ReportError> {
+    match format {
+        OutputFormat::Text if plain => write!(out, "{}", report.render_terminal(terminal)?)?,
+        OutputFormat::Text => report.write_terminal(out, terminal)?,
+        OutputFormat::Json => report.write_json(out)?,
+        OutputFormat::Markdown => write!(out, "{}", report.render_markdown(args.comment_limit))?,
+        OutputFormat::Csv => write!(out, "{}", report.render_delimited(','))?,
+        OutputFormat::Tsv => write!(out, "{}", report.render_delimited('\t'))?,
+        OutputFormat::Ndjson => {
+            for finding in report.findings().iter().chain(report.suppressed()) {
+                NdjsonRecord::Finding(finding).write(out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_report_file(path: &Path, content: &[u8]) -> Result<()> {
+    let written = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+    .and_then(|()| fs::write(path, content));
+    written
+        .map_err(ReportError::from)
+        .with_context(|| format!("failed to write report {}", path.display()))
+}
+
+// Commands go to stdout next to a text report and to stderr otherwise, so
+// machine-readable output stays valid.
+fn print_ignore_commands(report: &Report, stdout: bool) -> Result<()> {
+    let mut seen = HashSet::new();
+    let mut out: Box<dyn Write> = if stdout {
+        Box::new(io::stdout())
     } else {
-        args.fingerprint
+        Box::new(io::stderr())
     };
-    append_ignore(&ignore_path, &entry)?;
-    println!("Added ignore entry to {}", ignore_path.display());
+    for finding in report.findings() {
+        if !seen.insert((&finding.fingerprint, &finding.path)) {
+            continue;
+        }
+        writeln!(
+            out,
+            "nosecrets ignore {} --path {}  # {} at {}",
+            finding.fingerprint,
+            shell_quote(&finding.path),
+            finding.rule_id,
+            location(finding)
+        )?;
+    }
     Ok(())
 }
 
-fn interactive_filter(
+fn shell_quote(value: &str) -> String {
+    let plain = value
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || "/._-+:@".contains(ch));
+    if plain && !value.is_empty() {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+fn build_detector(root: &Path, config: Option<Config>) -> Result<Detector> {
+    // Expired entries are passed on so their findings are flagged.
+    let ignore_entries = read_ignore_file(&root.join(".nosecretsignore"))?;
+    let policy = Policy::load(root)?;
+    if let Some(policy) = &policy {
+        policy.check(config.as_ref(), &ignore_entries, &load_builtin_rules()?)?;
+    }
+    detector_with_ignores(root, config, ignore_entries, policy.as_ref())
+}
+
+pub(crate) fn detector_with_ignores(
     root: &Path,
-    findings: Vec<nosecrets_report::Finding>,
-) -> Result<Vec<nosecrets_report::Finding>> {
+    config: Option<Config>,
+    ignore_entries: Vec<IgnoreEntry>,
+    policy: Option<&Policy>,
+) -> Result<Detector> {
+    let mut options = ScanOptions::default();
+    if let Some(config) = &config {
+        options.apply_config(config)?;
+    }
+    let mut rules = load_builtin_rules()?;
+    if let Some(config) = &config {
+        config.apply_severity(&mut rules)?;
+    }
+    let filter = Filter::from_config(config, ignore_entries)?
+        .with_policy(policy)?
+        .with_nested(root);
+    Ok(Detector::new(rules, filter)?.with_options(options))
+}
+
+fn parse_confidence(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(score) if (0.0..=1.0).contains(&score) => Ok(score),
+        _ => Err(format!("expected a number from 0 to 1, got {value}")),
+    }
+}
+
+// `--jobs`, then `[scan] jobs`; low priority defaults to half the cores.
+fn jobs(args: &ScanArgs, config: Option<&Config>, low_priority: bool) -> Option<usize> {
+    args.jobs
+        .map(usize::from)
+        .or_else(|| {
+            config
+                .and_then(|config| config.scan.jobs)
+                .filter(|&jobs| jobs > 0)
+        })
+        .or_else(|| {
+            low_priority.then(|| {
+                std::thread::available_parallelism().map_or(1, |cores| (cores.get() / 2).max(1))
+            })
+        })
+}
+
+const LOW_PRIORITY_NICE: i32 = 10;
+
+// Threads started afterwards, including the scan pool, inherit the priority.
+#[cfg(unix)]
+fn lower_priority() {
+    // SAFETY: setpriority only changes the scheduling priority of this process.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICE) } != 0 {
+        tracing::debug!("failed to lower priority: {}", io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(unix))]
+fn lower_priority() {
+    tracing::debug!("--low-priority only lowers the thread count on this platform");
+}
+
+// `[report.template]`, with its catalog read from the repository.
+fn report_template(root: &Path, config: Option<&Config>) -> Result<Template> {
+    let Some(config) = config.map(|config| &config.report.template) else {
+        return Ok(Template::default());
+    };
+    let messages = config.load_messages(root)?;
+    Template::new(config.line.clone(), messages, config.links.clone()).map_err(|error| {
+        FilterError::Invalid {
+            key: "report.template",
+            message: error.to_string(),
+        }
+        .into()
+    })
+}
+
+fn walk_options(args: &ScanArgs, config: Option<&Config>) -> WalkOptions {
+    let mut options = WalkOptions::from_config(config);
+    if args.max_file_size.is_some() {
+        options.max_file_size = args.max_file_size;
+    }
+    if let Some(symlinks) = args.symlinks {
+        options.symlinks = symlinks.into();
+    } else if args.follow_symlinks {
+        options.symlinks = SymlinkPolicy::Follow;
+    }
+    options.include_ignored = args.include_ignored;
+    options.recurse_submodules = args.recurse_submodules;
+    options
+}
+
+fn scan_options(
+    args: &ScanArgs,
+    config: Option<&Config>,
+    walk: &mut WalkOptions,
+) -> Result<ScanOptions> {
+    let scan = config.map(|config| &config.scan);
+    let mut options = ScanOptions {
+        max_file_size: walk.max_file_size,
+        sampling: None,
+        chunking: Some(ChunkOptions::default()),
+        collect_stats: args.stats,
+        min_confidence: args.min_confidence,
+        ..ScanOptions::default()
+    };
+    if let Some(config) = config {
+        options.apply_config(config)?;
+    }
+    options.report_symlinks = walk.symlinks == SymlinkPolicy::Report;
+    let sample = args.sample_large_files
+        || scan
+            .and_then(|scan| scan.sample_large_files)
+            .unwrap_or(false);
+    if sample {
+        let mut sampling = SampleOptions::default();
+        if let Some(window) = scan.and_then(|scan| scan.sample_window) {
+            sampling.window_size = window;
+        }
+        if let Some(windows) = scan.and_then(|scan| scan.sample_windows) {
+            sampling.random_windows = windows;
+        }
+        // Large files must reach the detector so it can sample them.
+        options.max_file_size = Some(
+            walk.max_file_size
+                .take()
+                .unwrap_or(DEFAULT_SAMPLE_THRESHOLD),
+        );
+        options.sampling = Some(sampling);
+    }
+    Ok(options)
+}
+
+fn interactive_filter(root: &Path, findings: Vec<Finding>) -> Result<Vec<Finding>> {
     if findings.is_empty() {
         return Ok(findings);
     }
     let ignore_path = root.join(".nosecretsignore");
     let mut remaining = Vec::new();
+    // (rule id, path) pairs ignored with [r] during this session.
+    let mut ignored_rules: HashSet<(Arc<str>, String)> = HashSet::new();
     for finding in findings {
+        if ignored_rules.contains(&(finding.rule_id.clone(), finding.path.clone())) {
+            continue;
+        }
         println!(
             "\n{}:{}:{} {} ({})",
             finding.path, finding.line, finding.column, finding.rule_name, finding.rule_id
         );
         println!("Fingerprint: {}", finding.fingerprint);
-        print!("Ignore this finding? [y/N] ");
+        print!("Ignore this finding? [y]es, [r]ule in this file, [N]o ");
         io::stdout().flush()?;
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
@@ -142,6 +1058,10 @@ fn interactive_filter(
         if answer == "y" || answer == "yes" {
             let entry = format!("{}:{}", finding.fingerprint, finding.path);
             append_ignore(&ignore_path, &entry)?;
+        } else if answer == "r" || answer == "rule" {
+            let entry = format!("{RULE_ENTRY_PREFIX}{}:{}", finding.rule_id, finding.path);
+            append_ignore(&ignore_path, &entry)?;
+            ignored_rules.insert((finding.rule_id, finding.path));
         } else {
             remaining.push(finding);
         }
@@ -149,7 +1069,7 @@ fn interactive_filter(
     Ok(remaining)
 }
 
-fn append_ignore(path: &Path, entry: &str) -> Result<()> {
+pub(crate) fn append_ignore(path: &Path, entry: &str) -> Result<()> {
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use nosecrets_core::{ScanOptions, WalkOptions};
+use nosecrets_filter::{config_paths, normalize_path};
+use nosecrets_report::{sha256_hex, Manifest};
+use nosecrets_rules::{Severity, BUILTIN_RULE_FILES};
+
+use crate::ScanArgs;
+
+pub(crate) fn build_manifest(
+    root: &Path,
+    args: &ScanArgs,
+    fail_on: Severity,
+    walk: &WalkOptions,
+    options: &ScanOptions,
+    files: &[PathBuf],
+) -> Result<Manifest> {
+    let mut rules = Vec::new();
+    for (source, content) in BUILTIN_RULE_FILES {
+        rules.extend_from_slice(source.as_bytes());
+        rules.push(0);
+        rules.extend_from_slice(content.as_bytes());
+        rules.push(0);
+    }
+
+    let mut ignore_hashes = BTreeMap::new();
+    let ignore_path = root.join(".nosecretsignore");
+    if let Some(hash) = hash_file(&ignore_path)? {
+        ignore_hashes.insert(".nosecretsignore".to_string(), hash);
+    }
+
+    let mut paths: Vec<String> = files
+        .iter()
+        .map(|path| normalize_path(path.strip_prefix(root).unwrap_or(path)))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    Ok(Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        rules_hash: sha256_hex(&rules),
+        config_hash: hash_config(root)?,
+        ignore_hashes,
+        files_hash: sha256_hex(paths.join("\n").as_bytes()),
+        files: paths.len(),
+        flags: flags(args, fail_on, walk, options),
+    })
+}
+
+// Compares against a previous manifest and warns about every input that
+// changed. Differences never change the exit code.
+pub(crate) fn verify_manifest(path: &Path, current: &Manifest) -> Result<()> {
+    let previous = Manifest::load(path)
+        .with_context(|| format!("failed to read manifest {}", path.display()))?;
+    for change in current.changes(&previous) {
+        tracing::warn!("{change} since {}", path.display());
+    }
+    Ok(())
+}
+
+// Covers the user config as well, since it is merged into the repo config.
+fn hash_config(root: &Path) -> Result<Option<String>> {
+    let paths = config_paths(root);
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    for path in &paths {
+        bytes.extend(fs::read(path).with_context(|| format!("failed to read {}", path.display()))?);
+        bytes.push(0);
+    }
+    Ok(Some(sha256_hex(&bytes)))
+}
+
+fn hash_file(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(Some(sha256_hex(&bytes)))
+}
+
+fn flags(
+    args: &ScanArgs,
+    fail_on: Severity,
+    walk: &WalkOptions,
+    options: &ScanOptions,
+) -> BTreeMap<String, String> {
+    let mut flags = BTreeMap::new();
+    flags.insert("staged".to_string(), args.staged.to_string());
+    if args.stash {
+        flags.insert("stash".to_string(), "true".to_string());
+    }
+    if let Some(range) = &args.commits {
+        flags.insert("commits".to_string(), range.clone());
+    }
+    if let Some(limit) = options.max_file_size {
+        flags.insert("max_file_size".to_string(), limit.to_string());
+    }
+    if let Some(limit) = options.max_findings_per_file {
+        flags.insert("max_findings_per_file".to_string(), limit.to_string());
+    }
+    if let Some(timeout) = options.file_timeout {
+        flags.insert(
+            "file_timeout_ms".to_string(),
+            timeout.as_millis().to_string(),
+        );
+    }
+    if let Some(limit) = options.max_files {
+        flags.insert("max_files".to_string(), limit.to_string());
+    }
+    flags.insert("symlinks".to_string(), walk.symlinks.as_str().to_string());
+    if walk.include_ignored {
+        flags.insert("include_ignored".to_string(), "true".to_string());
+    }
+    if walk.tracked_only {
+        flags.insert("tracked_only".to_string(), "true".to_string());
+    }
+    if walk.recurse_submodules {
+        flags.insert("recurse_submodules".to_string(), "true".to_string());
+    }
+    if !walk.skip_extensions.is_empty() {
+        flags.insert(
+            "skip_extensions".to_string(),
+            walk.skip_extensions.join(","),
+        );
+    }
+    if let Some(sampling) = &options.sampling {
+        flags.insert(
+            "sample_large_files".to_string(),
+            format!("{}x{}", sampling.random_windows, sampling.window_size),
+        );
+    }
+    if let Some(min) = options.min_confidence {
+        flags.insert("min_confidence".to_string(), min.to_string());
+    }
+    flags.insert("interactive".to_string(), args.interactive.to_string());
+    flags.insert("fail_on".to_string(), fail_on.as_str().to_string());
+    flags
+}
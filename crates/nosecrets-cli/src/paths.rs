@@ -0,0 +1,236 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use nosecrets_core::Detector;
+use nosecrets_report::Finding;
+
+const STDIN_ARG: &str = "-";
+const STDIN_NAME: &str = "<stdin>";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ScanTargets {
+    pub(crate) paths: Vec<PathBuf>,
+    pub(crate) stdin: bool,
+}
+
+// Resolves scan arguments against the working directory. A mistyped path
+// is an error rather than a silent empty scan.
+pub(crate) fn resolve_targets(cwd: &Path, root: &Path, inputs: &[PathBuf]) -> Result<ScanTargets> {
+    let mut targets = ScanTargets::default();
+    for input in inputs {
+        if input.as_os_str() == STDIN_ARG {
+            targets.stdin = true;
+            continue;
+        }
+        let path = normalize_lexically(&cwd.join(input));
+        if !path.exists() {
+            let mut message = format!("path {} does not exist", input.display());
+            let suggestions = suggest(&path);
+            if !suggestions.is_empty() {
+                message.push_str(&format!(" (did you mean {}?)", suggestions.join(", ")));
+            }
+            return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
+        }
+        if !path.starts_with(root) {
+            tracing::warn!(
+                "{} is outside {}; its .nosecrets.toml and .nosecretsignore do not apply",
+                input.display(),
+                root.display()
+            );
+        }
+        targets.paths.push(path);
+    }
+    Ok(targets)
+}
+
+// Reads the paths listed in `source` (`-` for stdin), separated by
+// `separator`: `b'\n'` for `--files-from`, NUL for `--files-from0`. Entries
+// are relative to the working directory; missing ones, such as deleted files
+// still listed by `git ls-files`, are skipped with a warning.
+pub(crate) fn read_file_list(cwd: &Path, source: &Path, separator: u8) -> Result<Vec<PathBuf>> {
+    let mut content = Vec::new();
+    if source.as_os_str() == STDIN_ARG {
+        io::stdin()
+            .read_to_end(&mut content)
+            .context("failed to read the file list from stdin")?;
+    } else {
+        content = fs::read(cwd.join(source))
+            .with_context(|| format!("failed to read file list {}", source.display()))?;
+    }
+    let mut paths = Vec::new();
+    for entry in content.split(|&byte| byte == separator) {
+        let entry = match separator {
+            b'\n' => entry.strip_suffix(b"\r").unwrap_or(entry),
+            _ => entry,
+        };
+        if entry.is_empty() {
+            continue;
+        }
+        let path = normalize_lexically(&cwd.join(path_from_bytes(entry)));
+        if path.exists() {
+            paths.push(path);
+        } else {
+            tracing::warn!("{} does not exist; skipped", path.display());
+        }
+    }
+    Ok(paths)
+}
+
+// The files a hook manager passes for `--hook-mode`, scanned as given
+// without walking directories. Missing files, such as those deleted in the
+// commit, are skipped silently.
+pub(crate) fn hook_files(cwd: &Path, inputs: &[PathBuf]) -> Vec<PathBuf> {
+    inputs
+        .iter()
+        .map(|input| normalize_lexically(&cwd.join(input)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+pub(crate) fn scan_stdin(detector: &Detector) -> Result<Vec<Finding>> {
+    let mut content = Vec::new();
+    io::stdin()
+        .read_to_end(&mut content)
+        .context("failed to read stdin")?;
+    if content.contains(&0) {
+        return Ok(Vec::new());
+    }
+    let text = String::from_utf8_lossy(&content);
+    Ok(detector.scan_content(Path::new(STDIN_NAME), &text))
+}
+
+// Resolves `.` and `..` without touching the file system, so symlinked
+// inputs keep their own path.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// Siblings of a missing path whose names are a small edit away.
+fn suggest(missing: &Path) -> Vec<String> {
+    let (Some(parent), Some(name)) = (missing.parent(), missing.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<(usize, String)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter_map(|candidate| {
+            let distance = edit_distance(&name, &candidate);
+            (distance <= name.chars().count() / 3 + 1).then_some((distance, candidate))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn resolves_targets_and_suggests_similar_paths() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("repo");
+        fs::create_dir_all(root.join("src")).expect("create src");
+        let cwd = root.join("src");
+
+        let targets = resolve_targets(
+            &cwd,
+            &root,
+            &[
+                PathBuf::from("."),
+                PathBuf::from("-"),
+                PathBuf::from("../src"),
+            ],
+        )
+        .expect("resolve");
+        assert_eq!(targets.paths, vec![cwd.clone(), cwd.clone()]);
+        assert!(targets.stdin);
+
+        let error = resolve_targets(&root, &root, &[PathBuf::from("scr")]).expect_err("missing");
+        assert_eq!(
+            error.to_string(),
+            "path scr does not exist (did you mean src?)"
+        );
+    }
+
+    #[test]
+    fn reads_newline_and_nul_separated_file_lists() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("a b.txt"), "").expect("write");
+        fs::write(root.join("c.txt"), "").expect("write");
+        fs::write(root.join("lines"), "a b.txt\r\n./c.txt\ngone.txt\n\n").expect("write");
+        fs::write(root.join("nul"), "a b.txt\0c.txt\0").expect("write");
+
+        let expected = vec![root.join("a b.txt"), root.join("c.txt")];
+        let lines = read_file_list(root, Path::new("lines"), b'\n').expect("lines");
+        assert_eq!(lines, expected);
+        let nul = read_file_list(root, Path::new("nul"), 0).expect("nul");
+        assert_eq!(nul, expected);
+    }
+
+    #[test]
+    fn hook_files_keep_existing_files_only() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).expect("create src");
+        fs::write(root.join("src/app.py"), "").expect("write");
+
+        let files = hook_files(
+            root,
+            &[
+                PathBuf::from("./src/app.py"),
+                PathBuf::from("src"),
+                PathBuf::from("deleted.py"),
+            ],
+        );
+        assert_eq!(files, vec![root.join("src/app.py")]);
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{self, BufRead};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use nosecrets_core::{discover_repo_root, is_zero_oid, pushed_commit_ranges};
+use nosecrets_filter::{Config, Policy};
+use nosecrets_report::{location, Report};
+use nosecrets_rules::Severity;
+
+use crate::{build_detector, exit_code_for, SeverityArg, EXIT_CLEAN};
+
+#[derive(Parser, Debug)]
+pub(crate) struct PreReceiveArgs {
+    /// Lowest severity that rejects a push [default: medium, or fail_on from .nosecrets.policy.toml]
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    fail_on: Option<SeverityArg>,
+}
+
+// Reads `<old-rev> <new-rev> <ref>` lines as passed to a server-side
+// pre-receive hook and scans the lines added by the pushed commits. The hook
+// runs in the (usually bare) repository, so config and .nosecretsignore are
+// read from there.
+pub(crate) fn run_pre_receive(args: PreReceiveArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
+    let detector = build_detector(&root, Config::load(&root)?)?;
+    let fail_on = Policy::fail_on(
+        Policy::load(&root)?.as_ref(),
+        args.fail_on.map(Severity::from),
+        false,
+    )?;
+
+    let mut exit_code = EXIT_CLEAN;
+    // A push whose content could not be fully scanned is rejected too.
+    let mut failure_code = None;
+    for line in io::stdin().lock().lines() {
+        let line = line.context("failed to read hook input")?;
+        let mut fields = line.split_whitespace();
+        let (Some(_old), Some(new), Some(refname)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Deleted refs push no content.
+        if is_zero_oid(new) {
+            continue;
+        }
+        let ranges = pushed_commit_ranges(&root, new)?;
+        let outcome = detector.scan_named_ranges(&root, &ranges)?;
+        for failure in &outcome.failures {
+            tracing::error!("{}: {}", failure.path.display(), failure.error);
+        }
+        if let Some(failure) = outcome.failures.first() {
+            failure_code.get_or_insert(exit_code_for(failure.error.kind()));
+            println!(
+                "nosecrets: push to {refname} rejected: {} file(s) could not be scanned",
+                outcome.failures.len(),
+            );
+        }
+        let report = Report::new(outcome.findings);
+        let code = report.exit_code_at(fail_on);
+        if code == EXIT_CLEAN {
+            continue;
+        }
+        exit_code = code;
+        let blocking: Vec<_> = report
+            .findings()
+            .iter()
+            .filter(|finding| finding.severity.blocks_at(fail_on))
+            .collect();
+        println!(
+            "nosecrets: push to {refname} rejected: {} secret(s) in {} pushed commit(s)",
+            blocking.len(),
+            ranges.len(),
+        );
+        for finding in blocking {
+            println!(
+                "  {}  [{}] {} ({})  {}",
+                location(finding),
+                finding.severity.as_str().to_uppercase(),
+                finding.rule_name,
+                finding.rule_id,
+                finding.fingerprint,
+            );
+        }
+    }
+    if exit_code != EXIT_CLEAN {
+        println!(
+            "Remove the secrets from the commits (e.g. git commit --amend or git rebase -i), \
+             rotate them, and push again."
+        );
+    }
+    Ok(failure_code.unwrap_or(exit_code))
+}
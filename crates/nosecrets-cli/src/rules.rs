@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+
+use nosecrets_core::run_rule_tests;
+use nosecrets_rules::{load_builtin_rules, parse_rules, Rule};
+
+use crate::EXIT_CLEAN;
+
+const EXIT_TESTS_FAILED: i32 = 1;
+
+#[derive(Parser, Debug)]
+pub(crate) struct RulesArgs {
+    #[command(subcommand)]
+    command: RulesCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum RulesCommand {
+    /// Check the match/no_match samples declared in [[rule.tests]]
+    Test(TestArgs),
+    /// Show what a rule finds, its documentation and how to remediate findings
+    Show(ShowArgs),
+}
+
+#[derive(Parser, Debug)]
+struct TestArgs {
+    /// Rule files to test instead of the built-in rules
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct ShowArgs {
+    /// Rule id, e.g. aws-access-key
+    id: String,
+    /// Rule files to search instead of the built-in rules
+    files: Vec<PathBuf>,
+}
+
+pub(crate) fn run_rules(args: RulesArgs) -> Result<i32> {
+    match args.command {
+        RulesCommand::Test(args) => test(args),
+        RulesCommand::Show(args) => show(args),
+    }
+}
+
+// The built-in rules, or the rules in `files` when any are given.
+fn load_rules(files: &[PathBuf]) -> Result<Vec<Rule>> {
+    if files.is_empty() {
+        return Ok(load_builtin_rules()?);
+    }
+    let mut rules = Vec::new();
+    for path in files {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        rules.extend(parse_rules(&content, &path.display().to_string())?);
+    }
+    Ok(rules)
+}
+
+fn show(args: ShowArgs) -> Result<i32> {
+    let rules = load_rules(&args.files)?;
+    let Some(rule) = rules.iter().find(|rule| rule.id == args.id) else {
+        bail!("unknown rule {}", args.id);
+    };
+    println!("{} ({})", rule.name, rule.id);
+    println!("severity: {}", rule.severity.as_str());
+    println!("pattern: {}", rule.pattern);
+    if !rule.keywords.is_empty() {
+        println!("keywords: {}", rule.keywords.join(", "));
+    }
+    if let Some(description) = &rule.description {
+        println!("\n{description}");
+    }
+    if let Some(remediation) = &rule.remediation {
+        println!("\nremediation: {remediation}");
+    }
+    if let Some(docs_url) = &rule.docs_url {
+        println!("docs: {docs_url}");
+    }
+    Ok(EXIT_CLEAN)
+}
+
+fn test(args: TestArgs) -> Result<i32> {
+    let rules = load_rules(&args.files)?;
+    let untested: Vec<&str> = rules
+        .iter()
+        .filter(|rule| rule.tests.is_empty())
+        .map(|rule| rule.id.as_str())
+        .collect();
+
+    let report = run_rule_tests(&rules);
+    for failure in &report.failures {
+        println!("FAIL {}: {}", failure.rule, failure.message);
+    }
+    println!(
+        "{} rule(s), {} sample(s), {} failure(s), {} rule(s) without tests",
+        report.rules,
+        report.samples,
+        report.failures.len(),
+        untested.len()
+    );
+    Ok(if report.failures.is_empty() {
+        EXIT_CLEAN
+    } else {
+        EXIT_TESTS_FAILED
+    })
+}
@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use nosecrets_core::discover_repo_root;
+use nosecrets_report::{read_history, Trends, HISTORY_FILE};
+
+use crate::EXIT_CLEAN;
+
+#[derive(Parser, Debug)]
+pub(crate) struct TrendsArgs {
+    /// Only show the last N recorded scans
+    #[arg(long, value_name = "N")]
+    last: Option<usize>,
+    /// Print the trends as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+// First-seen dates come from the whole history, so `--last` only shortens
+// the table.
+pub(crate) fn run_trends(args: TrendsArgs) -> Result<i32> {
+    let cwd = std::env::current_dir().context("failed to read current dir")?;
+    let root = discover_repo_root(&cwd)?.unwrap_or(cwd);
+    let path = root.join(HISTORY_FILE);
+    let history =
+        read_history(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut trends = Trends::new(&history);
+    if let Some(last) = args.last {
+        let skip = trends.scans.len().saturating_sub(last);
+        trends.scans.drain(..skip);
+    }
+    if args.json {
+        trends.print_json()?;
+    } else {
+        trends.print_text()?;
+    }
+    Ok(EXIT_CLEAN)
+}
@@ -0,0 +1,152 @@
+use std::path::Path;
+
+use nosecrets_filter::{BinaryConfig, FilterError};
+
+use crate::encoding::Encoding;
+
+pub const DEFAULT_BINARY_SAMPLE_BYTES: usize = 8 * 1024;
+pub const DEFAULT_MAX_CONTROL_RATIO: f64 = 0.3;
+
+// Key stores and DER certificates, reported by the rules for binary files
+// whatever their content looks like.
+const BINARY_EXTENSIONS: &[&str] = &["der", "p12", "pfx", "jks", "keystore", "bks"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Content {
+    Text(Encoding),
+    Binary,
+}
+
+// Tells binary files from text by sampling their start, with extensions
+// that are always one or the other.
+#[derive(Debug, Clone)]
+pub struct BinaryOptions {
+    pub sample_bytes: usize,
+    pub max_control_ratio: f64,
+    pub text_extensions: Vec<String>,
+    pub binary_extensions: Vec<String>,
+}
+
+impl Default for BinaryOptions {
+    fn default() -> Self {
+        Self {
+            sample_bytes: DEFAULT_BINARY_SAMPLE_BYTES,
+            max_control_ratio: DEFAULT_MAX_CONTROL_RATIO,
+            text_extensions: Vec::new(),
+            binary_extensions: BINARY_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl BinaryOptions {
+    pub fn from_config(config: &BinaryConfig) -> Result<Self, FilterError> {
+        let mut options = Self::default();
+        if let Some(bytes) = config.sample_bytes {
+            options.sample_bytes = bytes.max(1);
+        }
+        if let Some(ratio) = config.validated_max_control_ratio()? {
+            options.max_control_ratio = ratio;
+        }
+        options.text_extensions = config.text_extensions.clone();
+        options
+            .binary_extensions
+            .extend(config.binary_extensions.iter().cloned());
+        // A listed text extension wins over the built-in binary ones.
+        options
+            .binary_extensions
+            .retain(|ext| !has_extension(ext, &config.text_extensions));
+        Ok(options)
+    }
+
+    pub(crate) fn classify(&self, path: &Path, bytes: &[u8]) -> Content {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        if !ext.is_empty() && has_extension(ext, &self.binary_extensions) {
+            return Content::Binary;
+        }
+        let sample = &bytes[..bytes.len().min(self.sample_bytes)];
+        let encoding = Encoding::detect(sample);
+        // UTF-16 text is full of NUL bytes.
+        if matches!(encoding, Encoding::Utf16 { .. })
+            || !ext.is_empty() && has_extension(ext, &self.text_extensions)
+        {
+            return Content::Text(encoding);
+        }
+        if sample.contains(&0) {
+            return Content::Binary;
+        }
+        let control = sample.iter().filter(|&&byte| is_control(byte)).count();
+        if !sample.is_empty() && control as f64 / sample.len() as f64 > self.max_control_ratio {
+            Content::Binary
+        } else {
+            Content::Text(encoding)
+        }
+    }
+}
+
+fn has_extension(ext: &str, list: &[String]) -> bool {
+    let ext = ext.trim_start_matches('.');
+    list.iter()
+        .any(|listed| listed.trim_start_matches('.').eq_ignore_ascii_case(ext))
+}
+
+// Tabs, line breaks, form feeds and the escapes of colored logs are text.
+fn is_control(byte: u8) -> bool {
+    (byte < 0x20 && !matches!(byte, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)) || byte == 0x7f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_content_and_honors_extensions() {
+        let options = BinaryOptions::default();
+        let classify = |path: &str, bytes: &[u8]| options.classify(Path::new(path), bytes);
+        let text = Content::Text(Encoding::Utf8);
+        assert_eq!(classify("a.txt", b"KEY=value\n\tnext\x1b[0m"), text);
+        assert_eq!(classify("a.bin", b"\x7fELF\x02\x01\x01\0"), Content::Binary);
+        assert_eq!(classify("a.dat", b"\x01\x02\x03\x04abc"), Content::Binary);
+        assert_eq!(classify("cert.DER", b"KEY=value"), Content::Binary);
+
+        // NUL bytes past the sample do not count.
+        let mut late = b"x".repeat(DEFAULT_BINARY_SAMPLE_BYTES);
+        late.push(0);
+        assert_eq!(classify("a.log", &late), text);
+
+        let utf16: Vec<u8> = "KEY=sk_live"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            classify("settings.ini", &utf16),
+            Content::Text(Encoding::Utf16 { big_endian: false })
+        );
+        assert_eq!(
+            classify("latin1.ini", b"name=J\xfcrgen"),
+            Content::Text(Encoding::Latin1)
+        );
+
+        let options = BinaryOptions::from_config(&BinaryConfig {
+            text_extensions: vec![".dat".to_string(), "der".to_string()],
+            binary_extensions: vec!["mo".to_string()],
+            ..BinaryConfig::default()
+        })
+        .expect("options");
+        let classify = |path: &str, bytes: &[u8]| options.classify(Path::new(path), bytes);
+        assert_eq!(classify("a.dat", b"\x01\x02\x03\x04abc"), text);
+        assert_eq!(classify("cert.der", b"KEY=value"), text);
+        assert_eq!(classify("messages.mo", b"msgid"), Content::Binary);
+
+        assert!(BinaryOptions::from_config(&BinaryConfig {
+            max_control_ratio: Some(1.5),
+            ..BinaryConfig::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use nosecrets_report::{Finding, SkippedFile};
+
+use crate::error::{Result, ScanError};
+
+pub const CACHE_FILE: &str = ".nosecrets/cache/staged.json";
+
+// Findings of the files an earlier `scan --staged` run scanned, keyed by
+// path, blob id and the subdirectory configs that applied. Written for one
+// `Detector::cache_key`; a cache of other rules, config or version is dropped
+// on load.
+#[derive(Debug)]
+pub struct ScanCache {
+    path: PathBuf,
+    file: CacheFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    pub(crate) blob: String,
+    pub(crate) findings: Vec<Finding>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) skipped: Option<SkippedFile>,
+    #[serde(default)]
+    pub(crate) scope: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    key: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+impl ScanCache {
+    // A missing or unreadable cache file starts an empty cache.
+    pub fn load(path: &Path, key: &str) -> Self {
+        let entries = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheFile>(&bytes).ok())
+            .filter(|file| file.key == key)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            file: CacheFile {
+                key: key.to_string(),
+                entries,
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.file.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.entries.is_empty()
+    }
+
+    pub(crate) fn get(&self, rel_path: &str, blob: &str, scope: &str) -> Option<&CacheEntry> {
+        self.file
+            .entries
+            .get(rel_path)
+            .filter(|entry| entry.blob == blob && entry.scope == scope)
+    }
+
+    // Only the files of the latest run are kept.
+    pub(crate) fn replace(&mut self, entries: BTreeMap<String, CacheEntry>) {
+        self.file.entries = entries;
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|source| ScanError::write(dir, source))?;
+            // Keeps the cache out of commits when .nosecrets/ is tracked.
+            let ignore = dir.join(".gitignore");
+            if !ignore.exists() {
+                fs::write(&ignore, "*\n").map_err(|source| ScanError::write(&ignore, source))?;
+            }
+        }
+        let write = |source| ScanError::write(&self.path, source);
+        let json = serde_json::to_vec(&self.file).map_err(|error| write(error.into()))?;
+        fs::write(&self.path, json).map_err(write)
+    }
+}
@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::{Result, ScanError};
+
+// Used for rules whose pattern has no upper bound on the match length.
+pub(crate) const UNBOUNDED_MATCH_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    // Files larger than this are memory-mapped and scanned in chunks.
+    pub threshold: u64,
+    pub chunk_size: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 16 * 1024 * 1024,
+            chunk_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Chunk {
+    pub(crate) range: std::ops::Range<usize>,
+    // Newlines before the chunk.
+    pub(crate) line_offset: usize,
+    // Last line whose findings this chunk reports; later ones are left to the
+    // next chunk, which sees them whole. `None` for the last chunk.
+    pub(crate) owned_lines: Option<usize>,
+}
+
+pub(crate) fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).map_err(|source| ScanError::read(path, source))?;
+    // SAFETY: the map is only read while scanning. A file truncated by another
+    // process during the scan can fault, as with any mmap-based reader.
+    unsafe { Mmap::map(&file) }.map_err(|source| ScanError::read(path, source))
+}
+
+// Splits `bytes` into chunks of whole lines. Consecutive chunks overlap by at
+// least `overlap` bytes so a match of that length is seen whole by the chunk
+// its first line belongs to.
+pub(crate) fn chunks(bytes: &[u8], chunk_size: usize, overlap: usize) -> Vec<Chunk> {
+    let overlap = overlap.min(chunk_size / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut line_offset = 0;
+    while start < bytes.len() {
+        let end = line_end(bytes, (start + chunk_size.max(1)).min(bytes.len()));
+        if end >= bytes.len() {
+            chunks.push(Chunk {
+                range: start..bytes.len(),
+                line_offset,
+                owned_lines: None,
+            });
+            break;
+        }
+        let mut next = line_start(bytes, end - overlap);
+        if next <= start {
+            next = end;
+        }
+        let next_offset = line_offset + count_newlines(&bytes[start..next]);
+        chunks.push(Chunk {
+            range: start..end,
+            line_offset,
+            owned_lines: Some(next_offset),
+        });
+        start = next;
+        line_offset = next_offset;
+    }
+    chunks
+}
+
+fn line_end(bytes: &[u8], from: usize) -> usize {
+    bytes[from..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(bytes.len(), |idx| from + idx + 1)
+}
+
+fn line_start(bytes: &[u8], from: usize) -> usize {
+    bytes[..from]
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |idx| idx + 1)
+}
+
+fn count_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&byte| byte == b'\n').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_overlap_on_line_boundaries() {
+        let text: String = (1..=100).map(|n| format!("line {n:03}\n")).collect();
+        let bytes = text.as_bytes();
+        let chunks = chunks(bytes, 200, 50);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks[0].range.start, 0);
+        assert_eq!(chunks.last().expect("chunk").range.end, bytes.len());
+        for pair in chunks.windows(2) {
+            let (first, second) = (&pair[0], &pair[1]);
+            assert!(second.range.start < first.range.end - 40);
+            assert_eq!(bytes[second.range.start - 1], b'\n');
+            assert_eq!(first.owned_lines, Some(second.line_offset));
+            assert_eq!(
+                second.line_offset,
+                count_newlines(&bytes[..second.range.start])
+            );
+        }
+    }
+}
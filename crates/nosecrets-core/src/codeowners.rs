@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+// Where GitHub and GitLab look for the file, first match wins.
+const LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+// Owners of paths as declared in a CODEOWNERS file.
+#[derive(Debug)]
+pub struct CodeOwners {
+    globs: GlobSet,
+    // The entry each glob in `globs` belongs to.
+    glob_entries: Vec<usize>,
+    owners: Vec<Vec<String>>,
+}
+
+impl CodeOwners {
+    // The CODEOWNERS file of the repository at `root`, if there is one.
+    // Like workspace discovery this is lenient: an unreadable file or a
+    // broken pattern leaves findings without owners rather than failing.
+    pub fn discover(root: &Path) -> Option<Self> {
+        LOCATIONS
+            .iter()
+            .find_map(|location| fs::read_to_string(root.join(location)).ok())
+            .map(|content| Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        let mut glob_entries = Vec::new();
+        let mut owners = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            // GitLab section headers (`[Docs]`, `^[Docs] @owner`) carry
+            // default owners this parser does not apply.
+            if line.is_empty() || line.starts_with('#') || line.starts_with(['[', '^']) {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else {
+                continue;
+            };
+            let entry_owners: Vec<String> = fields
+                .take_while(|field| !field.starts_with('#'))
+                .map(str::to_string)
+                .collect();
+            let globs = globs_for(&pattern.replace("\\#", "#"));
+            let entry = owners.len();
+            for glob in globs {
+                let Ok(glob) = GlobBuilder::new(&glob).literal_separator(true).build() else {
+                    continue;
+                };
+                builder.add(glob);
+                glob_entries.push(entry);
+            }
+            owners.push(entry_owners);
+        }
+        Self {
+            globs: builder.build().unwrap_or_else(|_| GlobSet::empty()),
+            glob_entries,
+            owners,
+        }
+    }
+
+    // Owners of `path`, relative to the repository root: those of the last
+    // matching entry. An entry without owners leaves the path unowned.
+    pub fn owners_for(&self, path: &str) -> &[String] {
+        self.globs
+            .matches(path.trim_start_matches('/'))
+            .into_iter()
+            .map(|glob| self.glob_entries[glob])
+            .max()
+            .map_or(&[], |entry| self.owners[entry].as_slice())
+    }
+}
+
+// Patterns follow .gitignore: without a slash except at the end they match at
+// any depth, a trailing slash matches only directories, and a directory
+// covers everything below it.
+fn globs_for(pattern: &str) -> Vec<String> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return Vec::new();
+    }
+    let base = if anchored || trimmed.starts_with("**") {
+        trimmed.to_string()
+    } else {
+        format!("**/{trimmed}")
+    };
+    let below = format!("{base}/**");
+    if dir_only {
+        vec![below]
+    } else {
+        vec![base, below]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_matching_entry_owns_a_path() {
+        let owners = CodeOwners::parse(
+            "# Default owners\n\
+             *        @acme/security\n\
+             *.js     @acme/web # frontend\n\
+             /docs/   @acme/docs writer@acme.com\n\
+             apps/api @acme/api\n\
+             build/   \n\
+             ^[Legacy] @acme/old\n",
+        );
+        let owners_for = |path| owners.owners_for(path).join(" ");
+        assert_eq!(owners_for("README.md"), "@acme/security");
+        assert_eq!(owners_for("src/deep/app.js"), "@acme/web");
+        assert_eq!(owners_for("docs/setup.md"), "@acme/docs writer@acme.com");
+        assert_eq!(owners_for("guide/docs/setup.md"), "@acme/security");
+        assert_eq!(owners_for("apps/api/src/main.rs"), "@acme/api");
+        assert_eq!(owners_for("apps/apiary/main.rs"), "@acme/security");
+        assert_eq!(owners_for("out/build/key.pem"), "");
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+const UTF16_LE_BOM: &[u8] = b"\xff\xfe";
+const UTF16_BE_BOM: &[u8] = b"\xfe\xff";
+
+// Share of the sampled UTF-16 code units that have to be ASCII for a file
+// without a byte order mark to count as UTF-16, in percent.
+const MIN_UTF16_ASCII_PERCENT: usize = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Utf8,
+    Utf16 { big_endian: bool },
+    // Any file that is not UTF-8; every byte is one character.
+    Latin1,
+}
+
+impl Encoding {
+    // Reads a byte order mark, or guesses from the sampled start of a file:
+    // UTF-16 when nearly every other byte is NUL, Latin-1 when the sample is
+    // not UTF-8.
+    pub(crate) fn detect(sample: &[u8]) -> Self {
+        if sample.starts_with(UTF8_BOM) {
+            return Self::Utf8;
+        }
+        if sample.starts_with(UTF16_LE_BOM) {
+            return Self::Utf16 { big_endian: false };
+        }
+        if sample.starts_with(UTF16_BE_BOM) {
+            return Self::Utf16 { big_endian: true };
+        }
+        if let Some(big_endian) = utf16_without_bom(sample) {
+            return Self::Utf16 { big_endian };
+        }
+        match std::str::from_utf8(sample) {
+            Ok(_) => Self::Utf8,
+            // A character cut off by the end of the sample.
+            Err(error) if error.error_len().is_none() => Self::Utf8,
+            Err(_) => Self::Latin1,
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => UTF8_BOM,
+            Self::Utf16 { big_endian: false } => UTF16_LE_BOM,
+            Self::Utf16 { big_endian: true } => UTF16_BE_BOM,
+            Self::Latin1 => b"",
+        }
+    }
+
+    // The text of `bytes` without the byte order mark. Invalid sequences
+    // become U+FFFD.
+    pub(crate) fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        let body = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(body),
+            Self::Utf16 { big_endian } => {
+                let units: Vec<u16> = body
+                    .chunks_exact(2)
+                    .map(|pair| match big_endian {
+                        true => u16::from_be_bytes([pair[0], pair[1]]),
+                        false => u16::from_le_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+                Cow::Owned(String::from_utf16_lossy(&units))
+            }
+            Self::Latin1 => Cow::Owned(body.iter().map(|&byte| byte as char).collect()),
+        }
+    }
+
+    // Maps `offset` in the text decoded from `bytes` back to `bytes`.
+    pub(crate) fn file_offset(self, bytes: &[u8], text: &str, offset: usize) -> usize {
+        let bom = if bytes.starts_with(self.bom()) {
+            self.bom().len()
+        } else {
+            0
+        };
+        let before = text.get(..offset).unwrap_or(text);
+        bom + match self {
+            Self::Utf8 => before.len(),
+            Self::Utf16 { .. } => before.encode_utf16().count() * 2,
+            Self::Latin1 => before.chars().count(),
+        }
+    }
+}
+
+// Windows tools write UTF-16 without a byte order mark too. Mostly-ASCII
+// text then has a NUL in every high byte.
+fn utf16_without_bom(sample: &[u8]) -> Option<bool> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let ascii =
+        |low: u8, high: u8| high == 0 && (low.is_ascii_graphic() || low.is_ascii_whitespace());
+    let (mut little, mut big) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        little += usize::from(ascii(pair[0], pair[1]));
+        big += usize::from(ascii(pair[1], pair[0]));
+    }
+    let enough = |count: usize| count * 100 >= units * MIN_UTF16_ASCII_PERCENT;
+    match (enough(little), enough(big)) {
+        (true, false) => Some(false),
+        (false, true) => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_and_decodes_encodings() {
+        let text = "user = \"jürgen\"\r\nKEY=sk_live\r\n";
+        let offset = text.find("sk_live").unwrap();
+
+        for big_endian in [false, true] {
+            let plain = utf16(text, big_endian);
+            let encoding = Encoding::detect(&plain);
+            assert_eq!(encoding, Encoding::Utf16 { big_endian });
+            assert_eq!(encoding.decode(&plain), text);
+            assert_eq!(encoding.file_offset(&plain, text, offset), 2 * 21);
+
+            let mut marked = utf16("\u{feff}", big_endian);
+            marked.extend(&plain);
+            assert_eq!(Encoding::detect(&marked), encoding);
+            assert_eq!(encoding.decode(&marked), text);
+            assert_eq!(encoding.file_offset(&marked, text, offset), 2 + 2 * 21);
+        }
+
+        let latin1: Vec<u8> = text.chars().map(|ch| ch as u8).collect();
+        assert_eq!(Encoding::detect(&latin1), Encoding::Latin1);
+        assert_eq!(Encoding::Latin1.decode(&latin1), text);
+        assert_eq!(Encoding::Latin1.file_offset(&latin1, text, offset), 21);
+
+        let mut utf8 = UTF8_BOM.to_vec();
+        utf8.extend(text.as_bytes());
+        assert_eq!(Encoding::detect(&utf8), Encoding::Utf8);
+        assert_eq!(Encoding::Utf8.decode(&utf8), text);
+        assert_eq!(Encoding::Utf8.file_offset(&utf8, text, offset), 3 + 22);
+        // Cut inside `ü`.
+        assert_eq!(Encoding::detect(&text.as_bytes()[..10]), Encoding::Utf8);
+    }
+}
@@ -0,0 +1,213 @@
+use std::error::Error as StdError;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use nosecrets_filter::{FilterError, PolicyError, ScopeError};
+use nosecrets_report::ReportError;
+use nosecrets_rules::RulesError;
+use thiserror::Error;
+
+pub use crate::git::GitError;
+
+pub type Result<T, E = ScanError> = std::result::Result<T, E>;
+
+// Errors of the library, by what failed, so callers can e.g. skip a file
+// that cannot be read but stop on a rule that does not compile.
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("failed to read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid rule {rule}: invalid regex: {source}")]
+    Regex {
+        rule: String,
+        #[source]
+        source: regex::Error,
+    },
+    #[error("invalid rule {rule}: invalid glob pattern {pattern}: {source}")]
+    Glob {
+        rule: String,
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+    // A rule that parsed but cannot be used, e.g. one naming an unknown
+    // analyzer or capture group.
+    #[error("invalid rule {rule}: {message}")]
+    Rule { rule: String, message: String },
+    #[error(transparent)]
+    Git(#[from] GitError),
+    #[error(transparent)]
+    Config(#[from] FilterError),
+    #[error(transparent)]
+    Scope(#[from] ScopeError),
+}
+
+impl ScanError {
+    pub(crate) fn read(path: &Path, source: io::Error) -> Self {
+        ScanError::Read {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub(crate) fn write(path: &Path, source: io::Error) -> Self {
+        ScanError::Write {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ScanError::Read { .. } | ScanError::Write { .. } => ErrorKind::Io,
+            ScanError::Regex { .. } | ScanError::Glob { .. } | ScanError::Rule { .. } => {
+                ErrorKind::Rules
+            }
+            ScanError::Git(_) => ErrorKind::Git,
+            ScanError::Config(_) | ScanError::Scope(ScopeError::Config { .. }) => ErrorKind::Config,
+            ScanError::Scope(ScopeError::Policy(_)) => ErrorKind::Policy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Config,
+    Policy,
+    Rules,
+    Io,
+    Git,
+    Report,
+    Other,
+}
+
+impl ErrorKind {
+    // Classifies by the first typed error found in the chain, so context
+    // added on top of e.g. a FilterError does not change its kind.
+    pub fn of(error: &(dyn StdError + 'static)) -> Self {
+        for cause in chain(error) {
+            if let Some(error) = cause.downcast_ref::<ScanError>() {
+                return error.kind();
+            }
+            if cause.is::<FilterError>() {
+                return ErrorKind::Config;
+            }
+            if cause.is::<PolicyError>() {
+                return ErrorKind::Policy;
+            }
+            if cause.is::<RulesError>() {
+                return ErrorKind::Rules;
+            }
+            if cause.is::<GitError>() {
+                return ErrorKind::Git;
+            }
+            if cause.is::<ReportError>() {
+                return ErrorKind::Report;
+            }
+            if cause.is::<io::Error>() {
+                return ErrorKind::Io;
+            }
+        }
+        ErrorKind::Other
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Config => "config",
+            ErrorKind::Policy => "policy",
+            ErrorKind::Rules => "rules",
+            ErrorKind::Io => "io",
+            ErrorKind::Git => "git",
+            ErrorKind::Report => "report",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+// Like `{:#}` but skips causes whose message the previous error already
+// embeds, which most of our thiserror types do.
+pub fn error_message(error: &(dyn StdError + 'static)) -> String {
+    let mut message = String::new();
+    let mut previous = String::new();
+    for cause in chain(error) {
+        let text = cause.to_string();
+        if !previous.is_empty() && previous.contains(&text) {
+            continue;
+        }
+        if !message.is_empty() {
+            message.push_str(": ");
+        }
+        message.push_str(&text);
+        previous = text;
+    }
+    message
+}
+
+fn chain<'a>(
+    error: &'a (dyn StdError + 'static),
+) -> impl Iterator<Item = &'a (dyn StdError + 'static)> {
+    std::iter::successors(Some(error), |&error| error.source())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use std::path::PathBuf;
+
+    #[test]
+    fn classifies_through_context() {
+        let config: anyhow::Error = FilterError::Read {
+            path: PathBuf::from(".nosecrets.toml"),
+            error: io::Error::new(io::ErrorKind::PermissionDenied, "denied"),
+        }
+        .into();
+        assert_eq!(ErrorKind::of(config.as_ref()), ErrorKind::Config);
+
+        let io: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        let io = io.context("reading src/main.rs").unwrap_err();
+        assert_eq!(ErrorKind::of(io.as_ref()), ErrorKind::Io);
+
+        let git: anyhow::Error = GitError::RepositoryRequired("--staged").into();
+        assert_eq!(ErrorKind::of(git.as_ref()), ErrorKind::Git);
+        let boom = anyhow::anyhow!("boom");
+        assert_eq!(ErrorKind::of(boom.as_ref()), ErrorKind::Other);
+
+        let rule: anyhow::Error = ScanError::Rule {
+            rule: "aws".to_string(),
+            message: "unknown analyzer x".to_string(),
+        }
+        .into();
+        let rule = rule.context("loading rules");
+        assert_eq!(ErrorKind::of(rule.as_ref()), ErrorKind::Rules);
+        let config = ScanError::from(FilterError::Invalid {
+            key: "scan.jobs",
+            message: "must be positive".to_string(),
+        });
+        assert_eq!(config.kind(), ErrorKind::Config);
+    }
+
+    #[test]
+    fn error_message_skips_embedded_sources() {
+        let error: anyhow::Error = FilterError::Read {
+            path: PathBuf::from(".nosecrets.toml"),
+            error: io::Error::new(io::ErrorKind::PermissionDenied, "denied"),
+        }
+        .into();
+        let error = error.context("loading config");
+        assert_eq!(
+            error_message(error.as_ref()),
+            "loading config: failed to read .nosecrets.toml: denied"
+        );
+    }
+}
@@ -0,0 +1,821 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use gix::index::entry::{Mode, Stage};
+use nosecrets_filter::utc_date;
+use nosecrets_report::{Blame, GitMessage, MessageKind};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("failed to execute git: {0}")]
+    Spawn(#[source] io::Error),
+    #[error("{command} failed: {message}")]
+    Command { command: String, message: String },
+    #[error("{0} requires a git repository")]
+    RepositoryRequired(&'static str),
+    #[error("failed to {operation}: {error}")]
+    Repository {
+        operation: &'static str,
+        #[source]
+        error: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl GitError {
+    fn gix(operation: &'static str, error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        GitError::Repository {
+            operation,
+            error: Box::new(error),
+        }
+    }
+}
+
+type Result<T, E = GitError> = std::result::Result<T, E>;
+
+const SHORT_COMMIT_LEN: usize = 12;
+
+fn run_git(command: &mut Command, description: &str) -> Result<Output, GitError> {
+    let output = command.output().map_err(GitError::Spawn)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(GitError::Command {
+            command: description.to_string(),
+            message: if stderr.is_empty() {
+                output.status.to_string()
+            } else {
+                stderr
+            },
+        });
+    }
+    Ok(output)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddedLines {
+    pub path: PathBuf,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl AddedLines {
+    pub fn contains(&self, line: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| line >= start && line <= end)
+    }
+}
+
+pub fn discover_repo_root(start: &Path) -> Result<Option<PathBuf>> {
+    match gix::discover(start) {
+        Ok(repo) => Ok(repo.work_dir().map(|path| path.to_path_buf())),
+        Err(_) => Ok(None),
+    }
+}
+
+// Files added, copied or modified in the index relative to HEAD, like
+// `git diff --name-only --cached --diff-filter=ACM`, read without a git
+// binary. Unmerged entries, submodules and sparse directories are skipped.
+pub fn collect_staged_files(repo_root: &Path) -> Result<Vec<PathBuf>> {
+    let repo = gix::open(repo_root).map_err(|error| GitError::gix("open repository", error))?;
+    let index = repo
+        .index_or_empty()
+        .map_err(|error| GitError::gix("read index", error))?;
+    let head = repo
+        .head()
+        .map_err(|error| GitError::gix("read HEAD", error))?;
+    let head_index = if head.is_unborn() {
+        None
+    } else {
+        let tree = repo
+            .head_tree_id()
+            .map_err(|error| GitError::gix("read HEAD tree", error))?;
+        Some(
+            repo.index_from_tree(&tree)
+                .map_err(|error| GitError::gix("read HEAD tree", error))?,
+        )
+    };
+
+    let mut files = Vec::new();
+    for entry in index.entries() {
+        if entry.stage() != Stage::Unconflicted || matches!(entry.mode, Mode::COMMIT | Mode::DIR) {
+            continue;
+        }
+        let path = entry.path(&index);
+        let unchanged = head_index
+            .as_ref()
+            .and_then(|head| head.entry_by_path(path))
+            .is_some_and(|head| head.id == entry.id && head.mode == entry.mode);
+        if !unchanged {
+            files.push(repo_root.join(gix::path::from_bstr(path)));
+        }
+    }
+    Ok(files)
+}
+
+// The id git gives `content` as a blob, like `git hash-object`.
+pub(crate) fn blob_id(content: &[u8]) -> String {
+    gix::objs::compute_hash(gix::hash::Kind::Sha1, gix::objs::Kind::Blob, content).to_string()
+}
+
+// Paths in the index, like `git ls-files`: tracked files and staged
+// additions, whether or not they still exist in the working tree. With
+// `submodules`, the files of checked-out submodules are listed as well.
+pub fn tracked_files(repo_root: &Path, submodules: bool) -> Result<Vec<PathBuf>> {
+    let repo = gix::open(repo_root).map_err(|error| GitError::gix("open repository", error))?;
+    let index = repo
+        .index_or_empty()
+        .map_err(|error| GitError::gix("read index", error))?;
+
+    let mut files = Vec::new();
+    for entry in index.entries() {
+        let path = repo_root.join(gix::path::from_bstr(entry.path(&index)));
+        match entry.mode {
+            Mode::COMMIT if submodules && path.join(".git").exists() => {
+                files.extend(tracked_files(&path, true)?);
+            }
+            Mode::COMMIT | Mode::DIR => {}
+            _ => files.push(path),
+        }
+    }
+    // Unmerged paths have one entry per stage.
+    files.dedup();
+    Ok(files)
+}
+
+// A file in the tree of a commit, read from the object database.
+#[derive(Debug, Clone)]
+pub struct TreeFile {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+// Files in the tree `rev` resolves to (a commit, tag, branch or tree),
+// read without checking it out. Submodules and symlinks are skipped, and
+// blobs of paths `keep` rejects are never loaded.
+pub fn tree_files_at(
+    repo_root: &Path,
+    rev: &str,
+    keep: impl Fn(&Path) -> bool,
+) -> Result<Vec<TreeFile>> {
+    let repo = gix::open(repo_root).map_err(|error| GitError::gix("open repository", error))?;
+    let tree = repo
+        .rev_parse_single(rev)
+        .map_err(|error| GitError::gix("resolve revision", error))?
+        .object()
+        .map_err(|error| GitError::gix("read revision", error))?
+        .peel_to_tree()
+        .map_err(|error| GitError::gix("read revision tree", error))?;
+    let index = repo
+        .index_from_tree(&tree.id)
+        .map_err(|error| GitError::gix("read revision tree", error))?;
+
+    let mut files = Vec::new();
+    for entry in index.entries() {
+        if matches!(entry.mode, Mode::COMMIT | Mode::DIR | Mode::SYMLINK) {
+            continue;
+        }
+        let path = gix::path::from_bstr(entry.path(&index)).into_owned();
+        if !keep(&path) {
+            continue;
+        }
+        let blob = repo
+            .find_blob(entry.id)
+            .map_err(|error| GitError::gix("read blob", error))?;
+        files.push(TreeFile {
+            path,
+            content: blob.detach().data,
+        });
+    }
+    Ok(files)
+}
+
+pub fn git_user_name(repo_root: &Path) -> Option<String> {
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["config", "user.name"]),
+        "git config user.name",
+    )
+    .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+// Short id of the checked out commit, if there is one.
+pub fn head_commit(repo_root: &Path) -> Option<String> {
+    let output = run_git(
+        Command::new("git").arg("-C").arg(repo_root).args([
+            "rev-parse",
+            &format!("--short={SHORT_COMMIT_LEN}"),
+            "HEAD",
+        ]),
+        "git rev-parse HEAD",
+    )
+    .ok()?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+/// Lines added by `range`, keyed by path relative to the repository root.
+/// A bare revision is treated as `<rev>..HEAD`.
+pub fn diff_added_lines(repo_root: &Path, range: &str) -> Result<Vec<AddedLines>> {
+    let range = normalize_range(range);
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args([
+                "-c",
+                "core.quotePath=false",
+                "diff",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                "--diff-filter=ACMR",
+            ])
+            .arg(&range),
+        &format!("git diff {range}"),
+    )?;
+
+    Ok(parse_added_lines(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The revision whose content the added lines of `range` refer to.
+pub fn range_target(range: &str) -> &str {
+    let rhs = range
+        .split_once("...")
+        .or_else(|| range.split_once(".."))
+        .map(|(_, rhs)| rhs)
+        .unwrap_or("");
+    if rhs.is_empty() {
+        "HEAD"
+    } else {
+        rhs
+    }
+}
+
+// Diff ranges covering each stash entry, keyed by its name (`stash@{0}`):
+// the stashed changes against their base commit and, for `git stash -u`,
+// the untracked files stored in the third parent.
+pub fn stash_ranges(repo_root: &Path) -> Result<Vec<(String, String)>> {
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["stash", "list", "--format=%gd"]),
+        "git stash list",
+    )?;
+    let mut ranges = Vec::new();
+    let mut empty_tree = None;
+    for name in String::from_utf8_lossy(&output.stdout).lines() {
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        ranges.push((name.to_string(), format!("{name}^1..{name}")));
+        let untracked = format!("{name}^3");
+        let has_untracked = run_git(
+            Command::new("git")
+                .arg("-C")
+                .arg(repo_root)
+                .args(["rev-parse", "--verify", "--quiet"])
+                .arg(&untracked),
+            "git rev-parse",
+        )
+        .is_ok();
+        if has_untracked {
+            if empty_tree.is_none() {
+                empty_tree = Some(empty_tree_id(repo_root)?);
+            }
+            let base = empty_tree.as_deref().unwrap_or_default();
+            ranges.push((name.to_string(), format!("{base}..{untracked}")));
+        }
+    }
+    Ok(ranges)
+}
+
+// Diff ranges of the commits reachable from `new_rev` but from no ref,
+// keyed by abbreviated commit id. In a pre-receive hook the refs have not
+// moved yet, so these are the pushed commits. Each commit is diffed against
+// its first parent, a root commit against the empty tree.
+pub fn pushed_commit_ranges(repo_root: &Path, new_rev: &str) -> Result<Vec<(String, String)>> {
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["rev-list", "--reverse", "--parents"])
+            .arg(new_rev)
+            .args(["--not", "--all"]),
+        &format!("git rev-list {new_rev}"),
+    )?;
+    let mut ranges = Vec::new();
+    let mut empty_tree = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut ids = line.split_whitespace();
+        let Some(commit) = ids.next() else {
+            continue;
+        };
+        let base = match ids.next() {
+            Some(parent) => parent.to_string(),
+            None => {
+                if empty_tree.is_none() {
+                    empty_tree = Some(empty_tree_id(repo_root)?);
+                }
+                empty_tree.clone().unwrap_or_default()
+            }
+        };
+        let name = commit[..commit.len().min(SHORT_COMMIT_LEN)].to_string();
+        ranges.push((name, format!("{base}..{commit}")));
+    }
+    Ok(ranges)
+}
+
+// The commit messages of `range`, the annotations of tags pointing to its
+// commits and the notes attached to them, each with its text. A bare
+// revision is treated as `<rev>..HEAD`.
+pub fn range_messages(repo_root: &Path, range: &str) -> Result<Vec<(GitMessage, String)>> {
+    let range = normalize_range(range);
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["log", "-z", "--format=%H%n%B"])
+            .arg(&range)
+            .arg("--"),
+        &format!("git log {range}"),
+    )?;
+    let mut messages = Vec::new();
+    for entry in String::from_utf8_lossy(&output.stdout).split('\0') {
+        let Some((commit, text)) = entry.split_once('\n') else {
+            continue;
+        };
+        messages.push((message(MessageKind::Commit, commit, None), text.to_string()));
+    }
+    let commits: HashSet<String> = messages
+        .iter()
+        .map(|(message, _)| message.commit.clone())
+        .collect();
+
+    // One record per tag: type, tagged object, name and annotation.
+    let output = run_git(
+        Command::new("git").arg("-C").arg(repo_root).args([
+            "for-each-ref",
+            "--format=%(objecttype)%00%(*objectname)%00%(refname:short)%00%(contents)%00",
+            "refs/tags",
+        ]),
+        "git for-each-ref refs/tags",
+    )?;
+    for record in String::from_utf8_lossy(&output.stdout).split("\0\n") {
+        let mut fields = record.splitn(4, '\0');
+        let (Some("tag"), Some(commit), Some(name), Some(text)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if commits.contains(commit) {
+            messages.push((
+                message(MessageKind::Tag, commit, Some(name)),
+                text.to_string(),
+            ));
+        }
+    }
+
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["notes", "list"]),
+        "git notes list",
+    )?;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((note, commit)) = line.split_once(' ') else {
+            continue;
+        };
+        if !commits.contains(commit) {
+            continue;
+        }
+        let text = run_git(
+            Command::new("git")
+                .arg("-C")
+                .arg(repo_root)
+                .args(["cat-file", "blob", note]),
+            &format!("git cat-file blob {note}"),
+        )?;
+        messages.push((
+            message(MessageKind::Note, commit, None),
+            String::from_utf8_lossy(&text.stdout).into_owned(),
+        ));
+    }
+    Ok(messages)
+}
+
+fn message(kind: MessageKind, commit: &str, tag: Option<&str>) -> GitMessage {
+    GitMessage {
+        kind,
+        commit: commit.to_string(),
+        tag: tag.map(str::to_string),
+    }
+}
+
+// How findings in a message are reported in place of a path:
+// `commit:<id>`, `tag:<name>` or `note:<id>`.
+pub fn message_label(message: &GitMessage) -> String {
+    let short = &message.commit[..message.commit.len().min(SHORT_COMMIT_LEN)];
+    match (message.kind, &message.tag) {
+        (MessageKind::Tag, Some(tag)) => format!("tag:{tag}"),
+        (MessageKind::Note, _) => format!("note:{short}"),
+        _ => format!("commit:{short}"),
+    }
+}
+
+// Whether `rev` is the all-zero object id git uses for a missing ref in
+// hook input.
+pub fn is_zero_oid(rev: &str) -> bool {
+    !rev.is_empty() && rev.bytes().all(|byte| byte == b'0')
+}
+
+// The id of the empty tree; differs between SHA-1 and SHA-256 repositories.
+fn empty_tree_id(repo_root: &Path) -> Result<String> {
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["hash-object", "-t", "tree", "--stdin"])
+            .stdin(Stdio::null()),
+        "git hash-object",
+    )?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// The commit that last changed each of `lines` (1-based) in `path`, relative
+// to the repository root. Lines that are not committed yet are left out.
+pub fn blame_lines(
+    repo_root: &Path,
+    path: &Path,
+    lines: &[usize],
+) -> Result<HashMap<usize, Blame>> {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(repo_root)
+        .args(["blame", "--line-porcelain"]);
+    for line in lines {
+        command.arg(format!("-L{line},{line}"));
+    }
+    let output = run_git(
+        command.arg("--").arg(path),
+        &format!("git blame {}", path.display()),
+    )?;
+    Ok(parse_blame(&String::from_utf8_lossy(&output.stdout)))
+}
+
+pub fn read_file_at(repo_root: &Path, rev: &str, path: &Path) -> Result<Vec<u8>> {
+    let spec = format!("{}:{}", rev, path.to_string_lossy().replace('\\', "/"));
+    let output = run_git(
+        Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["cat-file", "blob"])
+            .arg(&spec),
+        &format!("git cat-file blob {spec}"),
+    )?;
+    Ok(output.stdout)
+}
+
+fn normalize_range(range: &str) -> String {
+    if range.contains("..") {
+        range.to_string()
+    } else {
+        format!("{range}..HEAD")
+    }
+}
+
+fn parse_added_lines(diff: &str) -> Vec<AddedLines> {
+    let mut files: Vec<AddedLines> = Vec::new();
+    let mut current: Option<AddedLines> = None;
+    for line in diff.lines() {
+        if let Some(target) = line.strip_prefix("+++ ") {
+            if let Some(done) = current.take() {
+                if !done.ranges.is_empty() {
+                    files.push(done);
+                }
+            }
+            current = parse_target_path(target).map(|path| AddedLines {
+                path,
+                ranges: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = line.strip_prefix("@@ ") else {
+            continue;
+        };
+        let Some(file) = current.as_mut() else {
+            continue;
+        };
+        if let Some(range) = parse_hunk_added(hunk) {
+            file.ranges.push(range);
+        }
+    }
+    if let Some(done) = current {
+        if !done.ranges.is_empty() {
+            files.push(done);
+        }
+    }
+    files
+}
+
+// Reads `git blame --line-porcelain`: a `<commit> <orig> <final> [<count>]`
+// header, the commit's `author*` fields and other headers, then the line
+// itself prefixed by a tab.
+fn parse_blame(porcelain: &str) -> HashMap<usize, Blame> {
+    let mut lines = HashMap::new();
+    let mut line = 0;
+    let mut blame: Option<Blame> = None;
+    let mut time = 0i64;
+    let mut tz = 0;
+    for entry in porcelain.lines() {
+        if entry.starts_with('\t') {
+            if let Some(mut blame) = blame.take().filter(|blame| !is_zero_oid(&blame.commit)) {
+                blame.date = utc_date(time + tz);
+                lines.insert(line, blame);
+            }
+            continue;
+        }
+        let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+        match key {
+            "author" => blame
+                .iter_mut()
+                .for_each(|blame| blame.author = value.to_string()),
+            "author-mail" => blame.iter_mut().for_each(|blame| {
+                blame.author_email = value.trim_matches(['<', '>']).to_string();
+            }),
+            "author-time" => time = value.parse().unwrap_or(0),
+            "author-tz" => tz = parse_tz(value),
+            commit if blame.is_none() && commit.len() >= 40 => {
+                let mut fields = value.split_whitespace();
+                line = fields
+                    .nth(1)
+                    .and_then(|line| line.parse().ok())
+                    .unwrap_or(0);
+                (time, tz) = (0, 0);
+                blame = Some(Blame {
+                    commit: commit.to_string(),
+                    author: String::new(),
+                    author_email: String::new(),
+                    date: String::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+// `+0130` as seconds east of UTC.
+fn parse_tz(tz: &str) -> i64 {
+    let (sign, digits) = match tz.strip_prefix('-') {
+        Some(digits) => (-1, digits),
+        None => (1, tz.trim_start_matches('+')),
+    };
+    if digits.len() != 4 {
+        return 0;
+    }
+    let hours: i64 = digits[..2].parse().unwrap_or(0);
+    let minutes: i64 = digits[2..].parse().unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+fn parse_target_path(target: &str) -> Option<PathBuf> {
+    let target = target.trim_end();
+    if target == "/dev/null" {
+        return None;
+    }
+    let unquoted = target
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .unwrap_or(target);
+    let path = unquoted.strip_prefix("b/").unwrap_or(unquoted);
+    Some(PathBuf::from(path))
+}
+
+// Parses the `+start,count` part of a `@@ -a,b +c,d @@` header into an
+// inclusive line range. Pure deletions (count 0) yield nothing.
+fn parse_hunk_added(hunk: &str) -> Option<(usize, usize)> {
+    let added = hunk.split_whitespace().find(|part| part.starts_with('+'))?;
+    let added = &added[1..];
+    let (start, count) = match added.split_once(',') {
+        Some((start, count)) => (start.parse().ok()?, count.parse().ok()?),
+        None => (added.parse().ok()?, 1usize),
+    };
+    if count == 0 {
+        return None;
+    }
+    Some((start, start + count - 1))
+}
+
+// Runs git in `root` for tests, failing the test when git does, and returns
+// its trimmed stdout.
+#[cfg(test)]
+pub(crate) fn git(root: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+        .args(args)
+        .output()
+        .expect("run git");
+    assert!(output.status.success(), "git {args:?}");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_added_hunks_per_file() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -3,0 +4,2 @@ fn main() {
++let a = 1;
++let b = 2;
+@@ -10 +12 @@
+-old
++new
+diff --git a/gone.txt b/gone.txt
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/only_removed.txt b/only_removed.txt
+--- a/only_removed.txt
++++ b/only_removed.txt
+@@ -2 +1,0 @@
+-removed
+";
+        let files = parse_added_lines(diff);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, PathBuf::from("src/a.rs"));
+        assert_eq!(files[0].ranges, vec![(4, 5), (12, 12)]);
+        assert!(files[0].contains(5));
+        assert!(!files[0].contains(6));
+    }
+
+    #[test]
+    fn parses_blame_porcelain() {
+        let porcelain = "\
+3f2a6c1e9b0d4e5f60718293a4b5c6d7e8f90a1b 1 4 1
+author Ada Lovelace
+author-mail <ada@example.com>
+author-time 1700000000
+author-tz +0200
+committer Ada Lovelace
+summary Add config
+filename config.py
+\tkey = 1
+0000000000000000000000000000000000000000 7 7 1
+author Not Committed Yet
+author-mail <not.committed.yet>
+author-time 1700000000
+author-tz +0000
+filename config.py
+\tkey = 2
+";
+        let lines = parse_blame(porcelain);
+        assert_eq!(lines.len(), 1);
+        let blame = &lines[&4];
+        assert_eq!(blame.author, "Ada Lovelace");
+        assert_eq!(blame.author_email, "ada@example.com");
+        // 2023-11-14T22:13:20Z is already the 15th two hours east.
+        assert_eq!(blame.date, "2023-11-15");
+        assert_eq!(parse_tz("+0530"), 19_800);
+    }
+
+    #[test]
+    fn staged_files_come_from_the_index() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        std::fs::write(root.join("kept.txt"), "a").expect("write");
+        std::fs::write(root.join("changed.txt"), "a").expect("write");
+        std::fs::write(root.join("removed.txt"), "a").expect("write");
+        git(root, &["add", "."]);
+        assert_eq!(collect_staged_files(root).expect("unborn HEAD").len(), 3);
+        git(root, &["commit", "-q", "-m", "init"]);
+
+        std::fs::write(root.join("changed.txt"), "b").expect("write");
+        std::fs::write(root.join("added.txt"), "b").expect("write");
+        std::fs::write(root.join("kept.txt"), "unstaged").expect("write");
+        git(root, &["add", "changed.txt", "added.txt"]);
+        git(root, &["rm", "-q", "removed.txt"]);
+        let mut files = collect_staged_files(root).expect("staged");
+        files.sort();
+        assert_eq!(
+            files,
+            vec![root.join("added.txt"), root.join("changed.txt")]
+        );
+    }
+
+    #[test]
+    fn tree_files_are_read_at_a_tag() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        std::fs::create_dir(root.join("src")).expect("mkdir");
+        std::fs::write(root.join("src/config.txt"), "released").expect("write");
+        std::fs::write(root.join("logo.png"), "png").expect("write");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "release"]);
+        git(root, &["tag", "-a", "v1.0.0", "-m", "v1.0.0"]);
+        std::fs::write(root.join("src/config.txt"), "working copy").expect("write");
+        git(root, &["commit", "-q", "-am", "later"]);
+
+        let files = tree_files_at(root, "v1.0.0", |path| {
+            path.extension().is_none_or(|ext| ext != "png")
+        })
+        .expect("tree");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, Path::new("src/config.txt"));
+        assert_eq!(files[0].content, b"released");
+        assert!(tree_files_at(root, "v9.9.9", |_| true).is_err());
+    }
+
+    #[test]
+    fn pushed_commits_exclude_existing_refs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        std::fs::write(root.join("a.txt"), "a").expect("write");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "root"]);
+        let root_commit = git(root, &["rev-parse", "HEAD"]);
+        let ranges = pushed_commit_ranges(root, &root_commit).expect("ranges");
+        assert!(ranges.is_empty(), "already on a ref: {ranges:?}");
+
+        // A commit no ref points to any more stands in for a pushed one.
+        std::fs::write(root.join("b.txt"), "b").expect("write");
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "second"]);
+        let second = git(root, &["rev-parse", "HEAD"]);
+        git(root, &["reset", "-q", "--hard", &root_commit]);
+        let ranges = pushed_commit_ranges(root, &second).expect("ranges");
+        assert_eq!(ranges.len(), 1);
+        assert!(second.starts_with(&ranges[0].0));
+        assert_eq!(ranges[0].1, format!("{root_commit}..{second}"));
+        assert!(is_zero_oid("0000000000000000000000000000000000000000"));
+        assert!(!is_zero_oid(&second));
+    }
+
+    #[test]
+    fn messages_of_a_range_include_tags_and_notes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["commit", "-q", "--allow-empty", "-m", "root"]);
+        git(root, &["tag", "-a", "v0", "-m", "before the range"]);
+        let base = git(root, &["rev-parse", "HEAD"]);
+        git(
+            root,
+            &[
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "fix login\n\ntoken=abc",
+            ],
+        );
+        let commit = git(root, &["rev-parse", "HEAD"]);
+        git(root, &["tag", "-a", "v1", "-m", "release notes"]);
+        git(root, &["tag", "lightweight"]);
+        git(root, &["notes", "add", "-m", "reviewed", "HEAD"]);
+
+        let messages = range_messages(root, &base).expect("messages");
+        let labels: Vec<(String, &str)> = messages
+            .iter()
+            .map(|(message, text)| (message_label(message), text.trim()))
+            .collect();
+        let short = &commit[..SHORT_COMMIT_LEN];
+        assert_eq!(
+            labels,
+            [
+                (format!("commit:{short}"), "fix login\n\ntoken=abc"),
+                ("tag:v1".to_string(), "release notes"),
+                (format!("note:{short}"), "reviewed"),
+            ]
+        );
+        assert!(messages.iter().all(|(message, _)| message.commit == commit));
+    }
+
+    #[test]
+    fn range_target_picks_right_hand_side() {
+        assert_eq!(range_target("origin/main..HEAD"), "HEAD");
+        assert_eq!(range_target("main...feature"), "feature");
+        assert_eq!(range_target("main.."), "HEAD");
+        assert_eq!(range_target("abc123"), "HEAD");
+    }
+}
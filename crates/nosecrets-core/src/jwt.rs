@@ -0,0 +1,84 @@
+use nosecrets_report::JwtClaims;
+use serde_json::{Map, Value};
+
+use crate::algorithm::decode_base64;
+
+pub(crate) struct Token {
+    alg: String,
+    payload: Map<String, Value>,
+}
+
+// The header and payload of `token`, decoded without verifying the signature.
+// None unless both are JSON objects and the header names an `alg`.
+pub(crate) fn parse(token: &str) -> Option<Token> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(_signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let object = |part: &str| match serde_json::from_slice(&decode_base64(part)?) {
+        Ok(Value::Object(map)) => Some(map),
+        _ => None,
+    };
+    let alg = object(header)?.get("alg")?.as_str()?.to_string();
+    Some(Token {
+        alg,
+        payload: object(payload)?,
+    })
+}
+
+impl Token {
+    // `now` is a Unix time, compared against the `exp` claim.
+    pub(crate) fn claims(&self, now: i64) -> JwtClaims {
+        let audience = match self.payload.get("aud") {
+            Some(Value::String(audience)) => vec![audience.clone()],
+            Some(Value::Array(audiences)) => audiences
+                .iter()
+                .filter_map(|audience| audience.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let exp = self
+            .payload
+            .get("exp")
+            .and_then(|exp| exp.as_i64().or_else(|| exp.as_f64().map(|exp| exp as i64)));
+        JwtClaims {
+            alg: self.alg.clone(),
+            issuer: self
+                .payload
+                .get("iss")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            audience,
+            expires: exp.map(nosecrets_filter::utc_date),
+            expired: exp.is_some_and(|exp| exp < now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_claims_without_the_signature() {
+        let token = parse(
+            "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+             eyJzdWIiOiI0MiIsImF1ZCI6WyJhcGkiLCJhZG1pbiJdLCJleHAiOjE2MDAwMDAwMDB9.sig",
+        )
+        .expect("jwt");
+        let claims = token.claims(1_700_000_000);
+        assert_eq!(claims.alg, "HS256");
+        assert_eq!(claims.issuer, None);
+        assert_eq!(claims.audience, ["api", "admin"]);
+        assert_eq!(claims.expires.as_deref(), Some("2020-09-13"));
+        assert!(claims.expired);
+        assert!(!token.claims(1_500_000_000).expired);
+
+        let unsigned = parse("eyJhbGciOiJub25lIn0.eyJpc3MiOiJhIn0.").expect("jwt");
+        assert_eq!(unsigned.claims(0).alg, "none");
+        assert_eq!(unsigned.claims(0).issuer.as_deref(), Some("a"));
+        assert!(parse("eyJ0eXAiOiJKV1QifQ.eyJzdWIiOiIxMjMifQ.c2ln").is_none());
+    }
+}
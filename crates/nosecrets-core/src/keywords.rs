@@ -0,0 +1,116 @@
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+// Shorter literals match almost every file and are not worth prefiltering on.
+const MIN_KEYWORD_LEN: usize = 3;
+
+// More alternatives than this make a rule cheaper to check directly.
+const MAX_KEYWORDS: usize = 16;
+
+// Literals one of which every match of `hir` contains, lowercased for the
+// case-insensitive prefilter. Empty when no such set is worth using, e.g.
+// for `[A-Za-z0-9]{40}`.
+pub(crate) fn derive(hir: &Hir) -> Vec<String> {
+    let mut keywords = required(hir).unwrap_or_default();
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+fn required(hir: &Hir) -> Option<Vec<String>> {
+    match hir.kind() {
+        HirKind::Literal(literal) => {
+            let text = std::str::from_utf8(&literal.0).ok()?;
+            usable(text).then(|| vec![text.to_ascii_lowercase()])
+        }
+        HirKind::Capture(capture) => required(&capture.sub),
+        HirKind::Repetition(repetition) if repetition.min >= 1 => required(&repetition.sub),
+        HirKind::Alternation(alternatives) => {
+            let mut keywords = Vec::new();
+            for alternative in alternatives {
+                keywords.extend(required(alternative)?);
+            }
+            (keywords.len() <= MAX_KEYWORDS).then_some(keywords)
+        }
+        HirKind::Concat(parts) => concat(parts),
+        _ => None,
+    }
+}
+
+// The best of the literal runs in `parts` and the sets its sub-expressions
+// require. A run joins literals and case-insensitive letters (`[Kk]`) that
+// follow each other.
+fn concat(parts: &[Hir]) -> Option<Vec<String>> {
+    let mut candidates = Vec::new();
+    let mut run = String::new();
+    for part in parts {
+        if let Some(text) = run_text(part) {
+            run.push_str(&text);
+            continue;
+        }
+        // Assertions such as `\b` match no text and leave the run intact.
+        if matches!(part.kind(), HirKind::Look(_)) {
+            continue;
+        }
+        candidates.push(vec![std::mem::take(&mut run)]);
+        candidates.extend(required(part));
+    }
+    candidates.push(vec![run]);
+    candidates
+        .into_iter()
+        .filter(|keywords| keywords.iter().all(|keyword| usable(keyword)))
+        .max_by_key(|keywords| keywords.iter().map(String::len).min())
+}
+
+fn run_text(hir: &Hir) -> Option<String> {
+    match hir.kind() {
+        HirKind::Literal(literal) => {
+            let text = std::str::from_utf8(&literal.0).ok()?;
+            text.is_ascii().then(|| text.to_ascii_lowercase())
+        }
+        HirKind::Class(Class::Unicode(class)) => {
+            let mut chars = class
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .take(3);
+            let (first, second) = (chars.next()?, chars.next()?);
+            let is_case_pair = chars.next().is_none()
+                && first.is_ascii_alphabetic()
+                && first.eq_ignore_ascii_case(&second)
+                && first != second;
+            is_case_pair.then(|| first.to_ascii_lowercase().to_string())
+        }
+        _ => None,
+    }
+}
+
+// Non-ASCII text is left out because the prefilter only folds ASCII case.
+fn usable(keyword: &str) -> bool {
+    keyword.len() >= MIN_KEYWORD_LEN && keyword.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derived(pattern: &str) -> Vec<String> {
+        derive(&regex_syntax::parse(pattern).expect("pattern"))
+    }
+
+    #[test]
+    fn derives_literals_every_match_contains() {
+        assert_eq!(derived(r"\bghp_[A-Za-z0-9]{36}\b"), ["ghp_"]);
+        assert_eq!(derived(r"(?:sk|rk)_live_[0-9a-zA-Z]{24}"), ["_live_"]);
+        assert_eq!(
+            derived(r"(?:xoxb|xoxp)-[0-9]{10,13}-[a-zA-Z0-9-]*"),
+            ["xoxb", "xoxp"]
+        );
+        assert_eq!(derived(r"(?i)api[_-]?key\s*=\s*\S+"), ["api"]);
+        assert_eq!(
+            derived(r"-----BEGIN (RSA )?PRIVATE KEY-----"),
+            ["private key-----"]
+        );
+        assert!(derived(r"[A-Za-z0-9/+]{40}").is_empty());
+        assert!(derived(r"(?:ab|cd)[0-9]+").is_empty());
+        assert!(derived(r"(?:token_)?[a-f0-9]{32}").is_empty());
+    }
+}
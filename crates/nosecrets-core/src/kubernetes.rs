@@ -0,0 +1,192 @@
+use std::ops::Range;
+use std::path::Path;
+
+use base64::Engine;
+
+// Values of `kind: Secret` documents in a Kubernetes or Helm manifest.
+#[derive(Debug, Default)]
+pub(crate) struct SecretFields {
+    // Byte range of each `data:` value and its base64-decoded text.
+    pub(crate) data: Vec<(Range<usize>, String)>,
+    // Byte range of each `stringData:` value.
+    pub(crate) string_data: Vec<Range<usize>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Data,
+    StringData,
+}
+
+pub(crate) fn is_manifest(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"))
+}
+
+pub(crate) fn secret_fields(text: &str) -> SecretFields {
+    let mut fields = SecretFields::default();
+    if !text.contains("Secret") {
+        return fields;
+    }
+    for document in documents(text) {
+        if document.iter().any(|(_, line)| is_secret_kind(line)) {
+            collect_fields(&document, &mut fields);
+        }
+    }
+    fields
+}
+
+// Lines with their byte offsets, split into YAML documents at `---`.
+fn documents(text: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut documents = vec![Vec::new()];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content.trim_end() == "---" {
+            documents.push(Vec::new());
+        } else if let Some(document) = documents.last_mut() {
+            document.push((offset, content));
+        }
+        offset += line.len();
+    }
+    documents
+}
+
+fn is_secret_kind(line: &str) -> bool {
+    line.split_once(':')
+        .is_some_and(|(key, value)| key == "kind" && unquote(value.trim()) == "Secret")
+}
+
+fn collect_fields(lines: &[(usize, &str)], fields: &mut SecretFields) {
+    let mut section = None;
+    let mut entry_indent = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let (offset, line) = lines[idx];
+        idx += 1;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if indent == 0 {
+            section = match line.split_once(':').map(|(key, _)| key.trim()) {
+                Some("data") => Some(Section::Data),
+                Some("stringData") => Some(Section::StringData),
+                _ => None,
+            };
+            entry_indent = None;
+            continue;
+        }
+        let Some(section) = section else {
+            continue;
+        };
+        if *entry_indent.get_or_insert(indent) != indent {
+            continue;
+        }
+        let Some(colon) = trimmed.find(':') else {
+            continue;
+        };
+        let after = &trimmed[colon + 1..];
+        let value = after.trim();
+        let value_start = offset + indent + colon + 1 + (after.len() - after.trim_start().len());
+        let (range, text) = if value.starts_with('|') || value.starts_with('>') {
+            // Block scalar: the value is the following, further indented lines.
+            let first = idx;
+            while idx < lines.len() {
+                let (_, next) = lines[idx];
+                let next_indent = next.len() - next.trim_start().len();
+                if !next.trim().is_empty() && next_indent <= indent {
+                    break;
+                }
+                idx += 1;
+            }
+            let block = &lines[first..idx];
+            let Some(((start, first_line), (end, last_line))) = block.first().zip(block.last())
+            else {
+                continue;
+            };
+            let block_indent = first_line.len() - first_line.trim_start().len();
+            let text: Vec<&str> = block
+                .iter()
+                .map(|(_, line)| line.get(block_indent..).unwrap_or("").trim_end())
+                .collect();
+            (start + block_indent..end + last_line.len(), text.join("\n"))
+        } else {
+            let unquoted = unquote(value);
+            let quote = (value.len() - unquoted.len()) / 2;
+            let start = value_start + quote;
+            (start..start + unquoted.len(), unquoted.to_string())
+        };
+        if text.is_empty() {
+            continue;
+        }
+        match section {
+            Section::StringData => fields.string_data.push(range),
+            Section::Data => {
+                let compact: String = text.split_whitespace().collect();
+                let decoded = base64::engine::general_purpose::STANDARD
+                    .decode(compact)
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok());
+                if let Some(decoded) = decoded {
+                    fields.data.push((range, decoded));
+                }
+            }
+        }
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_data_and_string_data_of_secret_documents() {
+        let manifest = "\
+apiVersion: v1
+kind: ConfigMap
+data:
+  plain: aGVsbG8=
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: app
+data:
+  token: \"aGVsbG8=\"
+  broken: not base64!
+stringData:
+  password: hunter2hunter2
+  config: |
+    user=admin
+    pass=s3cret
+";
+        let fields = secret_fields(manifest);
+        assert_eq!(fields.data.len(), 1);
+        let (range, decoded) = &fields.data[0];
+        assert_eq!(&manifest[range.clone()], "aGVsbG8=");
+        assert_eq!(decoded, "hello");
+        let values: Vec<&str> = fields
+            .string_data
+            .iter()
+            .map(|range| &manifest[range.clone()])
+            .collect();
+        assert_eq!(
+            values,
+            vec!["hunter2hunter2", "user=admin\n    pass=s3cret"]
+        );
+    }
+}
@@ -1,33 +1,346 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::ops::{Range, RangeInclusive};
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
-use anyhow::{anyhow, Context, Result};
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
-use regex::Regex;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use regex::{Regex, RegexBuilder};
+use tracing::{debug, info, warn, Level};
 
-use nosecrets_filter::{normalize_path, Filter};
-use nosecrets_report::{fingerprint_secret, mask_secret, Finding};
-use nosecrets_rules::{Rule, RuleAllow, RulePaths, RuleValidate};
+use nosecrets_filter::{
+    normalize_glob_pattern, normalize_path, path_glob, relative_path, ColumnUnit, Config,
+    FileIgnore, Filter, IgnoreEntry, MaskMode, PlaceholderAction, SymlinkPolicy,
+    IGNORE_START_MARKER,
+};
+use nosecrets_report::{
+    sha256_hex, Finding, FingerprintOptions, MaskStyle, NotebookCell, ScanStats, ScanWarning,
+    SkippedFile, SuppressionReason, DEFAULT_REVEAL_PERCENT,
+};
+use nosecrets_rules::{Capture, Rule, RuleAllow, RulePaths, RuleValidate, Severity};
+
+use crate::analyzer::Analyzer;
+use crate::binary::Content;
+use crate::cache::CacheEntry;
+use crate::encoding::Encoding;
+use crate::error::Result;
+use crate::stats::{RuleSample, StatsCollector};
+use crate::syntax::Syntax;
+
+mod algorithm;
+mod analyzer;
+mod binary;
+mod cache;
+mod chunk;
+mod codeowners;
+mod confidence;
+mod encoding;
+mod error;
+mod git;
+mod heuristics;
+mod jwt;
+mod keywords;
+mod kubernetes;
+mod notebook;
+mod plugin;
+mod remediation;
+mod rule_tests;
+mod sample;
+mod stats;
+mod syntax;
+mod walk;
+mod workspace;
+
+pub use binary::{BinaryOptions, DEFAULT_BINARY_SAMPLE_BYTES, DEFAULT_MAX_CONTROL_RATIO};
+pub use cache::{ScanCache, CACHE_FILE};
+pub use chunk::ChunkOptions;
+pub use codeowners::CodeOwners;
+pub use confidence::shannon_entropy;
+pub use error::{error_message, ErrorKind, GitError, ScanError};
+pub use git::{
+    blame_lines, collect_staged_files, diff_added_lines, discover_repo_root, git_user_name,
+    head_commit, is_zero_oid, message_label, pushed_commit_ranges, range_messages, range_target,
+    read_file_at, stash_ranges, tracked_files, tree_files_at, AddedLines, TreeFile,
+};
+pub use heuristics::Heuristics;
+pub use plugin::{Detection, SecretDetector};
+pub use remediation::StoreRecipes;
+pub use rule_tests::{run_rule_tests, RuleTestFailure, RuleTestReport};
+pub use sample::SampleOptions;
+pub use walk::{collect_files, submodule_dirs, WalkOptions, DEFAULT_SKIP_EXTENSIONS};
+pub use workspace::{discover_packages, package_for, Package};
+
+// Enough to cover the first lines a file-level ignore directive may use.
+const FILE_IGNORE_HEAD_BYTES: u64 = 4096;
 
 pub struct Detector {
     rules: Arc<Vec<CompiledRule>>,
     prefilter: Prefilter,
     filter: Arc<Filter>,
+    options: ScanOptions,
+    stats: Option<StatsCollector>,
+    // Longest match any rule can produce, bounding the chunk overlap.
+    max_match_len: usize,
+    // Set when `jobs` limits the threads; the global pool is used otherwise.
+    pool: Option<ThreadPool>,
+    secret_detectors: Vec<Arc<dyn SecretDetector>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    pub max_file_size: Option<u64>,
+    pub sampling: Option<SampleOptions>,
+    pub chunking: Option<ChunkOptions>,
+    pub collect_stats: bool,
+    pub resolve_overlaps: bool,
+    pub fingerprint: FingerprintOptions,
+    // Findings scoring below this confidence are dropped.
+    pub min_confidence: Option<f64>,
+    // Findings kept per file; the rest are dropped with a warning.
+    pub max_findings_per_file: Option<usize>,
+    // Matching time per file; rules left when it runs out are skipped with
+    // a warning. Off by default, as a skipped rule may miss a secret.
+    pub file_timeout: Option<Duration>,
+    // Files scanned per run; the rest are skipped with a warning.
+    pub max_files: Option<usize>,
+    // Suppressed findings are returned with `suppressed` set instead of
+    // dropped.
+    pub keep_suppressed: bool,
+    pub mask: MaskStyle,
+    pub columns: ColumnUnit,
+    pub heuristics: Heuristics,
+    // `[scan.binary]`.
+    pub binary: BinaryOptions,
+    // `[remediation]`.
+    pub recipes: StoreRecipes,
+    // Symbolic links to places outside the root are reported instead of
+    // read; set for `symlinks = "report"`.
+    pub report_symlinks: bool,
+    // Threads scanning files; None uses one per core.
+    pub jobs: Option<usize>,
+    // Texts of at least this many bytes are matched by all rules in
+    // parallel instead of one rule after another; None turns it off.
+    pub parallel_rules_threshold: Option<u64>,
+    // Only rules of this severity or a more severe one run.
+    pub rule_severity: Option<Severity>,
+    // Matching a file stops at its first finding that blocks at this
+    // severity, with the most severe rules running first.
+    pub stop_at: Option<Severity>,
+}
+
+pub const DEFAULT_MAX_FINDINGS_PER_FILE: usize = 1000;
+pub const DEFAULT_PARALLEL_RULES_THRESHOLD: u64 = 1024 * 1024;
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: None,
+            sampling: None,
+            chunking: Some(ChunkOptions::default()),
+            collect_stats: false,
+            resolve_overlaps: true,
+            fingerprint: FingerprintOptions::default(),
+            min_confidence: None,
+            max_findings_per_file: Some(DEFAULT_MAX_FINDINGS_PER_FILE),
+            file_timeout: None,
+            max_files: None,
+            keep_suppressed: false,
+            mask: MaskStyle::default(),
+            columns: ColumnUnit::default(),
+            heuristics: Heuristics::default(),
+            binary: BinaryOptions::default(),
+            recipes: StoreRecipes::default(),
+            report_symlinks: false,
+            jobs: None,
+            parallel_rules_threshold: Some(DEFAULT_PARALLEL_RULES_THRESHOLD),
+            rule_severity: None,
+            stop_at: None,
+        }
+    }
+}
+
+impl ScanOptions {
+    // Applies the `[scan]` overlap, limit, symlink and binary settings, where 0 turns
+    // a limit off, `[report]` masking and columns, `[fingerprint]` and
+    // `[heuristics]`.
+    pub fn apply_config(&mut self, config: &Config) -> Result<()> {
+        let scan = &config.scan;
+        self.resolve_overlaps = scan.resolve_overlaps.unwrap_or(true);
+        if let Some(max) = scan.max_findings_per_file {
+            self.max_findings_per_file = (max > 0).then_some(max);
+        }
+        if let Some(timeout) = scan.file_timeout_ms {
+            self.file_timeout = (timeout > 0).then(|| Duration::from_millis(timeout));
+        }
+        if let Some(max) = scan.max_files {
+            self.max_files = (max > 0).then_some(max);
+        }
+        if let Some(threshold) = scan.parallel_rules_threshold {
+            self.parallel_rules_threshold = (threshold > 0).then_some(threshold);
+        }
+        self.report_symlinks = scan.symlink_policy() == SymlinkPolicy::Report;
+        self.binary = BinaryOptions::from_config(&scan.binary)?;
+        let report = &config.report;
+        self.mask = match report.mask {
+            Some(MaskMode::Full) => MaskStyle::Full,
+            Some(MaskMode::Fixed) => MaskStyle::Fixed,
+            Some(MaskMode::Partial) | None => MaskStyle::Partial {
+                percent: report
+                    .validated_reveal_percent()?
+                    .unwrap_or(DEFAULT_REVEAL_PERCENT),
+            },
+        };
+        self.columns = report.columns;
+        let fingerprint = &config.fingerprint;
+        self.fingerprint.salt = fingerprint.salt.clone();
+        if let Some(length) = fingerprint.validated_length()? {
+            self.fingerprint.length = length;
+        }
+        self.fingerprint.key = fingerprint.key()?;
+        self.heuristics = Heuristics::from_config(&config.heuristics);
+        self.recipes = StoreRecipes::from_config(&config.remediation)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ScanOutcome {
+    pub findings: Vec<Finding>,
+    pub failures: Vec<ScanFailure>,
+    pub files_scanned: usize,
+    pub skipped: Vec<SkippedFile>,
+    // Limits that cut the scan short.
+    pub warnings: Vec<ScanWarning>,
+    // Findings beneath a `.nosecrets-fixtures` marker. They are only in
+    // `findings` with `keep_suppressed`.
+    pub fixture_findings: usize,
+}
+
+type FileScan = (Vec<Finding>, Option<SkippedFile>, Vec<ScanWarning>);
+// Boxed to keep the results of scanned files small.
+type FileResult = Result<FileScan, Box<ScanFailure>>;
+
+#[derive(Debug, Clone)]
+pub struct SecretSpan {
+    pub finding: Finding,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug)]
+pub struct ScanFailure {
+    pub path: PathBuf,
+    pub error: ScanError,
+}
+
+impl ScanOutcome {
+    fn from_results(results: Vec<FileResult>, keep_suppressed: bool) -> Self {
+        let mut outcome = Self::default();
+        for result in results {
+            match result {
+                Ok((findings, skipped, warnings)) => {
+                    outcome.files_scanned += 1;
+                    for finding in findings {
+                        if is_fixture(&finding) {
+                            outcome.fixture_findings += 1;
+                            if !keep_suppressed {
+                                continue;
+                            }
+                        }
+                        outcome.findings.push(finding);
+                    }
+                    outcome.skipped.extend(skipped);
+                    outcome.warnings.extend(warnings);
+                }
+                Err(failure) => outcome.failures.push(*failure),
+            }
+        }
+        outcome
+    }
+
+    // Adds the results of another scan, e.g. of commit messages.
+    pub fn extend(&mut self, other: ScanOutcome) {
+        self.findings.extend(other.findings);
+        self.failures.extend(other.failures);
+        self.files_scanned += other.files_scanned;
+        self.skipped.extend(other.skipped);
+        self.warnings.extend(other.warnings);
+        self.fixture_findings += other.fixture_findings;
+    }
+}
+
+fn is_fixture(finding: &Finding) -> bool {
+    finding.suppressed == Some(SuppressionReason::Fixtures)
+}
+
+// Matching time left for one file. Checked between matches, since a regex
+// search itself cannot be interrupted.
+struct Deadline {
+    at: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl Deadline {
+    fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            at: timeout.map(|timeout| Instant::now() + timeout),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    fn expired(&self) -> bool {
+        if !self.expired.load(Ordering::Relaxed) && self.at.is_some_and(|at| Instant::now() >= at) {
+            self.expired.store(true, Ordering::Relaxed);
+        }
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+// What the rules matching one text share.
+struct MatchInput<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    ignored_regions: Vec<RangeInclusive<usize>>,
+    // Tokenized on first use by a rule with a `context`.
+    syntax: OnceLock<Option<Syntax>>,
 }
 
+// The spans of one rule, with its timing when rules are timed.
+type RuleMatches = (Vec<(usize, SecretSpan)>, Option<(usize, RuleSample)>);
+
 struct CompiledRule {
     rule: Rule,
+    // One finding per target and match.
+    captures: Vec<CaptureTarget>,
+    description: Option<Arc<str>>,
+    docs_url: Option<Arc<str>>,
+    remediation: Option<Arc<str>>,
     regex: Regex,
     allow_patterns: Vec<Regex>,
     allow_values: HashSet<String>,
     include_paths: Option<GlobSet>,
     exclude_paths: Option<GlobSet>,
     charset_regex: Option<Regex>,
+    analyzer: Option<Analyzer>,
+    max_match_len: usize,
+    // The rule's keywords, or those derived from its pattern without them.
+    keywords: Vec<String>,
+}
+
+struct CaptureTarget {
+    group: usize,
+    // Shared by every finding of the target instead of cloned per match.
+    id: Arc<str>,
+    name: Arc<str>,
 }
 
 struct Prefilter {
@@ -40,100 +353,1514 @@ impl Detector {
     pub fn new(rules: Vec<Rule>, filter: Filter) -> Result<Self> {
         let mut compiled = Vec::with_capacity(rules.len());
         for rule in rules {
-            let regex = Regex::new(&rule.pattern)
-                .with_context(|| format!("invalid regex for rule {}", rule.id))?;
-            let (allow_patterns, allow_values) = compile_rule_allow(rule.allow.as_ref())?;
-            let (include_paths, exclude_paths) = compile_rule_paths(rule.paths.as_ref())?;
-            let charset_regex = compile_charset(rule.validate.as_ref())?;
-            compiled.push(CompiledRule {
-                rule,
-                regex,
-                allow_patterns,
-                allow_values,
-                include_paths,
-                exclude_paths,
-                charset_regex,
-            });
+            compiled.push(CompiledRule::compile(rule)?);
         }
+        let max_match_len = compiled
+            .iter()
+            .map(|rule| rule.max_match_len)
+            .max()
+            .unwrap_or(0);
         let compiled = Arc::new(compiled);
         let prefilter = Prefilter::new(&compiled);
         Ok(Self {
             rules: compiled,
             prefilter,
             filter: Arc::new(filter),
+            options: ScanOptions::default(),
+            stats: None,
+            max_match_len,
+            pool: None,
+            secret_detectors: Vec::new(),
         })
     }
 
+    // Runs `detector` on every scanned text next to the rules.
+    pub fn with_secret_detector(mut self, detector: Arc<dyn SecretDetector>) -> Self {
+        self.secret_detectors.push(detector);
+        self
+    }
+
+    pub fn with_options(mut self, options: ScanOptions) -> Self {
+        self.stats = options.collect_stats.then(StatsCollector::default);
+        self.pool = options.jobs.and_then(|jobs| {
+            ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .thread_name(|idx| format!("nosecrets-scan-{idx}"))
+                .build()
+                .inspect_err(|error| warn!("using all cores, could not limit threads: {error}"))
+                .ok()
+        });
+        self.options = options;
+        self
+    }
+
+    fn run_parallel<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    pub fn options(&self) -> &ScanOptions {
+        &self.options
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter().map(|rule| &rule.rule)
+    }
+
+    pub fn stats(&self) -> Option<ScanStats> {
+        let ids: Vec<&str> = self
+            .rules
+            .iter()
+            .map(|rule| rule.rule.id.as_str())
+            .collect();
+        self.stats.as_ref().map(|stats| stats.snapshot(&ids))
+    }
+
     pub fn scan_files(&self, root: &Path, files: &[PathBuf]) -> Result<Vec<Finding>> {
-        let findings: Vec<Finding> = files
-            .par_iter()
-            .flat_map(|path| match self.scan_file(root, path) {
-                Ok(results) => results,
-                Err(error) => {
-                    eprintln!("nosecrets: failed to scan {}: {}", path.display(), error);
-                    Vec::new()
+        let mut outcome = self.scan_paths(root, files);
+        if !outcome.failures.is_empty() {
+            return Err(outcome.failures.swap_remove(0).error);
+        }
+        Ok(outcome.findings)
+    }
+
+    pub fn scan_paths(&self, root: &Path, files: &[PathBuf]) -> ScanOutcome {
+        let (files, warning) = self.limit_files(files);
+        let results: Vec<FileResult> = self.run_parallel(|| {
+            files
+                .par_iter()
+                .map(|path| self.scan_file_result(root, path))
+                .collect()
+        });
+        let mut outcome = ScanOutcome::from_results(results, self.options.keep_suppressed);
+        outcome.warnings.extend(warning);
+        outcome
+    }
+
+    // Like `scan_paths`, but hands each finding to `on_finding` as soon as its
+    // file is scanned instead of buffering them. `on_finding` runs on worker
+    // threads in no particular order; the returned outcome has no findings.
+    pub fn scan_paths_with<F>(&self, root: &Path, files: &[PathBuf], on_finding: F) -> ScanOutcome
+    where
+        F: Fn(Finding) + Sync,
+    {
+        let (files, warning) = self.limit_files(files);
+        let results: Vec<FileResult> = self.run_parallel(|| {
+            files
+                .par_iter()
+                .map(|path| {
+                    let (findings, skipped, warnings) = self.scan_file_result(root, path)?;
+                    // Fixture findings are returned too, to be counted.
+                    let fixtures = findings.iter().filter(|f| is_fixture(f)).cloned().collect();
+                    findings
+                        .into_iter()
+                        .filter(|finding| self.options.keep_suppressed || !is_fixture(finding))
+                        .for_each(&on_finding);
+                    Ok((fixtures, skipped, warnings))
+                })
+                .collect()
+        });
+        let mut outcome = ScanOutcome::from_results(results, false);
+        outcome.warnings.extend(warning);
+        outcome
+    }
+
+    // Identifies what the findings of a file depend on besides its content:
+    // the version, rules, filter, options and secret detectors.
+    pub fn cache_key(&self) -> String {
+        let rules: Vec<&Rule> = self.rules().collect();
+        let options = ScanOptions {
+            collect_stats: false,
+            jobs: None,
+            ..self.options.clone()
+        };
+        let state = format!(
+            "{} {rules:?} {:?} {options:?} {:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.filter,
+            self.secret_detectors
+        );
+        sha256_hex(state.as_bytes())
+    }
+
+    // Like `scan_paths`, but takes the findings of files whose path and blob
+    // id are in `cache` from there, and leaves `cache` with the files of this
+    // run. Files with warnings, symbolic links and revealed secrets are not
+    // cached.
+    pub fn scan_paths_cached(
+        &self,
+        root: &Path,
+        files: &[PathBuf],
+        cache: &mut ScanCache,
+    ) -> ScanOutcome {
+        if self.options.mask == MaskStyle::Reveal {
+            return self.scan_paths(root, files);
+        }
+        let (files, warning) = self.limit_files(files);
+        let results: Vec<(FileResult, Option<(String, CacheEntry)>)> = self.run_parallel(|| {
+            files
+                .par_iter()
+                .map(|path| {
+                    let regular = fs::symlink_metadata(path).is_ok_and(|meta| meta.is_file());
+                    let Some(content) = regular.then(|| fs::read(path).ok()).flatten() else {
+                        return (self.scan_file_result(root, path), None);
+                    };
+                    let rel_path = normalize_path(&relative_path(path, root));
+                    let blob = git::blob_id(&content);
+                    let scope =
+                        sha256_hex(self.filter.scope_state(Path::new(&rel_path)).as_bytes());
+                    let result = match cache.get(&rel_path, &blob, &scope) {
+                        Some(entry) => {
+                            debug!(path = %rel_path, "cached");
+                            Ok((entry.findings.clone(), entry.skipped.clone(), Vec::new()))
+                        }
+                        None => self.scan_file_result(root, path),
+                    };
+                    let key = CacheEntry {
+                        blob,
+                        findings: Vec::new(),
+                        skipped: None,
+                        scope,
+                    };
+                    (result, Some((rel_path, key)))
+                })
+                .collect()
+        });
+        let mut entries = BTreeMap::new();
+        for (result, key) in &results {
+            if let (Ok((findings, skipped, warnings)), Some((rel_path, key))) = (result, key) {
+                if warnings.is_empty() {
+                    let entry = CacheEntry {
+                        findings: findings.clone(),
+                        skipped: skipped.clone(),
+                        ..key.clone()
+                    };
+                    entries.insert(rel_path.clone(), entry);
+                }
+            }
+        }
+        cache.replace(entries);
+        let mut outcome = ScanOutcome::from_results(
+            results.into_iter().map(|(result, _)| result).collect(),
+            self.options.keep_suppressed,
+        );
+        outcome.warnings.extend(warning);
+        outcome
+    }
+
+    pub fn scan_files_with<F>(&self, root: &Path, files: &[PathBuf], on_finding: F) -> Result<()>
+    where
+        F: Fn(Finding) + Sync,
+    {
+        let mut outcome = self.scan_paths_with(root, files, on_finding);
+        if !outcome.failures.is_empty() {
+            return Err(outcome.failures.swap_remove(0).error);
+        }
+        Ok(())
+    }
+
+    fn scan_file_result(&self, root: &Path, path: &Path) -> FileResult {
+        let started = Instant::now();
+        let deadline = Deadline::new(self.options.file_timeout);
+        let rel_path = normalize_path(&relative_path(path, root));
+        let (findings, skipped) = self.scan_file(root, path, &deadline).map_err(|error| {
+            Box::new(ScanFailure {
+                path: path.to_path_buf(),
+                error,
+            })
+        })?;
+        info!(
+            path = %rel_path,
+            findings = findings.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "scanned"
+        );
+        Ok(self.limit_file(rel_path, findings, skipped, &deadline))
+    }
+
+    // Skips the files beyond `max_files`.
+    fn limit_files<'a, T>(&self, files: &'a [T]) -> (&'a [T], Option<ScanWarning>) {
+        match self.options.max_files {
+            Some(max) if files.len() > max => {
+                let message = format!(
+                    "{} files to scan, only the first {max} were scanned (max_files)",
+                    files.len()
+                );
+                warn!("{message}");
+                (
+                    &files[..max],
+                    Some(ScanWarning {
+                        path: None,
+                        message,
+                    }),
+                )
+            }
+            _ => (files, None),
+        }
+    }
+
+    // Applies `max_findings_per_file` and reports the limits the file hit.
+    fn limit_file(
+        &self,
+        path: String,
+        mut findings: Vec<Finding>,
+        skipped: Option<SkippedFile>,
+        deadline: &Deadline,
+    ) -> FileScan {
+        let mut warnings = Vec::new();
+        if deadline.expired() {
+            let timeout = self.options.file_timeout.unwrap_or_default();
+            warnings.push(format!(
+                "matching stopped after {}ms, remaining rules were not checked (file_timeout_ms)",
+                timeout.as_millis()
+            ));
+        }
+        if let Some(max) = self.options.max_findings_per_file {
+            if findings.len() > max {
+                warnings.push(format!(
+                    "{} findings, only the first {max} are reported (max_findings_per_file)",
+                    findings.len()
+                ));
+                findings.sort_by_key(|finding| (finding.line, finding.column));
+                findings.truncate(max);
+            }
+        }
+        let warnings = warnings
+            .into_iter()
+            .map(|message| {
+                warn!(path = %path, "{message}");
+                ScanWarning {
+                    path: Some(path.clone()),
+                    message,
                 }
             })
             .collect();
-        Ok(findings)
+        (findings, skipped, warnings)
+    }
+
+    pub fn scan_commit_range(&self, repo_root: &Path, range: &str) -> Result<ScanOutcome> {
+        let added = diff_added_lines(repo_root, range)?;
+        let target = range_target(range);
+        let (added, warning) = self.limit_files(&added);
+        let results: Vec<FileResult> = self.run_parallel(|| {
+            added
+                .par_iter()
+                .map(|file| {
+                    let deadline = Deadline::new(self.options.file_timeout);
+                    let (findings, skipped) = self
+                        .scan_added_lines(repo_root, target, file, &deadline)
+                        .map_err(|error| {
+                            Box::new(ScanFailure {
+                                path: file.path.clone(),
+                                error,
+                            })
+                        })?;
+                    Ok(self.limit_file(normalize_path(&file.path), findings, skipped, &deadline))
+                })
+                .collect()
+        });
+        let mut outcome = ScanOutcome::from_results(results, self.options.keep_suppressed);
+        outcome.warnings.extend(warning);
+        Ok(outcome)
+    }
+
+    fn scan_added_lines(
+        &self,
+        repo_root: &Path,
+        target: &str,
+        file: &AddedLines,
+        deadline: &Deadline,
+    ) -> Result<(Vec<Finding>, Option<SkippedFile>)> {
+        self.filter.check_scope(&file.path)?;
+        let ignored = self.filter.is_path_ignored(&file.path);
+        if ignored && !self.filter.protects_rules() {
+            return Ok((Vec::new(), None));
+        }
+        let content = read_file_at(repo_root, target, &file.path)?;
+        let Content::Text(encoding) = self.options.binary.classify(&file.path, &content) else {
+            return Ok((Vec::new(), None));
+        };
+        let text = encoding.decode(&content);
+        let ignore = Filter::file_ignore(&text);
+        let findings = if self.skips_file(ignore.as_ref()) {
+            Vec::new()
+        } else {
+            self.scan_decoded(&file.path, &content, &text, encoding, deadline)
+                .into_iter()
+                .filter(|finding| (finding.line..=finding.end_line).any(|line| file.contains(line)))
+                .collect()
+        };
+        let (mut findings, skipped) = self.apply_file_ignore(&file.path, findings, ignore);
+        if ignored {
+            self.apply_path_ignore(&mut findings);
+        }
+        self.mark_fixtures(&file.path, &mut findings);
+        Ok((findings, skipped))
+    }
+
+    // Scans the commit messages, tag annotations and git notes of `range`.
+    // Findings carry the commit in `message` and are reported under its
+    // `message_label`.
+    pub fn scan_messages(&self, repo_root: &Path, range: &str) -> Result<ScanOutcome> {
+        let messages = range_messages(repo_root, range)?;
+        let results: Vec<FileResult> = self.run_parallel(|| {
+            messages
+                .par_iter()
+                .map(|(message, text)| {
+                    let label = message_label(message);
+                    let mut findings = self.scan_content(Path::new(&label), text);
+                    for finding in &mut findings {
+                        finding.message = Some(message.clone());
+                    }
+                    Ok(self.limit_file(label, findings, None, &Deadline::unlimited()))
+                })
+                .collect()
+        });
+        Ok(ScanOutcome::from_results(
+            results,
+            self.options.keep_suppressed,
+        ))
+    }
+
+    // Scans every stash entry. Findings are reported as `stash@{N}:path`.
+    pub fn scan_stash(&self, repo_root: &Path) -> Result<ScanOutcome> {
+        self.scan_named_ranges(repo_root, &stash_ranges(repo_root)?)
+    }
+
+    // Scans the lines added by each range. Findings are reported as
+    // `<name>:path`.
+    pub fn scan_named_ranges(
+        &self,
+        repo_root: &Path,
+        ranges: &[(String, String)],
+    ) -> Result<ScanOutcome> {
+        let mut outcome = ScanOutcome::default();
+        for (name, range) in ranges {
+            let mut entry = self.scan_commit_range(repo_root, range)?;
+            for finding in &mut entry.findings {
+                finding.path = format!("{name}:{}", finding.path);
+            }
+            outcome.extend(entry);
+        }
+        Ok(outcome)
     }
 
-    fn scan_file(&self, root: &Path, path: &Path) -> Result<Vec<Finding>> {
-        let rel_path = path.strip_prefix(root).unwrap_or(path);
+    // Scans the tree of `rev` straight from the object database, leaving the
+    // working copy alone. Findings are reported as `<rev>:path`.
+    pub fn scan_rev(&self, repo_root: &Path, rev: &str, walk: &WalkOptions) -> Result<ScanOutcome> {
+        let files = tree_files_at(repo_root, rev, |path| {
+            !walk.is_skipped_extension(path)
+                && !self.skips_ignored_path(self.filter.is_path_ignored(path))
+        })?;
+        let (files, warning) = self.limit_files(&files);
+        let results: Vec<FileResult> = self.run_parallel(|| {
+            files
+                .par_iter()
+                .map(|file| {
+                    self.filter.check_scope(&file.path).map_err(|error| {
+                        Box::new(ScanFailure {
+                            path: file.path.clone(),
+                            error: error.into(),
+                        })
+                    })?;
+                    let deadline = Deadline::new(self.options.file_timeout);
+                    let (findings, skipped) = self.scan_blob(file, walk, &deadline);
+                    Ok(self.limit_file(normalize_path(&file.path), findings, skipped, &deadline))
+                })
+                .collect()
+        });
+        let mut outcome = ScanOutcome::from_results(results, self.options.keep_suppressed);
+        for finding in &mut outcome.findings {
+            finding.path = format!("{rev}:{}", finding.path);
+        }
+        outcome.warnings.extend(warning);
+        Ok(outcome)
+    }
+
+    fn scan_blob(
+        &self,
+        file: &TreeFile,
+        walk: &WalkOptions,
+        deadline: &Deadline,
+    ) -> (Vec<Finding>, Option<SkippedFile>) {
+        let started = Instant::now();
+        let rel_path = &file.path;
+        if walk.is_too_large(file.content.len() as u64) {
+            info!(path = %rel_path.display(), bytes = file.content.len(), "skipped: larger than max_file_size");
+            return (Vec::new(), None);
+        }
+        let Content::Text(encoding) = self.options.binary.classify(rel_path, &file.content) else {
+            info!(path = %rel_path.display(), "binary file");
+            return (self.scan_binary(rel_path, deadline), None);
+        };
+        let text = encoding.decode(&file.content);
+        let ignore = Filter::file_ignore(&text);
+        if self.skips_file(ignore.as_ref()) {
+            return self.apply_file_ignore(rel_path, Vec::new(), ignore);
+        }
+        let notebook = notebook::is_notebook(rel_path)
+            .then(|| self.scan_notebook(rel_path, &text, deadline))
+            .flatten();
+        let findings = notebook.unwrap_or_else(|| {
+            self.scan_decoded(rel_path, &file.content, &text, encoding, deadline)
+        });
+        self.record_file(rel_path, file.content.len() as u64, started);
+        let (mut findings, skipped) = self.apply_file_ignore(rel_path, findings, ignore);
         if self.filter.is_path_ignored(rel_path) {
-            return Ok(Vec::new());
+            self.apply_path_ignore(&mut findings);
+        }
+        self.mark_fixtures(rel_path, &mut findings);
+        (findings, skipped)
+    }
+
+    fn scan_file(
+        &self,
+        root: &Path,
+        path: &Path,
+        deadline: &Deadline,
+    ) -> Result<(Vec<Finding>, Option<SkippedFile>)> {
+        let rel_path = &relative_path(path, root);
+        self.filter.check_scope(rel_path)?;
+        let ignored = self.filter.is_path_ignored(rel_path);
+        if self.skips_ignored_path(ignored) {
+            debug!(path = %rel_path.display(), "skipped: ignored path");
+            return Ok((Vec::new(), None));
+        }
+        let link_target = self
+            .options
+            .report_symlinks
+            .then(|| walk::symlink_target_outside(root, path))
+            .flatten();
+        let (mut findings, skipped) = match link_target {
+            Some(target) => (self.scan_symlink(rel_path, &target, deadline), None),
+            None => self.read_and_scan(rel_path, path, deadline)?,
+        };
+        if ignored {
+            self.apply_path_ignore(&mut findings);
+        }
+        self.mark_fixtures(rel_path, &mut findings);
+        Ok((findings, skipped))
+    }
+
+    // Files in ignored paths are still scanned for the rules a policy
+    // protects.
+    fn skips_ignored_path(&self, ignored: bool) -> bool {
+        ignored && !self.options.keep_suppressed && !self.filter.protects_rules()
+    }
+
+    fn apply_path_ignore(&self, findings: &mut Vec<Finding>) {
+        for finding in findings.iter_mut() {
+            if !self.filter.is_protected(&finding.rule_id) {
+                finding.suppressed = Some(SuppressionReason::IgnoredPath);
+            }
+        }
+        if !self.options.keep_suppressed {
+            findings.retain(|finding| finding.suppressed.is_none());
+        }
+    }
+
+    // Findings beneath a `.nosecrets-fixtures` marker are suppressed but
+    // kept until the outcome counts them.
+    fn mark_fixtures<'a>(
+        &self,
+        rel_path: &Path,
+        findings: impl IntoIterator<Item = &'a mut Finding>,
+    ) {
+        if !self.filter.is_fixture_path(rel_path) {
+            return;
+        }
+        for finding in findings.into_iter().filter(|f| f.suppressed.is_none()) {
+            finding.suppressed = Some(SuppressionReason::Fixtures);
+        }
+    }
+
+    fn read_and_scan(
+        &self,
+        rel_path: &Path,
+        path: &Path,
+        deadline: &Deadline,
+    ) -> Result<(Vec<Finding>, Option<SkippedFile>)> {
+        let started = Instant::now();
+        if let Some(max) = self.options.max_file_size {
+            let len = fs::metadata(path)
+                .map_err(|source| ScanError::read(path, source))?
+                .len();
+            if len > max {
+                let Some(sampling) = &self.options.sampling else {
+                    info!(path = %rel_path.display(), bytes = len, "skipped: larger than max_file_size");
+                    return Ok((Vec::new(), None));
+                };
+                let head = sample::read_head(path, FILE_IGNORE_HEAD_BYTES)?;
+                // Sampled windows are read as UTF-8.
+                if matches!(
+                    self.options.binary.classify(rel_path, &head),
+                    Content::Binary | Content::Text(Encoding::Utf16 { .. })
+                ) {
+                    info!(path = %rel_path.display(), bytes = len, "skipped: large binary or UTF-16 file");
+                    return Ok((Vec::new(), None));
+                }
+                let ignore = Filter::file_ignore(&String::from_utf8_lossy(&head));
+                if self.skips_file(ignore.as_ref()) {
+                    return Ok(self.apply_file_ignore(rel_path, Vec::new(), ignore));
+                }
+                let (findings, bytes) =
+                    self.scan_sampled(rel_path, path, len, sampling, deadline)?;
+                self.record_file(rel_path, bytes, started);
+                return Ok(self.apply_file_ignore(rel_path, findings, ignore));
+            }
+        }
+        if let Some(chunking) = &self.options.chunking {
+            let len = fs::metadata(path)
+                .map_err(|source| ScanError::read(path, source))?
+                .len();
+            if len > chunking.threshold && !notebook::is_notebook(rel_path) {
+                let map = chunk::map_file(path)?;
+                match self.options.binary.classify(rel_path, &map) {
+                    Content::Binary => {
+                        info!(path = %rel_path.display(), "binary file");
+                        return Ok((self.scan_binary(rel_path, deadline), None));
+                    }
+                    Content::Text(Encoding::Utf8) => {
+                        let (findings, ignore) =
+                            self.scan_chunked(rel_path, &map, chunking, deadline);
+                        self.record_file(rel_path, len, started);
+                        return Ok(self.apply_file_ignore(rel_path, findings, ignore));
+                    }
+                    // Decoded in full below.
+                    Content::Text(_) => {}
+                }
+            }
         }
-        let content = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
-        if content.contains(&0) {
-            return Ok(Vec::new());
+        let content = fs::read(path).map_err(|source| ScanError::read(path, source))?;
+        let Content::Text(encoding) = self.options.binary.classify(rel_path, &content) else {
+            info!(path = %rel_path.display(), "binary file");
+            return Ok((self.scan_binary(rel_path, deadline), None));
+        };
+        let text = encoding.decode(&content);
+        let ignore = Filter::file_ignore(&text);
+        if self.skips_file(ignore.as_ref()) {
+            return Ok(self.apply_file_ignore(rel_path, Vec::new(), ignore));
         }
-        let text = String::from_utf8_lossy(&content);
-        let line_starts = build_line_starts(&text);
+        let notebook = notebook::is_notebook(rel_path)
+            .then(|| self.scan_notebook(rel_path, &text, deadline))
+            .flatten();
+        let findings = notebook
+            .unwrap_or_else(|| self.scan_decoded(rel_path, &content, &text, encoding, deadline));
+        self.record_file(rel_path, content.len() as u64, started);
+        Ok(self.apply_file_ignore(rel_path, findings, ignore))
+    }
+
+    fn record_file(&self, rel_path: &Path, bytes: u64, started: Instant) {
+        if let Some(stats) = &self.stats {
+            stats.record_file(rel_path, bytes, started.elapsed());
+        }
+    }
+
+    // Scans each cell on its own so matches see unescaped source and lines
+    // are reported relative to the cell.
+    fn scan_notebook(
+        &self,
+        rel_path: &Path,
+        text: &str,
+        deadline: &Deadline,
+    ) -> Option<Vec<Finding>> {
         let mut findings = Vec::new();
+        for segment in notebook::segments(text)? {
+            for mut finding in self.scan_text(rel_path, &segment.text, deadline) {
+                finding.cell = Some(NotebookCell {
+                    index: segment.cell,
+                    output: segment.output,
+                });
+                findings.push(finding);
+            }
+        }
+        Some(findings)
+    }
 
-        let candidate_rules = self.prefilter.candidates(&text);
-        for &rule_idx in &candidate_rules {
-            let rule = &self.rules[rule_idx];
-            if !rule.applies_to_path(rel_path) {
+    fn scan_sampled(
+        &self,
+        rel_path: &Path,
+        path: &Path,
+        len: u64,
+        sampling: &SampleOptions,
+        deadline: &Deadline,
+    ) -> Result<(Vec<Finding>, u64)> {
+        let mut findings = Vec::new();
+        let mut bytes = 0;
+        for window in sample::read_windows(path, len, sampling)? {
+            if window.bytes.contains(&0) {
                 continue;
             }
-            for caps in rule.regex.captures_iter(&text) {
-                let Some(matched) = caps.get(rule.rule.capture) else {
+            bytes += window.bytes.len() as u64;
+            let text = String::from_utf8_lossy(&window.bytes);
+            for mut finding in self.scan_text(rel_path, &text, deadline) {
+                finding.line += window.line_offset;
+                finding.end_line += window.line_offset;
+                finding.byte_offset += window.byte_offset;
+                finding.sampled = true;
+                findings.push(finding);
+            }
+        }
+        Ok((findings, bytes))
+    }
+
+    // Scans a mapped file chunk by chunk. Chunks are valid UTF-8 in the common
+    // case, so converting them borrows from the map instead of copying.
+    fn scan_chunked(
+        &self,
+        rel_path: &Path,
+        bytes: &[u8],
+        options: &ChunkOptions,
+        deadline: &Deadline,
+    ) -> (Vec<Finding>, Option<FileIgnore>) {
+        let mut findings = Vec::new();
+        let mut ignore = None;
+        let mut has_regions = false;
+        for (idx, chunk) in chunk::chunks(bytes, options.chunk_size, self.max_match_len)
+            .into_iter()
+            .enumerate()
+        {
+            let text = String::from_utf8_lossy(&bytes[chunk.range.clone()]);
+            if idx == 0 {
+                ignore = Filter::file_ignore(&text);
+                if self.skips_file(ignore.as_ref()) {
+                    return (Vec::new(), ignore);
+                }
+            }
+            has_regions |= text.contains(IGNORE_START_MARKER);
+            for mut finding in self.scan_text(rel_path, &text, deadline) {
+                finding.line += chunk.line_offset;
+                finding.end_line += chunk.line_offset;
+                finding.byte_offset += chunk.range.start;
+                if chunk.owned_lines.is_none_or(|owned| finding.line <= owned) {
+                    findings.push(finding);
+                }
+            }
+        }
+        // Ignore blocks may span chunks, so they are resolved on the whole file.
+        if has_regions {
+            let regions = Filter::ignored_regions(&String::from_utf8_lossy(bytes));
+            let in_region =
+                |finding: &Finding| regions.iter().any(|region| region.contains(&finding.line));
+            if self.options.keep_suppressed {
+                for finding in findings.iter_mut().filter(|finding| in_region(finding)) {
+                    finding
+                        .suppressed
+                        .get_or_insert(SuppressionReason::IgnoreBlock);
+                }
+            } else {
+                findings.retain(|finding| !in_region(finding));
+            }
+        }
+        (findings, ignore)
+    }
+
+    pub fn scan_content(&self, rel_path: &Path, text: &str) -> Vec<Finding> {
+        self.scan_spans(rel_path, text)
+            .into_iter()
+            .map(|span| span.finding)
+            .collect()
+    }
+
+    pub fn scan_spans(&self, rel_path: &Path, text: &str) -> Vec<SecretSpan> {
+        let ignored = self.filter.is_path_ignored(rel_path);
+        if ignored && !self.filter.protects_rules() {
+            return Vec::new();
+        }
+        if !self.options.keep_suppressed && self.filter.is_fixture_path(rel_path) {
+            return Vec::new();
+        }
+        let mut spans = match Filter::file_ignore(text) {
+            Some(ignore) if ignore.rules.is_empty() => Vec::new(),
+            Some(ignore) => self
+                .match_text(rel_path, text, &Deadline::unlimited())
+                .into_iter()
+                .filter(|span| {
+                    !ignore
+                        .rules
+                        .iter()
+                        .any(|rule| **rule == *span.finding.rule_id)
+                })
+                .collect(),
+            None => self.match_text(rel_path, text, &Deadline::unlimited()),
+        };
+        if ignored {
+            spans.retain(|span| self.filter.is_protected(&span.finding.rule_id));
+        }
+        self.mark_fixtures(rel_path, spans.iter_mut().map(|span| &mut span.finding));
+        spans
+    }
+
+    fn scan_text(&self, rel_path: &Path, text: &str, deadline: &Deadline) -> Vec<Finding> {
+        self.match_text(rel_path, text, deadline)
+            .into_iter()
+            .map(|span| span.finding)
+            .collect()
+    }
+
+    fn match_text(&self, rel_path: &Path, text: &str, deadline: &Deadline) -> Vec<SecretSpan> {
+        self.match_rules(rel_path, text, deadline, None)
+    }
+
+    // Lines and columns are those of the decoded `text`; byte offsets point
+    // into `content`.
+    fn scan_decoded(
+        &self,
+        rel_path: &Path,
+        content: &[u8],
+        text: &str,
+        encoding: Encoding,
+        deadline: &Deadline,
+    ) -> Vec<Finding> {
+        let mut findings = self.scan_text(rel_path, text, deadline);
+        if content.len() != text.len() {
+            for finding in &mut findings {
+                let end = finding.byte_offset + finding.match_length;
+                finding.byte_offset = encoding.file_offset(content, text, finding.byte_offset);
+                finding.match_length =
+                    encoding.file_offset(content, text, end) - finding.byte_offset;
+            }
+        }
+        findings
+    }
+
+    // A symbolic link that points outside the root is scanned as the line
+    // `symlink -> <target>`, by the rules with the `symlink-target` analyzer
+    // and no others.
+    fn scan_symlink(&self, rel_path: &Path, target: &Path, deadline: &Deadline) -> Vec<Finding> {
+        let text = format!("symlink -> {}", target.to_string_lossy());
+        self.scan_file_line(rel_path, &text, Analyzer::SymlinkTarget, deadline)
+    }
+
+    // A binary file is matched as the line `binary file: <path>`, by the
+    // rules with the `binary-file` analyzer and no others.
+    fn scan_binary(&self, rel_path: &Path, deadline: &Deadline) -> Vec<Finding> {
+        let text = format!("binary file: {}", normalize_path(rel_path));
+        self.scan_file_line(rel_path, &text, Analyzer::BinaryFile, deadline)
+    }
+
+    fn scan_file_line(
+        &self,
+        rel_path: &Path,
+        text: &str,
+        analyzer: Analyzer,
+        deadline: &Deadline,
+    ) -> Vec<Finding> {
+        self.match_rules(rel_path, text, deadline, Some(analyzer))
+            .into_iter()
+            .map(|span| Finding {
+                suggestion: None,
+                store_command: None,
+                ..span.finding
+            })
+            .collect()
+    }
+
+    fn match_rule(
+        &self,
+        rule_idx: usize,
+        rel_path: &Path,
+        input: &MatchInput<'_>,
+        deadline: &Deadline,
+        file_line: Option<Analyzer>,
+    ) -> RuleMatches {
+        let text = input.text;
+        let line_starts = &input.line_starts;
+        let ignored_regions = &input.ignored_regions;
+        let mut spans = Vec::new();
+        let rule = &self.rules[rule_idx];
+        if deadline.expired()
+            || rule.analyzer.filter(|analyzer| analyzer.describes_file()) != file_line
+        {
+            return (spans, None);
+        }
+        let rule_suppressed = if rule.applies_to_path(rel_path) {
+            self.rule_suppression(&rule.rule.id, rel_path)
+        } else {
+            Some(SuppressionReason::RulePathExclude)
+        };
+        if rule_suppressed.is_some() && !self.options.keep_suppressed {
+            return (spans, None);
+        }
+        let timed = self.stats.is_some() || tracing::enabled!(Level::DEBUG);
+        let started = timed.then(Instant::now);
+        let mut matches = 0;
+        let regions = rule.analyzer.and_then(|analyzer| analyzer.regions(text));
+        let expired_allowance = self.filter.expired_rule_allowance(&rule.rule.id, rel_path);
+        for caps in rule.regex.captures_iter(text) {
+            if deadline.expired() {
+                break;
+            }
+            matches += 1;
+            for target in &rule.captures {
+                let Some(matched) = caps.get(target.group) else {
                     continue;
                 };
-                let secret = matched.as_str();
-                if !validate_secret(&rule.rule.validate, rule.charset_regex.as_ref(), secret) {
+                if regions.as_ref().is_some_and(|regions| {
+                    !regions
+                        .iter()
+                        .any(|region| region.contains(&matched.start()))
+                }) {
                     continue;
                 }
-                if rule.is_allowed(secret) || self.filter.is_value_allowed(secret) {
+                let (start, secret) = match rule.analyzer {
+                    Some(analyzer) => {
+                        let Some(range) = analyzer.extract(matched.as_str()) else {
+                            continue;
+                        };
+                        (matched.start() + range.start, &matched.as_str()[range])
+                    }
+                    None => (matched.start(), matched.as_str()),
+                };
+                let (offset, secret) = trim_secret(secret, rule.rule.trim_chars());
+                if secret.is_empty() {
                     continue;
                 }
-                let (line, column) = line_col(&line_starts, matched.start());
-                let line_text = line_slice(&text, &line_starts, line);
-                if Filter::is_inline_ignored(line_text) {
+                let start = start + offset;
+                if let Some(context) = &rule.rule.context {
+                    let syntax = input.syntax.get_or_init(|| Syntax::parse(rel_path, text));
+                    if syntax
+                        .as_ref()
+                        .is_some_and(|syntax| !syntax.allows(context, start))
+                    {
+                        continue;
+                    }
+                }
+                if !validate_secret(&rule.rule.validate, rule.charset_regex.as_ref(), secret) {
                     continue;
                 }
-                let fingerprint = fingerprint_secret(secret);
-                if self.filter.is_fingerprint_ignored(&fingerprint, rel_path) {
+                let protected = self.filter.is_protected(&target.id);
+                let allowed_by = (!protected)
+                    .then(|| self.filter.value_allowance(secret, rel_path))
+                    .flatten();
+                // Symlink targets and binary files are paths, where words
+                // like `example` say nothing about the value.
+                let placeholder =
+                    file_line.is_none() && self.options.heuristics.is_placeholder(secret);
+                let downgrade = placeholder
+                    && self.options.heuristics.placeholders == PlaceholderAction::Downgrade;
+                let mut suppressed = rule_suppressed
+                    .or_else(|| self.rule_suppression(&target.id, rel_path))
+                    .or_else(|| {
+                        (rule.is_allowed(secret) || allowed_by.is_some())
+                            .then_some(SuppressionReason::AllowPattern)
+                    })
+                    .or_else(|| {
+                        (placeholder && !downgrade).then_some(SuppressionReason::Placeholder)
+                    });
+                if suppressed.is_some() && !self.options.keep_suppressed {
                     continue;
                 }
-                findings.push(Finding {
+                let line = line_number(line_starts, start);
+                let line_text = line_slice(text, line_starts, line);
+                suppressed =
+                    suppressed.or_else(|| line_suppression(line_text, line, ignored_regions));
+                if suppressed.is_some() && !self.options.keep_suppressed {
+                    continue;
+                }
+                let end = start + secret.len();
+                let end_line = line_number(line_starts, end.max(start + 1) - 1);
+                // A multi-line secret keeps its fingerprint when it is
+                // re-indented or its line endings change.
+                let fingerprint = if rule.rule.multiline {
+                    self.options
+                        .fingerprint
+                        .fingerprint(&secret.split_whitespace().collect::<String>())
+                } else {
+                    self.options.fingerprint.fingerprint(secret)
+                };
+                if suppressed.is_none() {
+                    if let Some(entry) =
+                        self.filter.fingerprint_ignore_entry(&fingerprint, rel_path)
+                    {
+                        if !self.options.keep_suppressed {
+                            continue;
+                        }
+                        suppressed = Some(fingerprint_ignore_reason(entry));
+                    }
+                }
+                let expired_ignore = expired_allowance.clone().or_else(|| {
+                    self.filter
+                        .expired_fingerprint_ignore(&fingerprint, rel_path)
+                        .map(str::to_string)
+                });
+                let jwt = (rule.analyzer == Some(Analyzer::Jwt))
+                    .then(|| jwt::parse(secret))
+                    .flatten()
+                    .map(|token| token.claims(unix_now()));
+                // An expired token grants nothing by itself.
+                let expired = jwt.as_ref().is_some_and(|claims| claims.expired);
+                let (entropy, mut confidence) = confidence::score(&rule.rule, secret, line_text);
+                if downgrade {
+                    confidence = confidence::round(confidence / 2.0);
+                }
+                if suppressed.is_none()
+                    && !protected
+                    && self
+                        .options
+                        .min_confidence
+                        .is_some_and(|min| confidence < min)
+                {
+                    if !self.options.keep_suppressed {
+                        continue;
+                    }
+                    suppressed = Some(SuppressionReason::LowConfidence);
+                }
+                let finding = Finding {
                     path: normalize_path(rel_path),
                     line,
-                    column,
-                    rule_id: rule.rule.id.clone(),
-                    rule_name: rule.rule.name.clone(),
-                    severity: rule.rule.severity,
+                    end_line,
+                    column: self.column(text, line_starts, line, start),
+                    end_column: self.column(text, line_starts, end_line, end),
+                    byte_offset: start,
+                    match_length: end - start,
+                    rule_id: Arc::clone(&target.id),
+                    rule_name: Arc::clone(&target.name),
+                    severity: if downgrade || expired {
+                        Severity::Low
+                    } else {
+                        rule.rule.severity
+                    },
                     fingerprint,
-                    preview: mask_secret(secret),
-                });
+                    preview: if end_line > line {
+                        format!(
+                            "{} ({} lines)",
+                            self.options.mask.mask(secret),
+                            end_line - line + 1
+                        )
+                    } else {
+                        self.options.mask.mask(secret)
+                    },
+                    entropy,
+                    confidence,
+                    sampled: false,
+                    cell: None,
+                    message: None,
+                    package: None,
+                    owners: Vec::new(),
+                    expired_ignore,
+                    suppressed_by: allowed_by
+                        .filter(|_| suppressed == Some(SuppressionReason::AllowPattern)),
+                    suppressed,
+                    suggestion: remediation::env_suggestion(
+                        rel_path,
+                        &text[line_starts[line - 1]..start],
+                        &target.id,
+                    ),
+                    store_command: self
+                        .options
+                        .recipes
+                        .command(&text[line_starts[line - 1]..start], &target.id),
+                    blame: None,
+                    jwt,
+                    description: rule.description.clone(),
+                    docs_url: rule.docs_url.clone(),
+                    remediation: rule.remediation.clone(),
+                };
+                spans.push((
+                    rule_idx,
+                    SecretSpan {
+                        finding,
+                        range: start..start + secret.len(),
+                    },
+                ));
+            }
+        }
+        let sample = started.map(|started| {
+            let elapsed = started.elapsed();
+            let findings = spans.len();
+            debug!(
+                path = %rel_path.display(),
+                rule = %rule.rule.id,
+                matches,
+                findings,
+                elapsed_us = elapsed.as_micros() as u64,
+                "rule timing"
+            );
+            (
+                rule_idx,
+                RuleSample {
+                    matches,
+                    findings,
+                    elapsed,
+                },
+            )
+        });
+        (spans, sample)
+    }
+
+    fn stops_at(&self, finding: &Finding) -> bool {
+        self.options.stop_at.is_some_and(|fail_on| {
+            finding.suppressed.is_none() && finding.severity.blocks_at(fail_on)
+        })
+    }
+
+    fn match_rules(
+        &self,
+        rel_path: &Path,
+        text: &str,
+        deadline: &Deadline,
+        file_line: Option<Analyzer>,
+    ) -> Vec<SecretSpan> {
+        let input = MatchInput {
+            text,
+            line_starts: build_line_starts(text),
+            ignored_regions: Filter::ignored_regions(text),
+            syntax: OnceLock::new(),
+        };
+        let mut candidate_rules = self.prefilter.candidates(text);
+        if let Some(severity) = self.options.rule_severity {
+            candidate_rules.retain(|&idx| self.rules[idx].rule.severity <= severity);
+        }
+        if self.options.stop_at.is_some() {
+            candidate_rules.sort_by_key(|&idx| {
+                let rule = &self.rules[idx].rule;
+                (rule.severity, Reverse(rule.specificity()), idx)
+            });
+        }
+        let blocked = AtomicBool::new(false);
+        let match_rule = |&rule_idx: &usize| {
+            if blocked.load(Ordering::Relaxed) {
+                return (Vec::new(), None);
+            }
+            let matches = self.match_rule(rule_idx, rel_path, &input, deadline, file_line);
+            if matches
+                .0
+                .iter()
+                .any(|(_, span)| self.stops_at(&span.finding))
+            {
+                blocked.store(true, Ordering::Relaxed);
+            }
+            matches
+        };
+        // A huge file would otherwise keep one core busy with every rule
+        // while the rest of the scan has finished.
+        let per_rule: Vec<RuleMatches> = if self
+            .options
+            .parallel_rules_threshold
+            .is_some_and(|threshold| text.len() as u64 >= threshold)
+        {
+            candidate_rules.par_iter().map(match_rule).collect()
+        } else {
+            candidate_rules.iter().map(match_rule).collect()
+        };
+        let mut spans = Vec::new();
+        let mut samples = Vec::new();
+        for (rule_spans, sample) in per_rule {
+            spans.extend(rule_spans);
+            samples.extend(sample);
+        }
+        if let Some(stats) = &self.stats {
+            stats.record_text(samples);
+        }
+        let blocked = blocked.into_inner();
+        if kubernetes::is_manifest(rel_path) && !blocked {
+            spans.extend(self.match_secret_data(
+                rel_path,
+                text,
+                &input.line_starts,
+                &input.ignored_regions,
+                deadline,
+            ));
+        }
+        // Detector spans are numbered after the rules for overlap resolution.
+        if file_line.is_none() && !blocked {
+            for (idx, detector) in self.secret_detectors.iter().enumerate() {
+                if deadline.expired() {
+                    break;
+                }
+                let detections = detector.detect(rel_path, text);
+                debug!(path = %rel_path.display(), detector = detector.name(), detections = detections.len(), "secret detector");
+                spans.extend(
+                    detections
+                        .into_iter()
+                        .filter_map(|detection| {
+                            self.detection_span(
+                                rel_path,
+                                text,
+                                &input.line_starts,
+                                &input.ignored_regions,
+                                detection,
+                            )
+                        })
+                        .map(|span| (self.rules.len() + idx, span)),
+                );
+            }
+        }
+        if self.options.resolve_overlaps {
+            self.resolve_overlaps(spans)
+        } else {
+            spans.into_iter().map(|(_, span)| span).collect()
+        }
+    }
+
+    // `data:` values of Kubernetes Secrets are base64; each is decoded and
+    // scanned on its own, and findings point at the encoded value.
+    fn match_secret_data(
+        &self,
+        rel_path: &Path,
+        text: &str,
+        line_starts: &[usize],
+        ignored_regions: &[RangeInclusive<usize>],
+        deadline: &Deadline,
+    ) -> Vec<(usize, SecretSpan)> {
+        let mut spans = Vec::new();
+        for (range, decoded) in kubernetes::secret_fields(text).data {
+            let line = line_number(line_starts, range.start);
+            let suppressed =
+                line_suppression(line_slice(text, line_starts, line), line, ignored_regions);
+            if suppressed.is_some() && !self.options.keep_suppressed {
+                continue;
+            }
+            for mut span in self.match_text(rel_path, &decoded, deadline) {
+                let Some(rule_idx) = self.rules.iter().position(|rule| {
+                    rule.captures
+                        .iter()
+                        .any(|target| target.id == span.finding.rule_id)
+                }) else {
+                    continue;
+                };
+                span.finding.line = line;
+                span.finding.end_line = line;
+                span.finding.column = self.column(text, line_starts, line, range.start);
+                span.finding.end_column = self.column(text, line_starts, line, range.end);
+                span.finding.byte_offset = range.start;
+                span.finding.match_length = range.len();
+                span.finding.preview = format!("{} (base64-decoded)", span.finding.preview);
+                // The secret is already kept in a Kubernetes Secret.
+                span.finding.suggestion = None;
+                if suppressed.is_some() {
+                    span.finding.suppressed = suppressed;
+                    span.finding.suppressed_by = None;
+                }
+                span.range = range.clone();
+                spans.push((rule_idx, span));
+            }
+        }
+        spans
+    }
+
+    // A span for a SecretDetector's detection, suppressed, fingerprinted and
+    // masked like a rule match. Ranges outside `text` are dropped.
+    fn detection_span(
+        &self,
+        rel_path: &Path,
+        text: &str,
+        line_starts: &[usize],
+        ignored_regions: &[RangeInclusive<usize>],
+        detection: Detection,
+    ) -> Option<SecretSpan> {
+        let range = detection.range;
+        let secret = text
+            .get(range.clone())
+            .filter(|secret| !secret.is_empty())?;
+        let line = line_number(line_starts, range.start);
+        let end_line = line_number(line_starts, range.end - 1);
+        let line_text = line_slice(text, line_starts, line);
+        let protected = self.filter.is_protected(&detection.rule_id);
+        let allowed_by = (!protected)
+            .then(|| self.filter.value_allowance(secret, rel_path))
+            .flatten();
+        let placeholder = self.options.heuristics.is_placeholder(secret);
+        let downgrade =
+            placeholder && self.options.heuristics.placeholders == PlaceholderAction::Downgrade;
+        let fingerprint = self.options.fingerprint.fingerprint(secret);
+        let confidence = if downgrade {
+            confidence::round(detection.confidence / 2.0)
+        } else {
+            detection.confidence
+        };
+        let suppressed = self
+            .rule_suppression(&detection.rule_id, rel_path)
+            .or_else(|| {
+                allowed_by
+                    .is_some()
+                    .then_some(SuppressionReason::AllowPattern)
+            })
+            .or_else(|| (placeholder && !downgrade).then_some(SuppressionReason::Placeholder))
+            .or_else(|| line_suppression(line_text, line, ignored_regions))
+            .or_else(|| {
+                self.filter
+                    .fingerprint_ignore_entry(&fingerprint, rel_path)
+                    .map(fingerprint_ignore_reason)
+            })
+            .or_else(|| {
+                (!protected
+                    && self
+                        .options
+                        .min_confidence
+                        .is_some_and(|min| confidence < min))
+                .then_some(SuppressionReason::LowConfidence)
+            });
+        if suppressed.is_some() && !self.options.keep_suppressed {
+            return None;
+        }
+        let expired_ignore = self
+            .filter
+            .expired_rule_allowance(&detection.rule_id, rel_path)
+            .or_else(|| {
+                self.filter
+                    .expired_fingerprint_ignore(&fingerprint, rel_path)
+                    .map(str::to_string)
+            });
+        let finding = Finding {
+            path: normalize_path(rel_path),
+            line,
+            end_line,
+            column: self.column(text, line_starts, line, range.start),
+            end_column: self.column(text, line_starts, end_line, range.end),
+            byte_offset: range.start,
+            match_length: range.len(),
+            rule_id: detection.rule_id.into(),
+            rule_name: detection.rule_name.into(),
+            severity: if downgrade {
+                Severity::Low
+            } else {
+                detection.severity
+            },
+            fingerprint,
+            preview: self.options.mask.mask(secret),
+            entropy: confidence::round(shannon_entropy(secret)),
+            confidence,
+            sampled: false,
+            cell: None,
+            message: None,
+            package: None,
+            owners: Vec::new(),
+            expired_ignore,
+            suppressed_by: allowed_by
+                .filter(|_| suppressed == Some(SuppressionReason::AllowPattern)),
+            suppressed,
+            suggestion: None,
+            store_command: None,
+            blame: None,
+            jwt: None,
+            description: None,
+            docs_url: None,
+            remediation: None,
+        };
+        Some(SecretSpan { finding, range })
+    }
+
+    // 1-based column of `index` on `line`, in the `[report] columns` unit.
+    fn column(&self, text: &str, line_starts: &[usize], line: usize, index: usize) -> usize {
+        self.options
+            .columns
+            .width(&text[line_starts[line - 1]..index])
+            + 1
+    }
+
+    // Several rules can match the same secret, e.g. a provider rule and a
+    // generic assignment rule. Of overlapping matches only the one with the
+    // highest severity is kept, then the most specific rule, then the rule
+    // listed first.
+    fn resolve_overlaps(&self, mut spans: Vec<(usize, SecretSpan)>) -> Vec<SecretSpan> {
+        spans.sort_by_key(|(_, span)| (span.range.start, span.range.end));
+        let mut resolved = Vec::with_capacity(spans.len());
+        let mut cluster = Vec::new();
+        let mut cluster_end = 0;
+        for (rule_idx, span) in spans {
+            if span.range.start >= cluster_end {
+                self.keep_strongest(&mut cluster, &mut resolved);
+            }
+            cluster_end = cluster_end.max(span.range.end);
+            cluster.push((rule_idx, span));
+        }
+        self.keep_strongest(&mut cluster, &mut resolved);
+        resolved.sort_by_key(|span| span.range.start);
+        resolved
+    }
+
+    fn keep_strongest(
+        &self,
+        cluster: &mut Vec<(usize, SecretSpan)>,
+        resolved: &mut Vec<SecretSpan>,
+    ) {
+        // Detections are checked by code, as specific as a rule can be.
+        cluster.sort_by_key(|(rule_idx, span)| match self.rules.get(*rule_idx) {
+            Some(rule) => (
+                rule.rule.severity,
+                Reverse(rule.rule.specificity()),
+                *rule_idx,
+            ),
+            None => (span.finding.severity, Reverse(u8::MAX), *rule_idx),
+        });
+        let kept = resolved.len();
+        for (_, mut span) in cluster.drain(..) {
+            // Suppressed spans would have been dropped before, so they never
+            // win against a reported one.
+            if span.finding.suppressed.is_some() {
+                resolved.push(span);
+                continue;
+            }
+            let overlaps = resolved[kept..].iter().any(|other: &SecretSpan| {
+                other.finding.suppressed.is_none()
+                    && span.range.start < other.range.end
+                    && other.range.start < span.range.end
+            });
+            if !overlaps {
+                resolved.push(span);
+            } else if self.options.keep_suppressed {
+                span.finding.suppressed = Some(SuppressionReason::Overlap);
+                resolved.push(span);
             }
         }
-        Ok(findings)
+    }
+
+    // Drops findings of rules excluded by an `@nosecrets-ignore-file` directive
+    // and records the exclusion for the skip report.
+    fn apply_file_ignore(
+        &self,
+        rel_path: &Path,
+        mut findings: Vec<Finding>,
+        ignore: Option<FileIgnore>,
+    ) -> (Vec<Finding>, Option<SkippedFile>) {
+        let Some(ignore) = ignore else {
+            return (findings, None);
+        };
+        // A rule id also covers the `<id>.<group>` ids of its captures.
+        let excluded = |finding: &Finding| {
+            ignore.rules.is_empty()
+                || ignore.rules.iter().any(|rule| {
+                    finding
+                        .rule_id
+                        .strip_prefix(rule.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+                })
+        };
+        if self.options.keep_suppressed {
+            for finding in findings.iter_mut().filter(|finding| excluded(finding)) {
+                finding.suppressed = Some(SuppressionReason::FileIgnore);
+            }
+        } else {
+            findings.retain(|finding| !excluded(finding));
+        }
+        let skipped = SkippedFile {
+            path: normalize_path(rel_path),
+            rules: ignore.rules,
+            reason: ignore.reason,
+        };
+        info!(
+            path = %skipped.path,
+            rules = %skipped.rules.join(","),
+            reason = %skipped.reason,
+            "skipped: @nosecrets-ignore-file"
+        );
+        (findings, Some(skipped))
+    }
+
+    // Whether an `@nosecrets-ignore-file` directive without rules makes
+    // scanning the file pointless.
+    fn skips_file(&self, ignore: Option<&FileIgnore>) -> bool {
+        !self.options.keep_suppressed && ignore.is_some_and(|ignore| ignore.rules.is_empty())
+    }
+
+    fn rule_suppression(&self, rule_id: &str, path: &Path) -> Option<SuppressionReason> {
+        if self.filter.rule_ignore_entry(rule_id, path).is_some() {
+            Some(SuppressionReason::RuleIgnore)
+        } else if self.filter.is_rule_allowed(rule_id, path) {
+            Some(SuppressionReason::AllowRule)
+        } else {
+            None
+        }
+    }
+}
+
+// `secret` without the leading and trailing `chars`, and where it starts.
+fn trim_secret<'a>(secret: &'a str, chars: &str) -> (usize, &'a str) {
+    let trimmed = secret.trim_start_matches(|ch| chars.contains(ch));
+    let offset = secret.len() - trimmed.len();
+    (offset, trimmed.trim_end_matches(|ch| chars.contains(ch)))
+}
+
+fn line_suppression(
+    line_text: &str,
+    line: usize,
+    ignored_regions: &[RangeInclusive<usize>],
+) -> Option<SuppressionReason> {
+    if Filter::is_inline_ignored(line_text) {
+        Some(SuppressionReason::InlineIgnore)
+    } else if ignored_regions.iter().any(|region| region.contains(&line)) {
+        Some(SuppressionReason::IgnoreBlock)
+    } else {
+        None
     }
 }
 
 impl CompiledRule {
+    fn compile(rule: Rule) -> Result<Self> {
+        let regex = RegexBuilder::new(&rule.pattern)
+            .dot_matches_new_line(rule.multiline)
+            .build()
+            .map_err(|source| ScanError::Regex {
+                rule: rule.id.clone(),
+                source,
+            })?;
+        let hir = regex_syntax::ParserBuilder::new()
+            .dot_matches_new_line(rule.multiline)
+            .build()
+            .parse(&rule.pattern)
+            .ok();
+        let max_match_len = hir
+            .as_ref()
+            .and_then(|hir| hir.properties().maximum_len())
+            .map_or(chunk::UNBOUNDED_MATCH_LEN, |len| {
+                len.min(chunk::UNBOUNDED_MATCH_LEN)
+            });
+        let keywords = match &hir {
+            Some(hir) if rule.keywords.is_empty() => keywords::derive(hir),
+            _ => rule.keywords.clone(),
+        };
+        let (allow_patterns, allow_values) = compile_rule_allow(&rule.id, rule.allow.as_ref())?;
+        let (include_paths, exclude_paths) = compile_rule_paths(&rule.id, rule.paths.as_ref())?;
+        let charset_regex = compile_charset(&rule.id, rule.validate.as_ref())?;
+        if let Some(min) = rule
+            .validate
+            .as_ref()
+            .and_then(|validate| validate.min_entropy)
+        {
+            if !min.is_finite() || min < 0.0 {
+                return Err(ScanError::Rule {
+                    rule: rule.id.clone(),
+                    message: format!("validate.min_entropy must not be negative, got {min}"),
+                });
+            }
+        }
+        let analyzer = rule
+            .analyzer
+            .as_deref()
+            .map(|name| {
+                Analyzer::from_name(name).ok_or_else(|| ScanError::Rule {
+                    rule: rule.id.clone(),
+                    message: format!("unknown analyzer {name}"),
+                })
+            })
+            .transpose()?;
+        let captures = compile_captures(&rule, &regex)?;
+        Ok(Self {
+            description: rule.description.as_deref().map(Arc::from),
+            docs_url: rule.docs_url.as_deref().map(Arc::from),
+            remediation: rule.remediation.as_deref().map(Arc::from),
+            captures,
+            rule,
+            regex,
+            allow_patterns,
+            allow_values,
+            include_paths,
+            exclude_paths,
+            charset_regex,
+            analyzer,
+            max_match_len,
+            keywords,
+        })
+    }
+
     fn applies_to_path(&self, path: &Path) -> bool {
         let normalized = normalize_path(path);
         if let Some(include) = &self.include_paths {
@@ -164,11 +1891,11 @@ impl Prefilter {
         let mut keyword_map: HashMap<String, Vec<usize>> = HashMap::new();
         let mut always_rules = Vec::new();
         for (idx, rule) in rules.iter().enumerate() {
-            if rule.rule.keywords.is_empty() {
+            if rule.keywords.is_empty() {
                 always_rules.push(idx);
                 continue;
             }
-            for keyword in &rule.rule.keywords {
+            for keyword in &rule.keywords {
                 keyword_map
                     .entry(keyword.to_lowercase())
                     .or_default()
@@ -213,47 +1940,90 @@ impl Prefilter {
     }
 }
 
-fn compile_rule_allow(allow: Option<&RuleAllow>) -> Result<(Vec<Regex>, HashSet<String>)> {
+fn compile_rule_allow(
+    id: &str,
+    allow: Option<&RuleAllow>,
+) -> Result<(Vec<Regex>, HashSet<String>)> {
     let Some(allow) = allow else {
         return Ok((Vec::new(), HashSet::new()));
     };
     let mut patterns = Vec::new();
     for pattern in &allow.patterns {
-        patterns.push(
-            Regex::new(pattern)
-                .with_context(|| format!("invalid allow regex pattern {pattern}"))?,
-        );
+        patterns.push(Regex::new(pattern).map_err(|source| ScanError::Regex {
+            rule: id.to_string(),
+            source,
+        })?);
     }
     let values = allow.values.iter().cloned().collect();
     Ok((patterns, values))
 }
 
-fn compile_rule_paths(paths: Option<&RulePaths>) -> Result<(Option<GlobSet>, Option<GlobSet>)> {
+fn compile_rule_paths(
+    id: &str,
+    paths: Option<&RulePaths>,
+) -> Result<(Option<GlobSet>, Option<GlobSet>)> {
     let Some(paths) = paths else {
         return Ok((None, None));
     };
-    let include = build_globset(&paths.include)?;
-    let exclude = build_globset(&paths.exclude)?;
+    let include = build_globset(id, &paths.include)?;
+    let exclude = build_globset(id, &paths.exclude)?;
     Ok((include, exclude))
 }
 
-fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>> {
+fn compile_captures(rule: &Rule, regex: &Regex) -> Result<Vec<CaptureTarget>> {
+    let missing = |message: String| ScanError::Rule {
+        rule: rule.id.clone(),
+        message,
+    };
+    let group = |capture: &Capture| match capture {
+        Capture::Index(index) if *index < regex.captures_len() => Ok(*index),
+        Capture::Name(name) => regex
+            .capture_names()
+            .position(|group| group == Some(name.as_str()))
+            .ok_or_else(|| missing(format!("no capture group named {name}"))),
+        Capture::Index(index) => Err(missing(format!("no capture group {index}"))),
+    };
+    if rule.captures.is_empty() {
+        return Ok(vec![CaptureTarget {
+            group: group(&rule.capture)?,
+            id: Arc::from(rule.id.as_str()),
+            name: Arc::from(rule.name.as_str()),
+        }]);
+    }
+    rule.captures
+        .iter()
+        .map(|capture| {
+            Ok(CaptureTarget {
+                group: group(capture)?,
+                id: Arc::from(format!("{}.{capture}", rule.id)),
+                name: Arc::from(format!("{} ({capture})", rule.name)),
+            })
+        })
+        .collect()
+}
+
+fn build_globset(id: &str, patterns: &[String]) -> Result<Option<GlobSet>> {
     if patterns.is_empty() {
         return Ok(None);
     }
+    let invalid = |pattern: String, source| ScanError::Glob {
+        rule: id.to_string(),
+        pattern,
+        source,
+    };
     let mut builder = GlobSetBuilder::new();
     for pattern in patterns {
-        let normalized = normalize_glob_pattern(pattern);
-        let glob =
-            Glob::new(&normalized).with_context(|| format!("invalid glob pattern {normalized}"))?;
+        let glob = path_glob(pattern)
+            .map_err(|source| invalid(normalize_glob_pattern(pattern), source))?;
         builder.add(glob);
     }
-    Ok(Some(
-        builder.build().with_context(|| "failed to build globset")?,
-    ))
+    let set = builder
+        .build()
+        .map_err(|source| invalid(patterns.join(", "), source))?;
+    Ok(Some(set))
 }
 
-fn compile_charset(validate: Option<&RuleValidate>) -> Result<Option<Regex>> {
+fn compile_charset(id: &str, validate: Option<&RuleValidate>) -> Result<Option<Regex>> {
     let Some(validate) = validate else {
         return Ok(None);
     };
@@ -261,9 +2031,25 @@ fn compile_charset(validate: Option<&RuleValidate>) -> Result<Option<Regex>> {
         return Ok(None);
     };
     let pattern = format!("^[{}]+$", charset);
-    Ok(Some(Regex::new(&pattern).with_context(|| {
-        format!("invalid charset regex {pattern}")
-    })?))
+    let regex = Regex::new(&pattern).map_err(|source| ScanError::Regex {
+        rule: id.to_string(),
+        source,
+    })?;
+    Ok(Some(regex))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+fn fingerprint_ignore_reason(entry: &IgnoreEntry) -> SuppressionReason {
+    if entry.is_baseline() {
+        SuppressionReason::Baseline
+    } else {
+        SuppressionReason::FingerprintIgnore
+    }
 }
 
 fn validate_secret(validate: &Option<RuleValidate>, charset: Option<&Regex>, secret: &str) -> bool {
@@ -298,81 +2084,14 @@ fn validate_secret(validate: &Option<RuleValidate>, charset: Option<&Regex>, sec
             return false;
         }
     }
-    true
-}
-
-pub fn collect_files(root: &Path, inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
-    let mut seen = HashSet::new();
-    let targets: Vec<PathBuf> = if inputs.is_empty() {
-        vec![root.to_path_buf()]
-    } else {
-        inputs.to_vec()
-    };
-
-    for target in targets {
-        let target = if target.is_absolute() {
-            target
-        } else {
-            root.join(target)
-        };
-        if target.is_file() {
-            if seen.insert(target.clone()) {
-                files.push(target);
-            }
-            continue;
-        }
-        if target.is_dir() {
-            for entry in walkdir::WalkDir::new(&target)
-                .follow_links(false)
-                .into_iter()
-                .filter_map(Result::ok)
-            {
-                if entry.file_type().is_file() {
-                    let path = entry.path().to_path_buf();
-                    if seen.insert(path.clone()) {
-                        files.push(path);
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(files)
-}
-
-pub fn discover_repo_root(start: &Path) -> Result<Option<PathBuf>> {
-    match gix::discover(start) {
-        Ok(repo) => Ok(repo.work_dir().map(|path| path.to_path_buf())),
-        Err(_) => Ok(None),
-    }
-}
-
-pub fn collect_staged_files(repo_root: &Path) -> Result<Vec<PathBuf>> {
-    let output = Command::new("git")
-        .arg("-C")
-        .arg(repo_root)
-        .args(["diff", "--name-only", "--cached", "--diff-filter=ACM"])
-        .output()
-        .with_context(|| "failed to execute git")?;
-
-    if !output.status.success() {
-        return Err(anyhow!(
-            "git diff --name-only --cached failed with status {}",
-            output.status
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut files = Vec::new();
-    for line in stdout.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+    if let Some(min) = validate.min_entropy {
+        if shannon_entropy(secret) < min {
+            return false;
         }
-        files.push(repo_root.join(trimmed));
     }
-    Ok(files)
+    validate
+        .algorithm
+        .is_none_or(|algorithm| algorithm::is_valid(algorithm, secret, &validate.prefix))
 }
 
 fn build_line_starts(text: &str) -> Vec<usize> {
@@ -385,15 +2104,11 @@ fn build_line_starts(text: &str) -> Vec<usize> {
     starts
 }
 
-fn line_col(line_starts: &[usize], index: usize) -> (usize, usize) {
-    let line_idx = match line_starts.binary_search(&index) {
-        Ok(idx) => idx,
-        Err(idx) => idx.saturating_sub(1),
-    };
-    let line = line_idx + 1;
-    let start = line_starts.get(line_idx).copied().unwrap_or(0);
-    let column = index.saturating_sub(start) + 1;
-    (line, column)
+fn line_number(line_starts: &[usize], index: usize) -> usize {
+    match line_starts.binary_search(&index) {
+        Ok(idx) => idx + 1,
+        Err(idx) => idx.max(1),
+    }
 }
 
 fn line_slice<'a>(text: &'a str, line_starts: &[usize], line: usize) -> &'a str {
@@ -410,14 +2125,6 @@ fn line_slice<'a>(text: &'a str, line_starts: &[usize], line: usize) -> &'a str
     text.get(start..end).unwrap_or("")
 }
 
-fn normalize_glob_pattern(pattern: &str) -> String {
-    let mut normalized = pattern.replace('\\', "/");
-    if normalized.ends_with('/') {
-        normalized.push_str("**");
-    }
-    normalized
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -433,10 +2140,19 @@ mod tests {
             severity: Severity::High,
             pattern: pattern.to_string(),
             keywords: vec!["secret_".to_string()],
-            capture: 1,
+            capture: Capture::Index(1),
+            captures: Vec::new(),
             validate: None,
             paths: None,
             allow: None,
+            analyzer: None,
+            context: None,
+            multiline: false,
+            trim: None,
+            description: None,
+            docs_url: None,
+            remediation: None,
+            tests: Vec::new(),
         }
     }
 
@@ -465,6 +2181,61 @@ mod tests {
         assert_eq!(finding.column, expected_col);
     }
 
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        let text = "x\n// Schlüssel 🔑: secret_ABC123\n";
+        let rule = base_rule(r"(secret_[A-Z0-9]{6})");
+        let columns = |columns| {
+            let filter = Filter::from_config(None, Vec::new()).expect("filter");
+            let detector = Detector::new(vec![rule.clone()], filter)
+                .expect("detector")
+                .with_options(ScanOptions {
+                    columns,
+                    ..ScanOptions::default()
+                });
+            let finding = detector
+                .scan_content(Path::new("notes.txt"), text)
+                .remove(0);
+            assert_eq!(finding.byte_offset, text.find("secret_").unwrap());
+            assert_eq!(finding.match_length, "secret_ABC123".len());
+            (finding.column, finding.end_column)
+        };
+        assert_eq!(columns(ColumnUnit::Chars), (17, 30));
+        assert_eq!(columns(ColumnUnit::Utf16), (18, 31));
+        assert_eq!(columns(ColumnUnit::Bytes), (21, 34));
+    }
+
+    #[test]
+    fn named_and_multiple_captures() {
+        let filter = || Filter::from_config(None, Vec::new()).expect("filter");
+        let text = "url = secret_db://admin:Zq8vLw3xR2@db\n";
+        let pattern = r"secret_db://(?P<user>\w+):(?P<password>\w+)@";
+
+        let mut named = base_rule(pattern);
+        named.capture = Capture::Name("password".to_string());
+        let detector = Detector::new(vec![named], filter()).expect("detector");
+        let spans = detector.scan_spans(Path::new("a.txt"), text);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(&text[spans[0].range.clone()], "Zq8vLw3xR2");
+
+        let mut multiple = base_rule(pattern);
+        multiple.captures = vec![
+            Capture::Name("user".to_string()),
+            Capture::Name("password".to_string()),
+        ];
+        let detector = Detector::new(vec![multiple], filter()).expect("detector");
+        let ids: Vec<String> = detector
+            .scan_content(Path::new("a.txt"), text)
+            .iter()
+            .map(|finding| finding.rule_id.to_string())
+            .collect();
+        assert_eq!(ids, vec!["test-rule.user", "test-rule.password"]);
+
+        let mut missing = base_rule(pattern);
+        missing.capture = Capture::Name("token".to_string());
+        assert!(Detector::new(vec![missing], filter()).is_err());
+    }
+
     #[test]
     fn inline_ignore_skips_finding() {
         let dir = tempdir().expect("tempdir");
@@ -540,4 +2311,280 @@ mod tests {
         let findings = detector.scan_files(root, &[path]).expect("scan");
         assert!(findings.is_empty());
     }
+
+    #[test]
+    fn scan_paths_with_streams_findings() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let files: Vec<PathBuf> = (0..8)
+            .map(|idx| {
+                let path = root.join(format!("file{idx}.txt"));
+                fs::write(&path, format!("secret_ABC12{idx}\nsecret_XYZ98{idx}")).expect("write");
+                path
+            })
+            .collect();
+        let rule = base_rule(r"(secret_[A-Z0-9]{6})");
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![rule], filter).expect("detector");
+
+        let streamed = std::sync::Mutex::new(Vec::new());
+        let outcome = detector.scan_paths_with(root, &files, |finding| {
+            streamed.lock().expect("lock").push(finding.fingerprint);
+        });
+        assert!(outcome.findings.is_empty());
+        assert_eq!(outcome.files_scanned, 8);
+
+        let mut streamed = streamed.into_inner().expect("lock");
+        let mut collected: Vec<String> = detector
+            .scan_files(root, &files)
+            .expect("scan")
+            .into_iter()
+            .map(|finding| finding.fingerprint)
+            .collect();
+        streamed.sort();
+        collected.sort();
+        assert_eq!(streamed.len(), 16);
+        assert_eq!(streamed, collected);
+    }
+
+    #[test]
+    fn scan_paths_reports_unreadable_files() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let present = root.join("present.txt");
+        fs::write(&present, "secret_ABC123").expect("write file");
+        let missing = root.join("missing.txt");
+
+        let rule = base_rule(r"(secret_[A-Z0-9]{6})");
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![rule], filter).expect("detector");
+
+        let outcome = detector.scan_paths(root, &[present, missing.clone()]);
+        assert_eq!(outcome.findings.len(), 1);
+        assert_eq!(outcome.files_scanned, 1);
+        assert_eq!(outcome.failures.len(), 1);
+        assert_eq!(outcome.failures[0].path, missing);
+        assert!(detector.scan_files(root, &[missing]).is_err());
+    }
+
+    #[test]
+    fn collects_per_rule_stats() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let file = root.join("a.txt");
+        fs::write(&file, "secret_ABC123\nsecret_DEF456\n").expect("write file");
+
+        let mut other = base_rule(r"(token_[a-z]{6})");
+        other.id = "other".to_string();
+        other.keywords = vec!["token_".to_string()];
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![base_rule(r"(secret_[A-Z0-9]{6})"), other], filter)
+            .expect("detector")
+            .with_options(ScanOptions {
+                collect_stats: true,
+                ..ScanOptions::default()
+            });
+        detector.scan_paths(root, &[file]);
+
+        let stats = detector.stats().expect("stats");
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.bytes_scanned, 28);
+        let rule = |id: &str| {
+            stats
+                .rules
+                .iter()
+                .find(|rule| rule.rule_id == id)
+                .expect(id)
+        };
+        assert_eq!(rule("test-rule").matches, 2);
+        assert_eq!(rule("test-rule").findings, 2);
+        assert_eq!(rule("other").prefilter_hits, 0);
+        assert_eq!(stats.slowest_files[0].path, "a.txt");
+    }
+
+    #[test]
+    fn samples_large_files_with_exact_lines() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let path = root.join("huge.log");
+        let mut content: String = (0..2000).map(|n| format!("filler line {n}\n")).collect();
+        content.push_str("token secret_ABC123\n");
+        fs::write(&path, &content).expect("write file");
+
+        let rule = base_rule(r"(secret_[A-Z0-9]{6})");
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![rule.clone()], filter).expect("detector");
+        let skipping = detector.with_options(ScanOptions {
+            max_file_size: Some(1024),
+            ..ScanOptions::default()
+        });
+        assert!(skipping
+            .scan_files(root, std::slice::from_ref(&path))
+            .expect("scan")
+            .is_empty());
+
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let sampling = Detector::new(vec![rule], filter)
+            .expect("detector")
+            .with_options(ScanOptions {
+                max_file_size: Some(1024),
+                sampling: Some(SampleOptions {
+                    window_size: 512,
+                    random_windows: 1,
+                }),
+                ..ScanOptions::default()
+            });
+        let findings = sampling.scan_files(root, &[path]).expect("scan");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line, 2001);
+        assert_eq!(findings[0].column, 7);
+        assert!(findings[0].sampled);
+    }
+
+    #[test]
+    fn chunked_scan_matches_whole_file_scan() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let path = root.join("big.log");
+        let content: String = (1..=300)
+            .map(|n| match n {
+                100 => "# @nosecrets-ignore-start\n".to_string(),
+                140 => "# @nosecrets-ignore-end\n".to_string(),
+                n if n % 37 == 0 => format!("token secret_ABC{n:03}\n"),
+                n => format!("filler line {n}\n"),
+            })
+            .collect();
+        fs::write(&path, &content).expect("write file");
+
+        let scan = |chunking| {
+            let filter = Filter::from_config(None, Vec::new()).expect("filter");
+            let detector = Detector::new(vec![base_rule(r"(secret_[A-Z0-9]{6})")], filter)
+                .expect("detector")
+                .with_options(ScanOptions {
+                    chunking,
+                    ..ScanOptions::default()
+                });
+            let mut lines: Vec<usize> = detector
+                .scan_files(root, std::slice::from_ref(&path))
+                .expect("scan")
+                .iter()
+                .map(|finding| finding.line)
+                .collect();
+            lines.sort_unstable();
+            lines
+        };
+        let whole = scan(None);
+        assert_eq!(whole, vec![37, 74, 148, 185, 222, 259, 296]);
+        let chunked = scan(Some(ChunkOptions {
+            threshold: 0,
+            chunk_size: 256,
+        }));
+        assert_eq!(chunked, whole);
+    }
+
+    #[test]
+    fn rules_matched_in_parallel_find_the_same_secrets() {
+        let rules: Vec<Rule> = (0..8)
+            .map(|n| Rule {
+                id: format!("rule-{n}"),
+                ..base_rule(&format!(r"(secret_{n}[A-Z0-9]{{6}})"))
+            })
+            .collect();
+        let content: String = (1..=200)
+            .map(|line| format!("key = secret_{}ABC{line:03}\n", line % 8))
+            .collect();
+
+        let scan = |parallel_rules_threshold| {
+            let filter = Filter::from_config(None, Vec::new()).expect("filter");
+            Detector::new(rules.clone(), filter)
+                .expect("detector")
+                .with_options(ScanOptions {
+                    parallel_rules_threshold,
+                    ..ScanOptions::default()
+                })
+                .scan_content(Path::new("big.txt"), &content)
+                .into_iter()
+                .map(|finding| (finding.line, finding.rule_id.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let sequential = scan(None);
+        assert_eq!(sequential.len(), 200);
+        assert_eq!(scan(Some(1)), sequential);
+    }
+
+    #[test]
+    fn fast_scans_skip_minor_rules_and_stop_at_the_first_blocking_finding() {
+        let rule = |id: &str, severity, pattern: &str| Rule {
+            id: id.to_string(),
+            severity,
+            ..base_rule(pattern)
+        };
+        let rules = vec![
+            rule("medium", Severity::Medium, r"(secret_M[A-Z0-9]{6})"),
+            rule("high", Severity::High, r"(secret_H[A-Z0-9]{6})"),
+            rule("critical", Severity::Critical, r"(secret_C[A-Z0-9]{6})"),
+        ];
+        let content = "a = secret_MABC123
+b = secret_HABC123
+c = secret_CABC123
+";
+        let scan = |rule_severity, stop_at| {
+            let filter = Filter::from_config(None, Vec::new()).expect("filter");
+            Detector::new(rules.clone(), filter)
+                .expect("detector")
+                .with_options(ScanOptions {
+                    rule_severity,
+                    stop_at,
+                    ..ScanOptions::default()
+                })
+                .scan_content(Path::new("app.env"), content)
+                .into_iter()
+                .map(|finding| finding.rule_id.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scan(None, None), ["medium", "high", "critical"]);
+        assert_eq!(scan(Some(Severity::High), None), ["high", "critical"]);
+        assert_eq!(scan(None, Some(Severity::Medium)), ["critical"]);
+    }
+
+    #[test]
+    fn rules_without_keywords_are_prefiltered_on_pattern_literals() {
+        let mut derived = base_rule(r"(tok_[A-Z0-9]{6})");
+        derived.keywords.clear();
+        let mut unbounded = base_rule(r"([A-Z0-9]{12})");
+        unbounded.keywords.clear();
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![derived, unbounded], filter).expect("detector");
+
+        assert_eq!(detector.rules[0].keywords, ["tok_"]);
+        assert_eq!(detector.prefilter.candidates("nothing here"), vec![1]);
+        let mut candidates = detector.prefilter.candidates("key = TOK_ABC123");
+        candidates.sort_unstable();
+        assert_eq!(candidates, vec![0, 1]);
+        let findings = detector.scan_content(Path::new("a.txt"), "key = tok_ABC123");
+        assert_eq!(findings.len(), 1);
+    }
+
+    #[test]
+    fn quotes_around_a_secret_do_not_change_its_fingerprint() {
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let rule = base_rule(r#"token = (["']?secret_[A-Z0-9]{6}["']?)"#);
+        let detector = Detector::new(vec![rule.clone()], filter).expect("detector");
+        let quoted = detector.scan_content(Path::new("a.txt"), "token = \"secret_ABC123\"");
+        let bare = detector.scan_content(Path::new("a.txt"), "token = secret_ABC123");
+        assert_eq!(quoted[0].fingerprint, bare[0].fingerprint);
+        assert_eq!(quoted[0].column, 10);
+        assert_eq!(quoted[0].preview, bare[0].preview);
+        let spans = detector.scan_spans(Path::new("a.txt"), "token = 'secret_ABC123'");
+        assert_eq!(spans[0].range, 9..22);
+
+        let untrimmed = Rule {
+            trim: Some(String::new()),
+            ..rule
+        };
+        let filter = Filter::from_config(None, Vec::new()).expect("filter");
+        let detector = Detector::new(vec![untrimmed], filter).expect("detector");
+        let quoted = detector.scan_content(Path::new("a.txt"), "token = \"secret_ABC123\"");
+        assert_ne!(quoted[0].fingerprint, bare[0].fingerprint);
+    }
 }
@@ -0,0 +1,130 @@
+use base64::Engine;
+use serde_json::Value;
+
+pub(crate) struct Segment {
+    pub(crate) cell: usize,
+    pub(crate) output: bool,
+    pub(crate) text: String,
+}
+
+pub(crate) fn is_notebook(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+// Splits a notebook into the unescaped source of each cell and the text of
+// its outputs. Returns None when the content is not notebook JSON.
+pub(crate) fn segments(content: &str) -> Option<Vec<Segment>> {
+    let notebook: Value = serde_json::from_str(content).ok()?;
+    let cells = notebook.get("cells")?.as_array()?;
+    let mut segments = Vec::new();
+    for (cell, value) in cells.iter().enumerate() {
+        if let Some(text) = value.get("source").and_then(multiline) {
+            segments.push(Segment {
+                cell,
+                output: false,
+                text,
+            });
+        }
+        let outputs = value.get("outputs").and_then(Value::as_array);
+        for output in outputs.into_iter().flatten() {
+            for text in output_texts(output) {
+                segments.push(Segment {
+                    cell,
+                    output: true,
+                    text,
+                });
+            }
+        }
+    }
+    Some(segments)
+}
+
+fn output_texts(output: &Value) -> Vec<String> {
+    let mut texts = Vec::new();
+    if let Some(text) = output.get("text").and_then(multiline) {
+        texts.push(text);
+    }
+    if let Some(traceback) = output.get("traceback").and_then(multiline) {
+        texts.push(traceback);
+    }
+    let data = output.get("data").and_then(Value::as_object);
+    for (mime, value) in data.into_iter().flatten() {
+        let Some(text) = multiline(value) else {
+            if value.is_object() || value.is_array() {
+                texts.push(value.to_string());
+            }
+            continue;
+        };
+        if is_text_mime(mime) {
+            texts.push(text);
+        } else if let Some(decoded) = decode_base64_text(&text) {
+            texts.push(decoded);
+        }
+    }
+    texts
+}
+
+// Notebook strings are either a single string or a list of lines.
+fn multiline(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(lines) => Some(lines.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || mime == "application/json"
+        || mime == "application/javascript"
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+}
+
+fn decode_base64_text(encoded: &str) -> Option<String> {
+    let compact: String = encoded.split_whitespace().collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_sources_and_outputs() {
+        let notebook = r##"{
+          "cells": [
+            {"cell_type": "markdown", "source": ["# Title\n"]},
+            {"cell_type": "code", "source": ["import os\n", "key = \"abc\"\n"],
+             "outputs": [
+               {"output_type": "stream", "name": "stdout", "text": ["done\n"]},
+               {"output_type": "display_data",
+                "data": {"text/plain": "plain", "application/x-secret": "dG9rZW49eHl6"}}
+             ]}
+          ]
+        }"##;
+        let segments = segments(notebook).expect("notebook");
+        let texts: Vec<(usize, bool, &str)> = segments
+            .iter()
+            .map(|segment| (segment.cell, segment.output, segment.text.as_str()))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                (0, false, "# Title\n"),
+                (1, false, "import os\nkey = \"abc\"\n"),
+                (1, true, "done\n"),
+                (1, true, "token=xyz"),
+                (1, true, "plain"),
+            ]
+        );
+        assert!(super::segments("not json").is_none());
+    }
+}
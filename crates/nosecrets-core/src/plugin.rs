@@ -0,0 +1,34 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+
+use nosecrets_rules::Severity;
+
+// A detector that runs next to the rules, for checks a pattern cannot
+// express such as checksums or proprietary token formats. Its detections go
+// through the same suppression, fingerprinting and masking as rule matches.
+pub trait SecretDetector: Send + Sync {
+    // Shown in logs; rule ids come from each detection.
+    fn name(&self) -> &str;
+
+    // Secrets in `text`, the content of `path` relative to the scan root.
+    fn detect(&self, path: &Path, text: &str) -> Vec<Detection>;
+}
+
+impl fmt::Debug for dyn SecretDetector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretDetector").field(&self.name()).finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    // Used like a rule id in findings, ignore entries and `[allow] rules`.
+    pub rule_id: String,
+    pub rule_name: String,
+    pub severity: Severity,
+    // Byte range of the secret in the text.
+    pub range: Range<usize>,
+    // 0 to 1, compared against `min_confidence`.
+    pub confidence: f64,
+}
@@ -0,0 +1,245 @@
+use std::cmp::Reverse;
+use std::path::Path;
+
+use globset::{Glob, GlobMatcher};
+use nosecrets_filter::{FilterError, RemediationConfig, SecretManager};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Dotenv,
+    Yaml,
+    Json,
+    Shell,
+}
+
+fn config_format(path: &Path) -> Option<ConfigFormat> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name == ".env" || name.starts_with(".env.") || name.ends_with(".env") {
+        return Some(ConfigFormat::Dotenv);
+    }
+    if matches!(
+        name.as_str(),
+        ".bashrc" | ".bash_profile" | ".profile" | ".zshrc" | ".zprofile" | ".envrc"
+    ) {
+        return Some(ConfigFormat::Shell);
+    }
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "yml" | "yaml" => Some(ConfigFormat::Yaml),
+        "json" => Some(ConfigFormat::Json),
+        "sh" | "bash" | "zsh" => Some(ConfigFormat::Shell),
+        _ => None,
+    }
+}
+
+// How to move a secret found in a .env, YAML, JSON or shell file into an
+// environment variable. `before` is the line up to the secret; the variable is
+// named after the key it is assigned to, or after the rule.
+pub(crate) fn env_suggestion(path: &Path, before: &str, rule_id: &str) -> Option<String> {
+    let format = config_format(path)?;
+    let key = assigned_key(before);
+    let var = env_var_name(key.unwrap_or(rule_id));
+    Some(match format {
+        ConfigFormat::Dotenv => format!(
+            "keep this file out of git and set {var} in the environment or a secret manager; \
+             commit a .env.example with an empty {var}= instead"
+        ),
+        ConfigFormat::Yaml => {
+            format!("move the value to environment variable {var} and reference it as ${{{var}}}")
+        }
+        ConfigFormat::Json => format!(
+            "move the value to environment variable {var} and read it at runtime; \
+             JSON has no variable references"
+        ),
+        ConfigFormat::Shell => format!(
+            "remove the value and export {var} from the environment or a secret manager; \
+             reference it as \"${{{var}}}\""
+        ),
+    })
+}
+
+// Commands that store a secret in the secret manager `[remediation]` picks
+// for its rule. They read the value from stdin, so it stays out of the
+// report and the shell history.
+#[derive(Debug, Clone, Default)]
+pub struct StoreRecipes {
+    config: RemediationConfig,
+    // Longest pattern first, like `[severity]`.
+    globs: Vec<(GlobMatcher, SecretManager)>,
+}
+
+impl StoreRecipes {
+    pub fn from_config(config: &RemediationConfig) -> Result<Self, FilterError> {
+        let mut globs = Vec::new();
+        for (pattern, manager) in &config.rules {
+            if !pattern.contains(['*', '?', '[', '{']) {
+                continue;
+            }
+            let matcher = Glob::new(pattern)
+                .map_err(|error| FilterError::Glob {
+                    pattern: pattern.clone(),
+                    error,
+                })?
+                .compile_matcher();
+            globs.push((pattern.len(), matcher, *manager));
+        }
+        globs.sort_by_key(|(len, _, _)| Reverse(*len));
+        Ok(Self {
+            config: config.clone(),
+            globs: globs
+                .into_iter()
+                .map(|(_, matcher, manager)| (matcher, manager))
+                .collect(),
+        })
+    }
+
+    fn manager(&self, rule_id: &str) -> Option<SecretManager> {
+        self.config
+            .rules
+            .get(rule_id)
+            .copied()
+            .or_else(|| {
+                self.globs
+                    .iter()
+                    .find(|(matcher, _)| matcher.is_match(rule_id))
+                    .map(|(_, manager)| *manager)
+            })
+            .or(self.config.secret_manager)
+    }
+
+    // `before` is the line up to the secret; the secret is named after the
+    // key it is assigned to, or after the rule.
+    pub(crate) fn command(&self, before: &str, rule_id: &str) -> Option<String> {
+        let manager = self.manager(rule_id)?;
+        let name = env_var_name(assigned_key(before).unwrap_or(rule_id));
+        let fill = |template: Option<&str>, default: &str| {
+            template
+                .unwrap_or(default)
+                .replace("{name}", &name)
+                .replace("{rule}", rule_id)
+        };
+        Some(match manager {
+            SecretManager::Vault => format!(
+                "vault kv put {} value=-",
+                fill(self.config.vault.path.as_deref(), "secret/{name}")
+            ),
+            SecretManager::Aws => {
+                let aws = &self.config.aws;
+                let region = aws
+                    .region
+                    .as_ref()
+                    .map(|region| format!(" --region {region}"))
+                    .unwrap_or_default();
+                format!(
+                    "aws secretsmanager create-secret --name {}{region} --secret-string file:///dev/stdin",
+                    fill(aws.name.as_deref(), "{name}")
+                )
+            }
+            SecretManager::Gcp => {
+                let gcp = &self.config.gcp;
+                // Secret ids allow letters, digits, `-` and `_`.
+                let id: String = fill(gcp.name.as_deref(), "{name}")
+                    .chars()
+                    .map(|ch| {
+                        if ch.is_ascii_alphanumeric() || ch == '_' {
+                            ch
+                        } else {
+                            '-'
+                        }
+                    })
+                    .collect();
+                let project = gcp
+                    .project
+                    .as_ref()
+                    .map(|project| format!(" --project {project}"))
+                    .unwrap_or_default();
+                format!("gcloud secrets create {id}{project} --data-file=-")
+            }
+        })
+    }
+}
+
+// The key of `KEY=`, `export KEY=`, `key:` or `"key":` right before the value.
+fn assigned_key(before: &str) -> Option<&str> {
+    let before = before.trim_end().trim_end_matches(['"', '\'']).trim_end();
+    let before = before
+        .strip_suffix('=')
+        .or_else(|| before.strip_suffix(':'))?
+        .trim_end()
+        .trim_end_matches(['"', '\'']);
+    let start = before
+        .rfind(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.')))
+        .map_or(0, |idx| idx + 1);
+    let key = &before[start..];
+    (!key.is_empty()).then_some(key)
+}
+
+// `apiKey`, `api-key` and `api.key` become `API_KEY`.
+fn env_var_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len() + 4);
+    let mut previous_lower = false;
+    for ch in key.chars() {
+        if ch.is_ascii_uppercase() && previous_lower {
+            name.push('_');
+        }
+        previous_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+        if ch.is_ascii_alphanumeric() {
+            name.push(ch.to_ascii_uppercase());
+        } else if !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestion_names_the_variable_after_the_key() {
+        let yaml = env_suggestion(Path::new("deploy/app.yaml"), "  apiKey: \"", "generic");
+        assert_eq!(
+            yaml.as_deref(),
+            Some("move the value to environment variable API_KEY and reference it as ${API_KEY}")
+        );
+        let json = env_suggestion(Path::new("config.json"), "{\"db.password\": \"", "generic");
+        assert!(json.is_some_and(|text| text.contains("DB_PASSWORD")));
+        let shell = env_suggestion(Path::new("run.sh"), "export AWS_KEY=", "aws-access-key");
+        assert!(shell.is_some_and(|text| text.contains("\"${AWS_KEY}\"")));
+        let dotenv = env_suggestion(Path::new(".env.local"), "", "github-pat");
+        assert!(dotenv.is_some_and(|text| text.contains("GITHUB_PAT=")));
+        assert_eq!(env_suggestion(Path::new("main.rs"), "key = \"", "x"), None);
+    }
+
+    #[test]
+    fn store_commands_follow_the_rule_mapping() {
+        let config: RemediationConfig = toml::from_str(
+            r#"
+            secret_manager = "vault"
+            rules = { "aws-*" = "aws", "gcp-service-account" = "gcp" }
+            vault = { path = "secret/acme/{name}" }
+            aws = { name = "acme/{rule}", region = "eu-central-1" }
+            gcp = { project = "acme-prod" }
+            "#,
+        )
+        .expect("config");
+        let recipes = StoreRecipes::from_config(&config).expect("recipes");
+        assert_eq!(
+            recipes.command("  apiKey: ", "generic-api-key").as_deref(),
+            Some("vault kv put secret/acme/API_KEY value=-")
+        );
+        assert_eq!(
+            recipes.command("", "aws-access-key").as_deref(),
+            Some(
+                "aws secretsmanager create-secret --name acme/aws-access-key --region eu-central-1 \
+                 --secret-string file:///dev/stdin"
+            )
+        );
+        assert_eq!(
+            recipes.command("", "gcp-service-account").as_deref(),
+            Some("gcloud secrets create GCP_SERVICE_ACCOUNT --project acme-prod --data-file=-")
+        );
+        assert_eq!(StoreRecipes::default().command("", "aws-access-key"), None);
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use nosecrets_filter::Filter;
+use nosecrets_rules::Rule;
+
+use crate::{error_message, Detector, ScanError};
+
+const DEFAULT_SAMPLE_PATH: &str = "sample";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTestFailure {
+    pub rule: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleTestReport {
+    pub rules: usize,
+    pub samples: usize,
+    pub failures: Vec<RuleTestFailure>,
+}
+
+// Compiles each rule on its own and runs its samples through the whole
+// pipeline (keywords, validation, allowlists), not just the regex.
+pub fn run_rule_tests(rules: &[Rule]) -> RuleTestReport {
+    let mut report = RuleTestReport::default();
+    for rule in rules {
+        report.rules += 1;
+        let fail = |message: String| RuleTestFailure {
+            rule: rule.id.clone(),
+            message,
+        };
+        let detector = Filter::from_config(None, Vec::new())
+            .map_err(ScanError::from)
+            .and_then(|filter| Detector::new(vec![rule.clone()], filter));
+        let detector = match detector {
+            Ok(detector) => detector,
+            Err(error) => {
+                // The failure already names the rule.
+                let message = error_message(&error);
+                let prefix = format!("invalid rule {}: ", rule.id);
+                let message = message.strip_prefix(&prefix).unwrap_or(&message);
+                report.failures.push(fail(message.to_string()));
+                continue;
+            }
+        };
+        for test in &rule.tests {
+            let path = Path::new(test.path.as_deref().unwrap_or(DEFAULT_SAMPLE_PATH));
+            let cases = test
+                .matches
+                .iter()
+                .map(|sample| (sample, true))
+                .chain(test.no_match.iter().map(|sample| (sample, false)));
+            for (sample, expected) in cases {
+                report.samples += 1;
+                let matched = !detector.scan_content(path, sample).is_empty();
+                if matched != expected {
+                    let expectation = if expected {
+                        "expected a match"
+                    } else {
+                        "expected no match"
+                    };
+                    report
+                        .failures
+                        .push(fail(format!("{expectation} for {sample:?}")));
+                }
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use nosecrets_report::sha256_hex;
+
+use crate::error::{Result, ScanError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleOptions {
+    pub window_size: u64,
+    pub random_windows: usize,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self {
+            window_size: 1024 * 1024,
+            random_windows: 4,
+        }
+    }
+}
+
+pub(crate) struct Window {
+    pub(crate) line_offset: usize,
+    pub(crate) byte_offset: usize,
+    pub(crate) bytes: Vec<u8>,
+}
+
+// Picks the head, the tail and `random_windows` windows in between. Offsets
+// are derived from a SHA-256 of the path so repeated scans, also by other
+// builds, sample the same regions.
+fn window_ranges(path: &Path, len: u64, options: &SampleOptions) -> Vec<(u64, u64)> {
+    let size = options.window_size.max(1);
+    if len <= size.saturating_mul(2) {
+        return vec![(0, len)];
+    }
+    let mut ranges = vec![(0, size), (len - size, len)];
+    let span = len - 2 * size;
+    for idx in 0..options.random_windows {
+        let digest = sha256_hex(format!("{}\0{idx}", path.display()).as_bytes());
+        let hex = digest.trim_start_matches("sha256:");
+        let hash = u64::from_str_radix(&hex[..16], 16).expect("hex digest");
+        let start = size + hash % span;
+        ranges.push((start, (start + size).min(len)));
+    }
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+pub(crate) fn read_head(path: &Path, limit: u64) -> Result<Vec<u8>> {
+    let read = |source| ScanError::read(path, source);
+    let file = File::open(path).map_err(read)?;
+    let mut bytes = Vec::new();
+    file.take(limit).read_to_end(&mut bytes).map_err(read)?;
+    Ok(bytes)
+}
+
+// Reads the sampled windows in a single pass, counting newlines in skipped
+// regions so line numbers stay exact. Windows are trimmed to whole lines.
+pub(crate) fn read_windows(path: &Path, len: u64, options: &SampleOptions) -> Result<Vec<Window>> {
+    let read_error = |source| ScanError::read(path, source);
+    let file = File::open(path).map_err(read_error)?;
+    let mut reader = BufReader::new(file);
+    let mut windows = Vec::new();
+    let mut position = 0u64;
+    let mut newlines = 0usize;
+    let mut last_byte = b'\n';
+    let mut buffer = vec![0u8; 64 * 1024];
+    for (start, end) in window_ranges(path, len, options) {
+        while position < start {
+            let want = ((start - position) as usize).min(buffer.len());
+            let read = reader.read(&mut buffer[..want]).map_err(read_error)?;
+            if read == 0 {
+                break;
+            }
+            newlines += buffer[..read].iter().filter(|&&byte| byte == b'\n').count();
+            last_byte = buffer[read - 1];
+            position += read as u64;
+        }
+        let mut bytes = Vec::with_capacity((end - start) as usize);
+        (&mut reader)
+            .take(end - start)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        position += bytes.len() as u64;
+
+        let window_newlines = bytes.iter().filter(|&&byte| byte == b'\n').count();
+        let mut line_offset = newlines;
+        let mut from = 0;
+        if last_byte != b'\n' {
+            match bytes.iter().position(|&byte| byte == b'\n') {
+                Some(idx) => {
+                    from = idx + 1;
+                    line_offset += 1;
+                }
+                None => from = bytes.len(),
+            }
+        }
+        let to = if position >= len {
+            bytes.len()
+        } else {
+            bytes
+                .iter()
+                .rposition(|&byte| byte == b'\n')
+                .map(|idx| idx + 1)
+                .unwrap_or(0)
+                .max(from)
+        };
+        newlines += window_newlines;
+        if let Some(&byte) = bytes.last() {
+            last_byte = byte;
+        }
+        if from < to {
+            windows.push(Window {
+                line_offset,
+                byte_offset: start as usize + from,
+                bytes: bytes[from..to].to_vec(),
+            });
+        }
+    }
+    Ok(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn windows_cover_head_and_tail_with_exact_lines() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("big.log");
+        let content: String = (1..=1000).map(|n| format!("line {n:04}\n")).collect();
+        fs::write(&path, &content).expect("write");
+
+        let options = SampleOptions {
+            window_size: 100,
+            random_windows: 2,
+        };
+        let windows = read_windows(&path, content.len() as u64, &options).expect("sample");
+        assert!(windows.len() >= 2);
+        for window in &windows {
+            let text = String::from_utf8_lossy(&window.bytes);
+            let first = text.lines().next().expect("line");
+            let number: usize = first["line ".len()..].parse().expect("number");
+            assert_eq!(number, window.line_offset + 1);
+        }
+        let last = windows.last().expect("tail");
+        assert!(String::from_utf8_lossy(&last.bytes).ends_with("line 1000\n"));
+    }
+
+    #[test]
+    fn window_offsets_do_not_depend_on_the_build() {
+        let options = SampleOptions {
+            window_size: 100,
+            random_windows: 2,
+        };
+        assert_eq!(
+            window_ranges(Path::new("data/big.log"), 10_000, &options),
+            [(0, 100), (3489, 3589), (9152, 9252), (9900, 10_000)]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use nosecrets_filter::normalize_path;
+use nosecrets_report::{FileStats, RuleStats, ScanStats};
+
+const SLOWEST_FILES: usize = 10;
+
+#[derive(Default)]
+pub(crate) struct RuleSample {
+    pub(crate) matches: usize,
+    pub(crate) findings: usize,
+    pub(crate) elapsed: Duration,
+}
+
+#[derive(Default)]
+struct RuleTotals {
+    prefilter_hits: usize,
+    matches: usize,
+    findings: usize,
+    elapsed: Duration,
+}
+
+#[derive(Default)]
+struct Totals {
+    files: usize,
+    bytes: u64,
+    texts: usize,
+    rules: HashMap<usize, RuleTotals>,
+    slowest: Vec<FileStats>,
+}
+
+#[derive(Default)]
+pub(crate) struct StatsCollector {
+    totals: Mutex<Totals>,
+}
+
+impl StatsCollector {
+    pub(crate) fn record_text(&self, samples: Vec<(usize, RuleSample)>) {
+        let mut totals = self.totals.lock().unwrap_or_else(|err| err.into_inner());
+        totals.texts += 1;
+        for (rule, sample) in samples {
+            let entry = totals.rules.entry(rule).or_default();
+            entry.prefilter_hits += 1;
+            entry.matches += sample.matches;
+            entry.findings += sample.findings;
+            entry.elapsed += sample.elapsed;
+        }
+    }
+
+    pub(crate) fn record_file(&self, path: &Path, bytes: u64, elapsed: Duration) {
+        let mut totals = self.totals.lock().unwrap_or_else(|err| err.into_inner());
+        totals.files += 1;
+        totals.bytes += bytes;
+        let duration_us = elapsed.as_micros() as u64;
+        let slowest = &mut totals.slowest;
+        if slowest.len() < SLOWEST_FILES
+            || slowest
+                .last()
+                .is_some_and(|last| last.duration_us < duration_us)
+        {
+            slowest.push(FileStats {
+                path: normalize_path(path),
+                bytes,
+                duration_us,
+            });
+            slowest.sort_by_key(|file| std::cmp::Reverse(file.duration_us));
+            slowest.truncate(SLOWEST_FILES);
+        }
+    }
+
+    // `rule_ids` maps rule indices to ids; rules are sorted by regex time.
+    pub(crate) fn snapshot(&self, rule_ids: &[&str]) -> ScanStats {
+        let totals = self.totals.lock().unwrap_or_else(|err| err.into_inner());
+        let mut rules: Vec<RuleStats> = rule_ids
+            .iter()
+            .enumerate()
+            .map(|(idx, id)| {
+                let rule = totals.rules.get(&idx);
+                let hits = rule.map_or(0, |rule| rule.prefilter_hits);
+                RuleStats {
+                    rule_id: id.to_string(),
+                    prefilter_hits: hits,
+                    prefilter_hit_rate: if totals.texts == 0 {
+                        0.0
+                    } else {
+                        hits as f64 / totals.texts as f64
+                    },
+                    matches: rule.map_or(0, |rule| rule.matches),
+                    findings: rule.map_or(0, |rule| rule.findings),
+                    regex_time_us: rule.map_or(0, |rule| rule.elapsed.as_micros() as u64),
+                }
+            })
+            .collect();
+        rules.sort_by(|a, b| {
+            b.regex_time_us
+                .cmp(&a.regex_time_us)
+                .then_with(|| a.rule_id.cmp(&b.rule_id))
+        });
+        ScanStats {
+            files: totals.files,
+            bytes_scanned: totals.bytes,
+            texts: totals.texts,
+            rules,
+            slowest_files: totals.slowest.clone(),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+use std::path::Path;
+
+use nosecrets_rules::RuleContext;
+
+// Comment and string-literal delimiters of a language. This is a tokenizer,
+// not a parser: it only has to tell comments, strings and other code apart.
+struct Language {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    // Tried in order, so triple quotes come before single ones. The flag
+    // marks literals that may span lines.
+    strings: &'static [(&'static str, bool)],
+    // Values may also be written without quotes (shell, YAML), so
+    // `require_string` cannot be decided.
+    bare_values: bool,
+}
+
+const C_LIKE: Language = Language {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[("\"\"\"", true), ("\"", false), ("'", false)],
+    bare_values: false,
+};
+
+const JAVASCRIPT: Language = Language {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[("`", true), ("\"", false), ("'", false)],
+    bare_values: false,
+};
+
+// `'` starts lifetimes and char literals, not strings.
+const RUST: Language = Language {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[("\"", true)],
+    bare_values: false,
+};
+
+const PYTHON: Language = Language {
+    line_comments: &["#"],
+    block_comment: None,
+    strings: &[("\"\"\"", true), ("'''", true), ("\"", false), ("'", false)],
+    bare_values: false,
+};
+
+const HASH: Language = Language {
+    line_comments: &["#"],
+    block_comment: None,
+    strings: &[("\"", false), ("'", false)],
+    bare_values: false,
+};
+
+const SHELL: Language = Language {
+    strings: &[("\"", true), ("'", true)],
+    bare_values: true,
+    ..HASH
+};
+
+const YAML: Language = Language {
+    bare_values: true,
+    ..HASH
+};
+
+const SQL: Language = Language {
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    strings: &[("'", false), ("\"", false)],
+    bare_values: false,
+};
+
+fn language(path: &Path) -> Option<&'static Language> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "cs" | "java" | "kt" | "kts" | "scala"
+        | "swift" | "dart" | "php" => &C_LIKE,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "go" => &JAVASCRIPT,
+        "rs" => &RUST,
+        "py" | "toml" => &PYTHON,
+        "rb" | "pl" | "r" => &HASH,
+        "sh" | "bash" | "zsh" => &SHELL,
+        "yaml" | "yml" => &YAML,
+        "sql" | "lua" => &SQL,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Comment,
+    String,
+}
+
+// Comments and string literals of one file.
+#[derive(Debug)]
+pub(crate) struct Syntax {
+    // Sorted, non-overlapping byte ranges.
+    tokens: Vec<(Range<usize>, Token)>,
+    bare_values: bool,
+}
+
+impl Syntax {
+    // None when the language is not known from the extension.
+    pub(crate) fn parse(path: &Path, text: &str) -> Option<Self> {
+        let language = language(path)?;
+        Some(Self {
+            tokens: tokenize(language, text.as_bytes()),
+            bare_values: language.bare_values,
+        })
+    }
+
+    fn token_at(&self, offset: usize) -> Option<Token> {
+        let idx = self
+            .tokens
+            .partition_point(|(range, _)| range.end <= offset);
+        self.tokens
+            .get(idx)
+            .filter(|(range, _)| range.contains(&offset))
+            .map(|(_, token)| *token)
+    }
+
+    // Whether a secret starting at `offset` satisfies the rule's context.
+    pub(crate) fn allows(&self, context: &RuleContext, offset: usize) -> bool {
+        let require_string = context.require_string && !self.bare_values;
+        match self.token_at(offset) {
+            Some(Token::String) => true,
+            Some(Token::Comment) => !context.skip_comments && !require_string,
+            None => !require_string,
+        }
+    }
+}
+
+fn tokenize(language: &Language, bytes: &[u8]) -> Vec<(Range<usize>, Token)> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    'outer: while i < bytes.len() {
+        let rest = &bytes[i..];
+        for marker in language.line_comments {
+            // `#` only starts a comment at the start of a word, so `a#b`
+            // and `${#var}` stay code.
+            let at_word_start = *marker != "#" || i == 0 || bytes[i - 1].is_ascii_whitespace();
+            if rest.starts_with(marker.as_bytes()) && at_word_start {
+                let end = find(bytes, i, b"\n").unwrap_or(bytes.len());
+                tokens.push((i..end, Token::Comment));
+                i = end;
+                continue 'outer;
+            }
+        }
+        if let Some((open, close)) = language.block_comment {
+            if rest.starts_with(open.as_bytes()) {
+                let end = find(bytes, i + open.len(), close.as_bytes())
+                    .map_or(bytes.len(), |end| end + close.len());
+                tokens.push((i..end, Token::Comment));
+                i = end;
+                continue;
+            }
+        }
+        for (quote, multiline) in language.strings {
+            if rest.starts_with(quote.as_bytes()) {
+                let end = string_end(bytes, i + quote.len(), quote.as_bytes(), *multiline);
+                tokens.push((i..end, Token::String));
+                i = end;
+                continue 'outer;
+            }
+        }
+        i += 1;
+    }
+    tokens
+}
+
+// End of a string literal whose content starts at `from`. Unterminated
+// literals run to the end of the line, or of the file if they may span lines.
+fn string_end(bytes: &[u8], from: usize, quote: &[u8], multiline: bool) -> usize {
+    let mut j = from;
+    while j < bytes.len() {
+        if bytes[j] == b'\\' {
+            j += 2;
+        } else if bytes[j..].starts_with(quote) {
+            return j + quote.len();
+        } else if bytes[j] == b'\n' && !multiline {
+            return j;
+        } else {
+            j += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|idx| from + idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens_of(path: &str, text: &str) -> Vec<(String, Token)> {
+        Syntax::parse(Path::new(path), text)
+            .expect("known language")
+            .tokens
+            .into_iter()
+            .map(|(range, token)| (text[range].to_string(), token))
+            .collect()
+    }
+
+    #[test]
+    fn finds_comments_and_strings() {
+        assert_eq!(
+            tokens_of(
+                "a.js",
+                "// api_key\nconst a = \"x\\\"y\"; /* b\n */ let c = `d\ne`;"
+            ),
+            vec![
+                ("// api_key".to_string(), Token::Comment),
+                ("\"x\\\"y\"".to_string(), Token::String),
+                ("/* b\n */".to_string(), Token::Comment),
+                ("`d\ne`".to_string(), Token::String),
+            ]
+        );
+        assert_eq!(
+            tokens_of("a.py", "x = '''a\n# b''' # c \"d\"\nurl#frag"),
+            vec![
+                ("'''a\n# b'''".to_string(), Token::String),
+                ("# c \"d\"".to_string(), Token::Comment),
+            ]
+        );
+        assert!(Syntax::parse(Path::new("a.env"), "x").is_none());
+    }
+
+    #[test]
+    fn context_decides_by_token_and_language() {
+        let context = RuleContext {
+            require_string: true,
+            skip_comments: true,
+        };
+        let text = "key = \"value\" # note\nother = value";
+        let python = Syntax::parse(Path::new("a.py"), text).expect("python");
+        assert!(python.allows(&context, text.find("value").unwrap()));
+        assert!(!python.allows(&context, text.find("note").unwrap()));
+        assert!(!python.allows(&context, text.rfind("value").unwrap()));
+        // Shell values need no quotes, so only comments are dropped.
+        let shell = Syntax::parse(Path::new("a.sh"), text).expect("shell");
+        assert!(shell.allows(&context, text.rfind("value").unwrap()));
+        assert!(!shell.allows(&context, text.find("note").unwrap()));
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use ignore::{WalkBuilder, WalkState};
+use nosecrets_filter::{Config, SymlinkPolicy};
+use tracing::warn;
+
+use crate::error::Result;
+use crate::git;
+
+const STATE_DIR: &str = ".nosecrets";
+
+pub const DEFAULT_SKIP_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tiff", "psd", "mp3", "mp4", "mov", "avi",
+    "wav", "flac", "ogg", "webm", "zip", "gz", "tgz", "bz2", "xz", "7z", "rar", "zst", "jar",
+    "war", "exe", "dll", "so", "dylib", "a", "o", "obj", "class", "pyc", "wasm", "woff", "woff2",
+    "ttf", "otf", "eot", "pdf", "iso", "dmg", "bin",
+];
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    pub max_file_size: Option<u64>,
+    pub skip_extensions: Vec<String>,
+    pub symlinks: SymlinkPolicy,
+    // Also walk files excluded by .gitignore, .git/info/exclude and the
+    // global gitignore, e.g. local .env files.
+    pub include_ignored: bool,
+    // Walk into submodules registered in .gitmodules. Other nested
+    // repositories are always skipped.
+    pub recurse_submodules: bool,
+    // Walker threads; None picks one per core.
+    pub threads: Option<usize>,
+    // Take the files of directories inside the repository from its index
+    // instead of walking them, so untracked build output, virtualenvs and
+    // vendored dependencies are never read. The root must be a repository.
+    pub tracked_only: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: None,
+            skip_extensions: DEFAULT_SKIP_EXTENSIONS
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            symlinks: SymlinkPolicy::Skip,
+            include_ignored: false,
+            recurse_submodules: false,
+            threads: None,
+            tracked_only: false,
+        }
+    }
+}
+
+impl WalkOptions {
+    // The `[scan]` file size, extension and symlink settings.
+    pub fn from_config(config: Option<&Config>) -> Self {
+        let mut options = Self::default();
+        if let Some(scan) = config.map(|config| &config.scan) {
+            options.max_file_size = scan.max_file_size;
+            options
+                .skip_extensions
+                .extend(scan.skip_extensions.iter().cloned());
+            options.symlinks = scan.symlink_policy();
+        }
+        options
+    }
+
+    pub(crate) fn is_skipped_extension(&self, path: &Path) -> bool {
+        let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+            return false;
+        };
+        self.skip_extensions
+            .iter()
+            .any(|skip| skip.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    }
+
+    pub(crate) fn is_too_large(&self, len: u64) -> bool {
+        self.max_file_size.is_some_and(|max| len > max)
+    }
+
+    fn accepts(&self, path: &Path, len: u64) -> bool {
+        !self.is_skipped_extension(path) && !self.is_too_large(len)
+    }
+
+    // Files reached through different links are scanned once.
+    fn dedup_key(&self, path: &Path) -> PathBuf {
+        match self.symlinks {
+            SymlinkPolicy::Follow => fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+pub fn collect_files(
+    root: &Path,
+    inputs: &[PathBuf],
+    options: &WalkOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let submodules: Arc<HashSet<PathBuf>> = if options.recurse_submodules {
+        Arc::new(submodule_dirs(root).into_iter().collect())
+    } else {
+        Arc::default()
+    };
+    let tracked = if options.tracked_only {
+        Some(git::tracked_files(root, options.recurse_submodules)?)
+    } else {
+        None
+    };
+    let targets: Vec<PathBuf> = if inputs.is_empty() {
+        vec![root.to_path_buf()]
+    } else {
+        inputs.to_vec()
+    };
+
+    for target in targets {
+        let target = if target.is_absolute() {
+            target
+        } else {
+            root.join(target)
+        };
+        if target.is_file() {
+            let len = target.metadata().map(|meta| meta.len()).unwrap_or(0);
+            if options.accepts(&target, len) && seen.insert(options.dedup_key(&target)) {
+                files.push(target);
+            }
+            continue;
+        }
+        if target.is_dir() {
+            let mut walked = match &tracked {
+                Some(tracked) if target.starts_with(root) => {
+                    tracked_in(root, &target, tracked, options)
+                }
+                _ => walk_dir(root, &target, options, &submodules),
+            };
+            walked.sort();
+            for path in walked {
+                if seen.insert(options.dedup_key(&path)) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn walk_dir(
+    root: &Path,
+    dir: &Path,
+    options: &WalkOptions,
+    submodules: &Arc<HashSet<PathBuf>>,
+) -> Vec<PathBuf> {
+    let found = Mutex::new(Vec::new());
+    let submodules = Arc::clone(submodules);
+    WalkBuilder::new(dir)
+        .hidden(false)
+        .follow_links(options.symlinks == SymlinkPolicy::Follow)
+        .threads(options.threads.unwrap_or(0))
+        .git_ignore(!options.include_ignored)
+        .git_exclude(!options.include_ignored)
+        .git_global(!options.include_ignored)
+        .filter_entry(move |entry| {
+            // .nosecrets holds our own state, such as the scan history.
+            if entry.file_name() == ".git" || entry.file_name() == STATE_DIR {
+                return false;
+            }
+            // A nested repository has its own history and root; its files
+            // are only walked when it is a submodule and submodules are on.
+            let nested = entry.depth() > 0
+                && entry.file_type().is_some_and(|kind| kind.is_dir())
+                && entry.path().join(".git").exists();
+            !nested || submodules.contains(entry.path())
+        })
+        .build_parallel()
+        .run(|| {
+            let found = &found;
+            Box::new(move |entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(error) => {
+                        if is_loop(&error) {
+                            warn!("skipped symlink loop: {error}");
+                        }
+                        return WalkState::Continue;
+                    }
+                };
+                let is_link = entry.file_type().is_some_and(|kind| kind.is_symlink());
+                if is_link && options.symlinks == SymlinkPolicy::Report {
+                    if symlink_target_outside(root, entry.path()).is_some() {
+                        found
+                            .lock()
+                            .expect("walk results lock")
+                            .push(entry.into_path());
+                    }
+                    return WalkState::Continue;
+                }
+                if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                    return WalkState::Continue;
+                }
+                let len = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+                if options.accepts(entry.path(), len) {
+                    found
+                        .lock()
+                        .expect("walk results lock")
+                        .push(entry.into_path());
+                }
+                WalkState::Continue
+            })
+        });
+    found.into_inner().expect("walk results lock")
+}
+
+// The files of `tracked` under `dir` that exist and that the walk would
+// have kept.
+fn tracked_in(root: &Path, dir: &Path, tracked: &[PathBuf], options: &WalkOptions) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in tracked {
+        let in_state_dir = path
+            .strip_prefix(root)
+            .is_ok_and(|rel| rel.components().any(|part| part.as_os_str() == STATE_DIR));
+        if !path.starts_with(dir) || in_state_dir {
+            continue;
+        }
+        // Deleted in the working tree but not yet staged.
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            continue;
+        };
+        if meta.file_type().is_symlink() {
+            match options.symlinks {
+                SymlinkPolicy::Skip => continue,
+                SymlinkPolicy::Report => {
+                    if symlink_target_outside(root, path).is_some() {
+                        files.push(path.clone());
+                    }
+                    continue;
+                }
+                SymlinkPolicy::Follow => {}
+            }
+        }
+        let Ok(meta) = fs::metadata(path) else {
+            continue;
+        };
+        if meta.is_file() && options.accepts(path, meta.len()) {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+fn is_loop(error: &ignore::Error) -> bool {
+    match error {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_loop(err),
+        ignore::Error::Partial(errors) => errors.iter().any(is_loop),
+        _ => false,
+    }
+}
+
+// The target of the symbolic link `path`, as written, when it resolves to a
+// place outside `root`. Broken links are resolved lexically.
+pub(crate) fn symlink_target_outside(root: &Path, path: &Path) -> Option<PathBuf> {
+    let target = fs::read_link(path).ok()?;
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let parent = fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+    let resolved = parent.join(&target);
+    let resolved = fs::canonicalize(&resolved).unwrap_or_else(|_| normalize_lexically(&resolved));
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    (!resolved.starts_with(&root)).then_some(target)
+}
+
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// Submodule directories of the repository at `root`, including submodules of
+// submodules, read from the `path` keys of .gitmodules.
+pub fn submodule_dirs(root: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(root.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    let mut dirs = Vec::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "path" {
+            continue;
+        }
+        let dir = root.join(value.trim());
+        if dir.is_dir() {
+            dirs.extend(submodule_dirs(&dir));
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::git;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn skips_binary_extensions_and_large_files() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).expect("create dir");
+        fs::write(root.join("src/main.rs"), "fn main() {}").expect("write");
+        fs::write(root.join("src/logo.PNG"), "not really a png").expect("write");
+        fs::write(root.join("src/big.log"), "x".repeat(64)).expect("write");
+        fs::write(root.join(".env"), "KEY=value").expect("write");
+
+        let options = WalkOptions {
+            max_file_size: Some(32),
+            ..WalkOptions::default()
+        };
+        let files = collect_files(root, &[], &options).expect("collect");
+        let names: Vec<String> = files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(names, vec![".env".to_string(), "src/main.rs".to_string()]);
+    }
+
+    #[test]
+    fn include_ignored_walks_gitignored_files() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git")).expect("create .git");
+        fs::write(root.join(".gitignore"), ".env.local\n").expect("write");
+        fs::write(root.join(".env.local"), "KEY=value").expect("write");
+        fs::create_dir_all(root.join(".nosecrets")).expect("create .nosecrets");
+        fs::write(root.join(".nosecrets/history.jsonl"), "{}\n").expect("write");
+
+        let files = collect_files(root, &[], &WalkOptions::default()).expect("collect");
+        assert_eq!(files, vec![root.join(".gitignore")]);
+
+        let options = WalkOptions {
+            include_ignored: true,
+            ..WalkOptions::default()
+        };
+        let files = collect_files(root, &[], &options).expect("collect");
+        assert_eq!(
+            files,
+            vec![root.join(".env.local"), root.join(".gitignore")]
+        );
+    }
+
+    #[test]
+    fn nested_repositories_are_skipped_unless_submodules_are_recursed() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git")).expect("create .git");
+        fs::write(
+            root.join(".gitmodules"),
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n",
+        )
+        .expect("write");
+        fs::create_dir_all(root.join("vendor/lib")).expect("create dir");
+        fs::write(
+            root.join("vendor/lib/.git"),
+            "gitdir: ../../.git/modules/lib\n",
+        )
+        .expect("write");
+        fs::write(root.join("vendor/lib/key.txt"), "KEY=value").expect("write");
+        fs::create_dir_all(root.join("scratch/.git")).expect("create dir");
+        fs::write(root.join("scratch/notes.txt"), "KEY=value").expect("write");
+
+        let files = collect_files(root, &[], &WalkOptions::default()).expect("collect");
+        assert_eq!(files, vec![root.join(".gitmodules")]);
+
+        let options = WalkOptions {
+            recurse_submodules: true,
+            ..WalkOptions::default()
+        };
+        let files = collect_files(root, &[], &options).expect("collect");
+        assert_eq!(
+            files,
+            vec![root.join(".gitmodules"), root.join("vendor/lib/key.txt")]
+        );
+    }
+
+    #[test]
+    fn tracked_only_skips_untracked_files() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        fs::create_dir_all(root.join("src")).expect("create dir");
+        fs::create_dir_all(root.join(".venv/lib")).expect("create dir");
+        fs::write(root.join("src/main.py"), "KEY=value").expect("write");
+        fs::write(root.join("src/gone.py"), "KEY=value").expect("write");
+        fs::write(root.join("staged.py"), "KEY=value").expect("write");
+        fs::write(root.join(".venv/lib/site.py"), "KEY=value").expect("write");
+        git(root, &["add", "src", "staged.py"]);
+        fs::remove_file(root.join("src/gone.py")).expect("remove");
+
+        let options = WalkOptions {
+            tracked_only: true,
+            ..WalkOptions::default()
+        };
+        let files = collect_files(root, &[], &options).expect("collect");
+        assert_eq!(
+            files,
+            vec![root.join("src/main.py"), root.join("staged.py")]
+        );
+        let files = collect_files(root, &[root.join("src")], &options).expect("collect");
+        assert_eq!(files, vec![root.join("src/main.py")]);
+        // Files named explicitly are scanned whether or not they are tracked.
+        let files =
+            collect_files(root, &[root.join(".venv/lib/site.py")], &options).expect("collect");
+        assert_eq!(files, vec![root.join(".venv/lib/site.py")]);
+
+        let files = collect_files(root, &[], &WalkOptions::default()).expect("collect");
+        assert_eq!(files.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_skipped_followed_or_reported() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempdir().expect("tempdir");
+        let root = &dir.path().join("repo");
+        fs::create_dir_all(root.join("sub")).expect("create dir");
+        fs::create_dir_all(dir.path().join("outside")).expect("create dir");
+        fs::write(dir.path().join("outside/secret.txt"), "KEY=value").expect("write");
+        fs::write(root.join("a.txt"), "KEY=value").expect("write");
+        symlink("a.txt", root.join("link_in")).expect("symlink");
+        symlink("../outside/secret.txt", root.join("link_out")).expect("symlink");
+        symlink("..", root.join("sub/loop")).expect("symlink");
+
+        let collect = |symlinks| {
+            let options = WalkOptions {
+                symlinks,
+                ..WalkOptions::default()
+            };
+            collect_files(root, &[], &options).expect("collect")
+        };
+        assert_eq!(collect(SymlinkPolicy::Skip), vec![root.join("a.txt")]);
+        assert_eq!(
+            collect(SymlinkPolicy::Follow),
+            vec![root.join("a.txt"), root.join("link_out")]
+        );
+        assert_eq!(
+            collect(SymlinkPolicy::Report),
+            vec![root.join("a.txt"), root.join("link_out")]
+        );
+        assert_eq!(
+            symlink_target_outside(root, &root.join("link_out")),
+            Some(PathBuf::from("../outside/secret.txt"))
+        );
+        assert_eq!(symlink_target_outside(root, &root.join("link_in")), None);
+        assert_eq!(symlink_target_outside(root, &root.join("sub/loop")), None);
+    }
+}
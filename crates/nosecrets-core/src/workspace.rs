@@ -0,0 +1,369 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobMatcher};
+
+// Directories never searched for workspace members.
+const SKIP_DIRS: &[&str] = &[".git", "node_modules", "target", "vendor"];
+
+// A package of a Cargo, npm/yarn/pnpm or Go workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub dir: PathBuf,
+    // Files the manifest declares outside or inside `dir`, e.g. a shared
+    // README referenced by Cargo's `readme`.
+    pub include: Vec<PathBuf>,
+}
+
+impl Package {
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.dir) || self.include.iter().any(|file| file == path)
+    }
+}
+
+// Packages of the workspaces declared at `root`. Discovery is lenient:
+// manifests that cannot be read or parsed are skipped so a broken
+// package.json never fails a scan.
+pub fn discover_packages(root: &Path) -> Vec<Package> {
+    let mut packages = Vec::new();
+    packages.extend(cargo_packages(root));
+    packages.extend(npm_packages(root));
+    packages.extend(go_packages(root));
+    packages.sort_by(|a, b| a.dir.cmp(&b.dir).then_with(|| a.name.cmp(&b.name)));
+    packages.dedup_by(|a, b| a.dir == b.dir && a.name == b.name);
+    packages
+}
+
+// The innermost package containing `path`.
+pub fn package_for<'a>(packages: &'a [Package], path: &Path) -> Option<&'a Package> {
+    packages
+        .iter()
+        .filter(|package| path.starts_with(&package.dir))
+        .max_by_key(|package| package.dir.components().count())
+}
+
+fn cargo_packages(root: &Path) -> Vec<Package> {
+    let Some(manifest) = read_toml(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let members = string_list(workspace.get("members"));
+    let exclude = string_list(workspace.get("exclude"));
+    let mut dirs = expand_members(root, &members, &exclude);
+    if manifest.get("package").is_some() {
+        dirs.push(root.to_path_buf());
+    }
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let manifest = read_toml(&dir.join("Cargo.toml"))?;
+            let package = manifest.get("package")?;
+            let name = package.get("name")?.as_str()?.to_string();
+            let mut declared = string_list(package.get("include"));
+            for key in ["readme", "license-file", "build"] {
+                if let Some(file) = package.get(key).and_then(|value| value.as_str()) {
+                    declared.push(file.to_string());
+                }
+            }
+            let include = declared_files(&dir, &declared);
+            Some(Package { name, dir, include })
+        })
+        .collect()
+}
+
+fn npm_packages(root: &Path) -> Vec<Package> {
+    let mut patterns = Vec::new();
+    if let Some(manifest) = read_json(&root.join("package.json")) {
+        // `workspaces` is a list, or `{ "packages": [...] }` in yarn.
+        let workspaces = manifest.get("workspaces");
+        let list = workspaces
+            .and_then(|value| value.get("packages"))
+            .or(workspaces);
+        if let Some(list) = list.and_then(|value| value.as_array()) {
+            patterns.extend(
+                list.iter()
+                    .filter_map(|value| value.as_str().map(str::to_string)),
+            );
+        }
+    }
+    if let Ok(content) = fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        patterns.extend(pnpm_patterns(&content));
+    }
+    let (exclude, members): (Vec<String>, Vec<String>) = patterns
+        .into_iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let exclude: Vec<String> = exclude
+        .iter()
+        .map(|pattern| pattern[1..].to_string())
+        .collect();
+    expand_members(root, &members, &exclude)
+        .into_iter()
+        .filter_map(|dir| {
+            let manifest = read_json(&dir.join("package.json"))?;
+            let name = match manifest.get("name").and_then(|value| value.as_str()) {
+                Some(name) => name.to_string(),
+                None => dir.file_name()?.to_string_lossy().into_owned(),
+            };
+            let declared: Vec<String> = manifest
+                .get("files")
+                .and_then(|value| value.as_array())
+                .map(|files| {
+                    files
+                        .iter()
+                        .filter_map(|value| value.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let include = declared_files(&dir, &declared);
+            Some(Package { name, dir, include })
+        })
+        .collect()
+}
+
+fn go_packages(root: &Path) -> Vec<Package> {
+    let Ok(content) = fs::read_to_string(root.join("go.work")) else {
+        return Vec::new();
+    };
+    go_work_dirs(&content)
+        .into_iter()
+        .filter_map(|dir| {
+            let dir = normalize(&root.join(dir));
+            let module = fs::read_to_string(dir.join("go.mod")).ok()?;
+            let name = module.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix("module ")
+                    .map(|name| name.trim().trim_matches('"').to_string())
+            })?;
+            Some(Package {
+                name,
+                dir,
+                include: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+// `packages:` entries of a pnpm-workspace.yaml, e.g. `- 'packages/*'`.
+fn pnpm_patterns(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let code = line.split(" #").next().unwrap_or_default().trim_end();
+        if code.trim().is_empty() || code.trim_start().starts_with('#') {
+            continue;
+        }
+        if !code.starts_with([' ', '\t', '-']) {
+            in_packages = code.trim() == "packages:";
+            continue;
+        }
+        if let Some(item) = code.trim().strip_prefix('-').filter(|_| in_packages) {
+            let item = item.trim().trim_matches(['\'', '"']);
+            if !item.is_empty() {
+                patterns.push(item.to_string());
+            }
+        }
+    }
+    patterns
+}
+
+// Directories of `use ./dir` and `use ( ... )` directives in a go.work file.
+fn go_work_dirs(content: &str) -> Vec<String> {
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                dirs.push(line.trim_matches('"').to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() && rest != line {
+                dirs.push(rest.trim_matches('"').to_string());
+            }
+        }
+    }
+    dirs
+}
+
+// Resolves member patterns such as `crates/*` to directories below `root`.
+fn expand_members(root: &Path, members: &[String], exclude: &[String]) -> Vec<PathBuf> {
+    let exclude: Vec<GlobMatcher> = exclude
+        .iter()
+        .filter_map(|pattern| matcher(pattern))
+        .collect();
+    let mut dirs = Vec::new();
+    for pattern in members {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        if !has_glob(pattern) {
+            let dir = normalize(&root.join(pattern));
+            if dir.is_dir() {
+                dirs.push(dir);
+            }
+            continue;
+        }
+        let Some(glob) = matcher(pattern) else {
+            continue;
+        };
+        let literal: PathBuf = Path::new(pattern)
+            .components()
+            .take_while(|component| !has_glob(&component.as_os_str().to_string_lossy()))
+            .collect();
+        let depth = if pattern.contains("**") {
+            usize::MAX
+        } else {
+            Path::new(pattern).components().count()
+        };
+        walk_dirs(root, &root.join(&literal), depth, &glob, &mut dirs);
+    }
+    dirs.retain(|dir| {
+        let relative = dir.strip_prefix(root).unwrap_or(dir);
+        !exclude.iter().any(|glob| glob.is_match(relative))
+    });
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn walk_dirs(root: &Path, dir: &Path, depth: usize, glob: &GlobMatcher, dirs: &mut Vec<PathBuf>) {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    if glob.is_match(relative) {
+        dirs.push(dir.to_path_buf());
+    }
+    if relative.components().count() >= depth {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let skipped = path
+            .file_name()
+            .is_some_and(|name| SKIP_DIRS.iter().any(|skip| name == *skip));
+        if !skipped && entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+            walk_dirs(root, &path, depth, glob, dirs);
+        }
+    }
+}
+
+// Declared files that exist; globs are left to the directory scan.
+fn declared_files(dir: &Path, declared: &[String]) -> Vec<PathBuf> {
+    declared
+        .iter()
+        .filter(|file| !has_glob(file))
+        .map(|file| normalize(&dir.join(file.trim_start_matches('/'))))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn matcher(pattern: &str) -> Option<GlobMatcher> {
+    Glob::new(pattern.trim_start_matches("./").trim_end_matches('/'))
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+fn has_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn read_toml(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn read_json(path: &Path) -> Option<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, content).expect("write");
+    }
+
+    #[test]
+    fn discovers_cargo_npm_and_go_workspaces() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/legacy\"]\n",
+        );
+        write(
+            root.join("crates/api/Cargo.toml"),
+            "[package]\nname = \"api\"\nreadme = \"../../README.md\"\n",
+        );
+        write(
+            root.join("crates/legacy/Cargo.toml"),
+            "[package]\nname = \"legacy\"\n",
+        );
+        write(root.join("README.md"), "# demo\n");
+        write(
+            root.join("package.json"),
+            r#"{"workspaces": {"packages": ["web/*", "!web/old"]}}"#,
+        );
+        write(
+            root.join("web/app/package.json"),
+            r#"{"name": "@demo/app"}"#,
+        );
+        write(root.join("web/old/package.json"), r#"{"name": "old"}"#);
+        write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'tools/cli'\n",
+        );
+        write(root.join("tools/cli/package.json"), r#"{"name": "cli"}"#);
+        write(root.join("go.work"), "go 1.22\n\nuse (\n\t./svc\n)\n");
+        write(root.join("svc/go.mod"), "module example.com/svc\n");
+
+        let packages = discover_packages(root);
+        let names: Vec<&str> = packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["api", "example.com/svc", "cli", "@demo/app"]);
+        assert_eq!(packages[0].include, vec![root.join("README.md")]);
+        assert!(packages[0].contains(&root.join("README.md")));
+        assert!(packages[0].contains(&root.join("crates/api/src/lib.rs")));
+        assert_eq!(
+            package_for(&packages, &root.join("web/app/index.js")).map(|p| p.name.as_str()),
+            Some("@demo/app")
+        );
+        assert!(package_for(&packages, &root.join("web/old/index.js")).is_none());
+    }
+}
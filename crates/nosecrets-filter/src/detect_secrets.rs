@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::FilterError;
+
+// Version written into exported baselines; detect-secrets accepts older ones.
+const BASELINE_VERSION: &str = "1.5.0";
+
+// Start of the reason of the .nosecretsignore entries an import writes.
+pub const BASELINE_REASON: &str = "detect-secrets baseline";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineFormat {
+    // .nosecretsignore entries
+    Nosecrets,
+    // Yelp detect-secrets `.secrets.baseline`
+    DetectSecrets,
+}
+
+impl BaselineFormat {
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".baseline") || name.ends_with(".json") {
+            BaselineFormat::DetectSecrets
+        } else {
+            BaselineFormat::Nosecrets
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectSecretsBaseline {
+    pub version: String,
+    #[serde(default)]
+    pub plugins_used: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub filters_used: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub results: BTreeMap<String, Vec<BaselineSecret>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    // Keys such as `exclude` or `word_list` are kept when re-writing a file.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineSecret {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub filename: String,
+    // Hex SHA-1 of the secret.
+    pub hashed_secret: String,
+    #[serde(default)]
+    pub is_verified: bool,
+    pub line_number: usize,
+    // Set by `detect-secrets audit`: false marks a reviewed false positive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_secret: Option<bool>,
+}
+
+impl DetectSecretsBaseline {
+    pub fn new(generated_at: String) -> Self {
+        Self {
+            version: BASELINE_VERSION.to_string(),
+            plugins_used: Vec::new(),
+            filters_used: Vec::new(),
+            results: BTreeMap::new(),
+            generated_at: Some(generated_at),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, FilterError> {
+        let content = fs::read_to_string(path).map_err(|error| FilterError::Read {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        serde_json::from_str(&content).map_err(|error| FilterError::Json {
+            path: path.to_path_buf(),
+            error,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self).map(|json| json + "\n")
+    }
+
+    pub fn push(&mut self, secret: BaselineSecret) {
+        self.results
+            .entry(secret.filename.clone())
+            .or_default()
+            .push(secret);
+    }
+
+    // The entry that accepts `secret` in `filename`, if it is recorded and
+    // not audited as a real secret.
+    pub fn accepted(&self, filename: &str, secret: &str) -> Option<&BaselineSecret> {
+        let hashed = hashed_secret(secret);
+        self.results
+            .get(filename)?
+            .iter()
+            .find(|entry| entry.hashed_secret == hashed && entry.is_accepted())
+    }
+}
+
+impl BaselineSecret {
+    // Unaudited entries count as accepted, as in detect-secrets.
+    pub fn is_accepted(&self) -> bool {
+        self.is_secret != Some(true)
+    }
+}
+
+pub fn hashed_secret(secret: &str) -> String {
+    sha1_smol::Sha1::from(secret).digest().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_audited_baseline_entries() {
+        let json = r#"{
+          "version": "1.4.0",
+          "plugins_used": [{"name": "AWSKeyDetector"}],
+          "filters_used": [],
+          "exclude": {"files": null, "lines": null},
+          "results": {
+            "config/app.yml": [
+              {"type": "AWS Access Key", "filename": "config/app.yml",
+               "hashed_secret": "HASH", "is_verified": false, "line_number": 3,
+               "is_secret": false},
+              {"type": "Secret Keyword", "filename": "config/app.yml",
+               "hashed_secret": "REAL", "is_verified": false, "line_number": 9,
+               "is_secret": true}
+            ]
+          },
+          "generated_at": "2024-05-01T00:00:00Z"
+        }"#;
+        let json = json
+            .replace("HASH", &hashed_secret("fixture-value"))
+            .replace("REAL", &hashed_secret("real-value"));
+        let baseline: DetectSecretsBaseline = serde_json::from_str(&json).expect("parse");
+        let accepted = baseline.accepted("config/app.yml", "fixture-value");
+        assert_eq!(accepted.map(|secret| secret.line_number), Some(3));
+        assert!(baseline.accepted("config/app.yml", "real-value").is_none());
+        assert!(baseline.accepted("other.yml", "fixture-value").is_none());
+        assert!(baseline.to_json().expect("json").contains("\"exclude\""));
+
+        assert_eq!(
+            hashed_secret("secret"),
+            "e5e9fa1ba31ecd1ae84f75caaa474f3a663f05f4"
+        );
+        assert_eq!(
+            BaselineFormat::from_path(Path::new(".secrets.baseline")),
+            BaselineFormat::DetectSecrets
+        );
+        assert_eq!(
+            BaselineFormat::from_path(Path::new(".nosecretsignore")),
+            BaselineFormat::Nosecrets
+        );
+    }
+}
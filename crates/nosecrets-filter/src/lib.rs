@@ -1,17 +1,169 @@
 use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use regex::Regex;
 use serde::Deserialize;
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 
+use nosecrets_rules::{FileFormat, FormatError, Rule, Severity};
+
+mod detect_secrets;
+mod nested;
+mod path;
+mod policy;
+mod remote;
+
+pub use detect_secrets::{
+    hashed_secret, BaselineFormat, BaselineSecret, DetectSecretsBaseline, BASELINE_REASON,
+};
+use nested::{NestedIndex, Scope};
+pub use nested::{ScopeError, FIXTURES_MARKER};
+pub use path::{normalize_glob_pattern, normalize_path, path_glob, relative_path};
+use policy::ProtectedRules;
+pub use policy::{Policy, PolicyError, POLICY_FILE};
+pub use remote::DEFAULT_REMOTE_TTL_HOURS;
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct Config {
     #[serde(default)]
     pub ignore: IgnoreConfig,
     #[serde(default)]
     pub allow: AllowConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub hook: HookConfig,
+    #[serde(default)]
+    pub report: ReportConfig,
+    // Rule id or glob of rule ids to the severity findings are reported with.
+    #[serde(default)]
+    pub severity: BTreeMap<String, Severity>,
+    #[serde(default)]
+    pub fingerprint: FingerprintConfig,
+    #[serde(default)]
+    pub heuristics: HeuristicsConfig,
+    #[serde(default)]
+    pub remediation: RemediationConfig,
+}
+
+pub const REPO_CONFIG_FILES: &[&str] = &[
+    ".nosecrets.toml",
+    ".nosecrets.yaml",
+    ".nosecrets.yml",
+    ".nosecrets.json",
+];
+
+pub const MIN_FINGERPRINT_LENGTH: usize = 12;
+pub const MAX_FINGERPRINT_LENGTH: usize = 64;
+
+// Commands that move a finding's secret into a secret manager. Off unless
+// `secret_manager` is set.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct RemediationConfig {
+    pub secret_manager: Option<SecretManager>,
+    // Rule id or glob of rule ids to another secret manager.
+    #[serde(default)]
+    pub rules: BTreeMap<String, SecretManager>,
+    #[serde(default)]
+    pub vault: VaultConfig,
+    #[serde(default)]
+    pub aws: AwsSecretsConfig,
+    #[serde(default)]
+    pub gcp: GcpSecretsConfig,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretManager {
+    Vault,
+    Aws,
+    Gcp,
+}
+
+// Names below may use {name}, the secret's variable name, and {rule}.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct VaultConfig {
+    // KV path, "secret/{name}" by default.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct AwsSecretsConfig {
+    // Secret name, "{name}" by default.
+    pub name: Option<String>,
+    pub region: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct GcpSecretsConfig {
+    // Secret id, "{name}" by default.
+    pub name: Option<String>,
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct FingerprintConfig {
+    pub salt: Option<String>,
+    pub length: Option<usize>,
+    // Environment variable holding the HMAC key; the key never lives in config.
+    pub key_env: Option<String>,
+}
+
+impl FingerprintConfig {
+    pub fn validated_length(&self) -> Result<Option<usize>, FilterError> {
+        match self.length {
+            Some(length)
+                if !(MIN_FINGERPRINT_LENGTH..=MAX_FINGERPRINT_LENGTH).contains(&length) =>
+            {
+                Err(FilterError::Invalid {
+                    key: "fingerprint.length",
+                    message: format!(
+                        "must be between {MIN_FINGERPRINT_LENGTH} and {MAX_FINGERPRINT_LENGTH}, got {length}"
+                    ),
+                })
+            }
+            length => Ok(length),
+        }
+    }
+
+    pub fn key(&self) -> Result<Option<Vec<u8>>, FilterError> {
+        let Some(name) = &self.key_env else {
+            return Ok(None);
+        };
+        match env::var(name) {
+            Ok(key) if !key.is_empty() => Ok(Some(key.into_bytes())),
+            _ => Err(FilterError::Invalid {
+                key: "fingerprint.key_env",
+                message: format!("environment variable {name} is not set"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct HeuristicsConfig {
+    #[serde(default)]
+    pub placeholders: PlaceholderAction,
+    // Words marking a value as a placeholder, besides the built-in ones.
+    #[serde(default)]
+    pub words: Vec<String>,
+}
+
+// What happens to matches whose value looks like a placeholder: `drop`
+// suppresses them, `downgrade` reports them as low severity with half the
+// confidence.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderAction {
+    #[default]
+    Drop,
+    Downgrade,
+    Off,
 }
 
 #[derive(Debug, Deserialize, Default, Clone)]
@@ -20,26 +172,305 @@ pub struct IgnoreConfig {
     pub paths: Vec<String>,
 }
 
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct ScanConfig {
+    pub max_file_size: Option<u64>,
+    #[serde(default)]
+    pub skip_extensions: Vec<String>,
+    // Older spelling of `symlinks = "follow"`.
+    pub follow_symlinks: Option<bool>,
+    pub symlinks: Option<SymlinkPolicy>,
+    pub sample_large_files: Option<bool>,
+    pub sample_window: Option<u64>,
+    pub sample_windows: Option<usize>,
+    pub resolve_overlaps: Option<bool>,
+    // Limits against pathological inputs; 0 disables a limit.
+    pub max_findings_per_file: Option<usize>,
+    pub file_timeout_ms: Option<u64>,
+    pub max_files: Option<usize>,
+    // Bytes from which a file is matched by its rules in parallel; 0 turns
+    // it off.
+    pub parallel_rules_threshold: Option<u64>,
+    // Scan threads; 0 or unset uses every core.
+    pub jobs: Option<usize>,
+    #[serde(default)]
+    pub binary: BinaryConfig,
+}
+
+impl ScanConfig {
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        match (self.symlinks, self.follow_symlinks) {
+            (Some(policy), _) => policy,
+            (None, Some(true)) => SymlinkPolicy::Follow,
+            (None, _) => SymlinkPolicy::Skip,
+        }
+    }
+}
+
+// How files are told apart from text. The start of each file is sampled:
+// a NUL byte, or more control characters than `max_control_ratio`, makes it
+// binary unless it starts with a byte order mark.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct BinaryConfig {
+    pub sample_bytes: Option<usize>,
+    pub max_control_ratio: Option<f64>,
+    // Always scanned as text, e.g. files with stray control characters.
+    #[serde(default)]
+    pub text_extensions: Vec<String>,
+    // Always binary without sampling; only rules for binary files see them.
+    #[serde(default)]
+    pub binary_extensions: Vec<String>,
+}
+
+impl BinaryConfig {
+    pub fn validated_max_control_ratio(&self) -> Result<Option<f64>, FilterError> {
+        match self.max_control_ratio {
+            Some(ratio) if !(0.0..=1.0).contains(&ratio) => Err(FilterError::Invalid {
+                key: "scan.binary.max_control_ratio",
+                message: format!("must be between 0 and 1, got {ratio}"),
+            }),
+            ratio => Ok(ratio),
+        }
+    }
+}
+
+// What the walk does with symbolic links. `follow` walks into linked files
+// and directories, stopping at loops; `report` skips links but reports those
+// that point outside the repository.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    #[default]
+    Skip,
+    Follow,
+    Report,
+}
+
+impl SymlinkPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Follow => "follow",
+            Self::Report => "report",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct HookConfig {
+    pub education: Option<bool>,
+    // A blocked `scan --staged` ends with the commands to unblock it.
+    pub remediation: Option<bool>,
+    // `scan --staged` runs as with `--low-priority`.
+    pub low_priority: Option<bool>,
+    // `scan --staged` runs as with `--fast`.
+    pub fast: Option<bool>,
+    // `scan --staged` reuses the findings of blobs an earlier run scanned.
+    pub cache: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct ReportConfig {
+    pub format: Option<ReportFormat>,
+    pub mask: Option<MaskMode>,
+    // Share of the characters `mask = "partial"` shows.
+    pub reveal_percent: Option<u8>,
+    #[serde(default)]
+    pub columns: ColumnUnit,
+    // File `scan` writes the report to, relative to the repository root.
+    pub output: Option<PathBuf>,
+    #[serde(default)]
+    pub template: ReportTemplateConfig,
+}
+
+// Wording of the text report.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct ReportTemplateConfig {
+    // First line of each finding, e.g. "{location} [{severity}] {rule}".
+    pub line: Option<String>,
+    // TOML, YAML or JSON file of messages, relative to the repository root.
+    pub catalog: Option<PathBuf>,
+    // Messages by key; they take precedence over the catalog.
+    #[serde(default)]
+    pub messages: BTreeMap<String, String>,
+    // Runbook URL per rule id.
+    #[serde(default)]
+    pub links: BTreeMap<String, String>,
+}
+
+impl ReportTemplateConfig {
+    // The catalog's messages overridden by `messages`.
+    pub fn load_messages(&self, root: &Path) -> Result<BTreeMap<String, String>, FilterError> {
+        let mut messages = match &self.catalog {
+            Some(catalog) => {
+                let path = root.join(catalog);
+                let content = fs::read_to_string(&path).map_err(|error| FilterError::Read {
+                    path: path.clone(),
+                    error,
+                })?;
+                FileFormat::from_path(&path)
+                    .parse(&content)
+                    .map_err(|error| FilterError::Parse { path, error })?
+            }
+            None => BTreeMap::new(),
+        };
+        messages.extend(self.messages.clone());
+        Ok(messages)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskMode {
+    Full,
+    Fixed,
+    Partial,
+}
+
+// What finding columns count. Editors index lines by characters or, like
+// LSP and VS Code, by UTF-16 code units.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnUnit {
+    #[default]
+    Chars,
+    Utf16,
+    Bytes,
+}
+
+impl ColumnUnit {
+    pub fn width(self, text: &str) -> usize {
+        match self {
+            Self::Chars => text.chars().count(),
+            Self::Utf16 => text.encode_utf16().count(),
+            Self::Bytes => text.len(),
+        }
+    }
+}
+
+pub const MAX_REVEAL_PERCENT: u8 = 50;
+
+impl ReportConfig {
+    pub fn validated_reveal_percent(&self) -> Result<Option<u8>, FilterError> {
+        match self.reveal_percent {
+            Some(percent) if percent > MAX_REVEAL_PERCENT => Err(FilterError::Invalid {
+                key: "report.reveal_percent",
+                message: format!("must be at most {MAX_REVEAL_PERCENT}, got {percent}"),
+            }),
+            percent => Ok(percent),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Markdown,
+    Csv,
+    Tsv,
+    Ndjson,
+}
+
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct AllowConfig {
     #[serde(default)]
     pub patterns: Vec<String>,
     #[serde(default)]
     pub values: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<AllowRule>,
+    // URL of a centrally managed allowlist merged into this one.
+    pub remote: Option<String>,
+    pub remote_ttl_hours: Option<u64>,
 }
 
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct AllowRule {
+    pub rule: String,
+    #[serde(default)]
+    pub paths: Vec<String>,
+    // YYYY-MM-DD; afterwards the rule's findings are reported again.
+    pub expires: Option<String>,
+}
+
+pub const FILE_IGNORE_DIRECTIVE: &str = "@nosecrets-ignore-file";
+pub const FILE_IGNORE_LINES: usize = 10;
+pub const IGNORE_START_MARKER: &str = "@nosecrets-ignore-start";
+pub const IGNORE_END_MARKER: &str = "@nosecrets-ignore-end";
+
+// An in-file exclusion. Without rules the whole file is skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIgnore {
+    pub rules: Vec<String>,
+    pub reason: String,
+}
+
+// RULE_ENTRY_PREFIX entries suppress a whole rule instead of one fingerprint.
+pub const RULE_ENTRY_PREFIX: &str = "rule:";
+
 #[derive(Debug)]
 pub struct IgnoreEntry {
+    // The entry as written: a fingerprint or `rule:<rule-id>`.
     pub fingerprint: String,
+    // Rule id of a `rule:<rule-id>` entry.
+    pub rule: Option<String>,
+    pub path: Option<String>,
     pub matcher: Option<GlobMatcher>,
+    pub reason: Option<String>,
+    pub by: Option<String>,
+    pub on: Option<String>,
+    pub expires: Option<String>,
+}
+
+impl IgnoreEntry {
+    pub fn is_expired(&self, today: &str) -> bool {
+        self.expires.as_deref().is_some_and(|date| date < today)
+    }
+
+    // Written by `nosecrets baseline import`.
+    pub fn is_baseline(&self) -> bool {
+        self.reason
+            .as_deref()
+            .is_some_and(|reason| reason.starts_with(BASELINE_REASON))
+    }
+
+    fn matches(&self, fingerprint: &str, normalized_path: &str) -> bool {
+        self.rule.is_none() && self.fingerprint == fingerprint && self.matches_path(normalized_path)
+    }
+
+    fn matches_rule(&self, rule_id: &str, normalized_path: &str) -> bool {
+        self.rule.as_deref() == Some(rule_id) && self.matches_path(normalized_path)
+    }
+
+    pub fn matches_path(&self, normalized_path: &str) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.is_match(normalized_path))
+    }
+}
+
+#[derive(Debug)]
+struct AllowedRule {
+    entry: AllowRule,
+    paths: Option<GlobSet>,
 }
 
 #[derive(Debug)]
 pub struct Filter {
     ignore_paths: Option<GlobSet>,
     allow_patterns: Vec<Regex>,
-    allow_values: HashSet<String>,
+    allow_values: BTreeSet<String>,
+    allow_rules: Vec<AllowedRule>,
     ignore_entries: Vec<IgnoreEntry>,
+    // Suppressions past their `expires` date. They no longer hide findings
+    // but mark them as expired ignores.
+    expired_allow_rules: Vec<AllowedRule>,
+    expired_entries: Vec<IgnoreEntry>,
+    nested: Option<NestedIndex>,
+    // Rules of `.nosecrets.policy.toml` that no setting may suppress.
+    protected: Option<ProtectedRules>,
 }
 
 #[derive(Debug, Error)]
@@ -54,7 +485,7 @@ pub enum FilterError {
     Parse {
         path: PathBuf,
         #[source]
-        error: toml::de::Error,
+        error: FormatError,
     },
     #[error("invalid glob pattern {pattern}: {error}")]
     Glob {
@@ -68,27 +499,174 @@ pub enum FilterError {
         #[source]
         error: regex::Error,
     },
+    #[error("failed to parse {path}: {error}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        error: serde_json::Error,
+    },
+    #[error("invalid {key}: {message}")]
+    Invalid { key: &'static str, message: String },
+    #[error("failed to fetch allow list {url}: {message}")]
+    Remote { url: String, message: String },
 }
 
 impl Config {
     pub fn load_from_dir(dir: &Path) -> Result<Option<Self>, FilterError> {
-        let path = dir.join(".nosecrets.toml");
-        if !path.exists() {
-            return Ok(None);
+        let paths: Vec<PathBuf> = repo_config_path(dir).into_iter().collect();
+        Self::load_layers(&paths)
+    }
+
+    // Loads the user config and merges the repository's .nosecrets.toml
+    // over it. Subdirectory configs are read by `Filter::with_nested`.
+    pub fn load(dir: &Path) -> Result<Option<Self>, FilterError> {
+        Self::load_layers(&config_paths(dir))
+    }
+
+    // Later layers win: tables are merged key by key, lists are appended
+    // and any other value is replaced.
+    pub fn load_layers(paths: &[PathBuf]) -> Result<Option<Self>, FilterError> {
+        let mut merged: Option<serde_json::Value> = None;
+        let mut last = None;
+        for path in paths {
+            let content = fs::read_to_string(path).map_err(|error| FilterError::Read {
+                path: path.clone(),
+                error,
+            })?;
+            let parse = |error| FilterError::Parse {
+                path: path.clone(),
+                error,
+            };
+            // All formats are read into JSON values so layers merge alike.
+            let mut value = FileFormat::from_path(path).parse(&content).map_err(parse)?;
+            drop_nulls(&mut value);
+            Config::deserialize(value.clone()).map_err(|error| parse(error.into()))?;
+            match &mut merged {
+                Some(base) => merge_value(base, value),
+                None => merged = Some(value),
+            }
+            last = Some(path);
         }
-        let content = fs::read_to_string(&path).map_err(|error| FilterError::Read {
-            path: path.clone(),
-            error,
-        })?;
-        let config = toml::from_str(&content).map_err(|error| FilterError::Parse {
+        let (Some(value), Some(path)) = (merged, last) else {
+            return Ok(None);
+        };
+        let mut config = Config::deserialize(value).map_err(|error| FilterError::Parse {
             path: path.clone(),
-            error,
+            error: error.into(),
         })?;
+        remote::merge_remote(&mut config.allow)?;
         Ok(Some(config))
     }
+
+    // Applies the `[severity]` table. An exact rule id wins over glob
+    // patterns; of several matching globs the longest one applies.
+    pub fn apply_severity(&self, rules: &mut [Rule]) -> Result<(), FilterError> {
+        let mut globs = Vec::new();
+        for (pattern, severity) in &self.severity {
+            if !pattern.contains(['*', '?', '[', '{']) {
+                continue;
+            }
+            let matcher = Glob::new(pattern)
+                .map_err(|error| FilterError::Glob {
+                    pattern: pattern.clone(),
+                    error,
+                })?
+                .compile_matcher();
+            globs.push((pattern.len(), matcher, *severity));
+        }
+        globs.sort_by_key(|(len, _, _)| Reverse(*len));
+        for rule in rules {
+            let severity = self.severity.get(&rule.id).copied().or_else(|| {
+                globs
+                    .iter()
+                    .find(|(_, matcher, _)| matcher.is_match(&rule.id))
+                    .map(|(_, _, severity)| *severity)
+            });
+            if let Some(severity) = severity {
+                rule.severity = severity;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Config files in the order they are merged. NOSECRETS_CONFIG replaces the
+// user config location; set it to an empty value to skip the user config.
+pub fn config_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    match env::var_os("NOSECRETS_CONFIG") {
+        Some(path) if path.is_empty() => {}
+        Some(path) => paths.push(PathBuf::from(path)),
+        None => paths.extend(user_config_path().filter(|path| path.is_file())),
+    }
+    paths.extend(repo_config_path(dir));
+    paths
+}
+
+// The repository config in `dir`. Of several files the first in
+// REPO_CONFIG_FILES is used.
+pub fn repo_config_path(dir: &Path) -> Option<PathBuf> {
+    REPO_CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("nosecrets/config.toml"));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = env::var_os("APPDATA") {
+            return Some(PathBuf::from(dir).join("nosecrets").join("config.toml"));
+        }
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/nosecrets/config.toml"))
+}
+
+fn merge_value(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(base), serde_json::Value::Array(overlay)) => base.extend(overlay),
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// An empty YAML key or a JSON null leaves the setting unset, as a missing
+// key does.
+pub(crate) fn drop_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        serde_json::Value::Array(items) => {
+            items.retain(|item| !item.is_null());
+            items.iter_mut().for_each(drop_nulls);
+        }
+        _ => {}
+    }
 }
 
+// Active entries only; entries past their `expires` date are dropped.
 pub fn load_ignore_file(path: &Path) -> Result<Vec<IgnoreEntry>, FilterError> {
+    let today = today();
+    let mut entries = read_ignore_file(path)?;
+    entries.retain(|entry| !entry.is_expired(&today));
+    Ok(entries)
+}
+
+// All entries including expired ones, e.g. for `nosecrets ignore list`.
+pub fn read_ignore_file(path: &Path) -> Result<Vec<IgnoreEntry>, FilterError> {
     if !path.exists() {
         return Ok(Vec::new());
     }
@@ -96,32 +674,73 @@ pub fn load_ignore_file(path: &Path) -> Result<Vec<IgnoreEntry>, FilterError> {
         path: path.to_path_buf(),
         error,
     })?;
+    let location = |idx: usize| format!("{}:{}", path.display(), idx + 1);
     let mut entries = Vec::new();
-    for line in content.lines() {
+    for (idx, line) in content.lines().enumerate() {
         let trimmed = line.trim();
         if trimmed.is_empty() || trimmed.starts_with('#') {
             continue;
         }
-        let mut parts = trimmed.splitn(2, ':');
-        let fingerprint = parts.next().unwrap().trim().to_string();
-        let matcher = parts
-            .next()
-            .map(|glob| glob.trim())
+        // Metadata follows the entry as `# key=value ...`.
+        let (trimmed, metadata) = match trimmed.find(" #") {
+            Some(idx) => (
+                trimmed[..idx].trim_end(),
+                parse_metadata(&trimmed[idx + 2..]),
+            ),
+            None => (trimmed, Vec::new()),
+        };
+        // `rule:<rule-id>[:glob]` or `<fingerprint>[:glob]`.
+        let (rule, rest) = match trimmed.strip_prefix(RULE_ENTRY_PREFIX) {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let (key, glob) = rest.split_once(':').unwrap_or((rest, ""));
+        let key = key.trim();
+        let (fingerprint, rule) = if rule {
+            (format!("{RULE_ENTRY_PREFIX}{key}"), Some(key.to_string()))
+        } else {
+            (key.to_string(), None)
+        };
+        let path = Some(glob.trim())
             .filter(|glob| !glob.is_empty())
+            .map(str::to_string);
+        let matcher = path
+            .as_deref()
             .map(|glob| {
-                let normalized = normalize_glob_pattern(glob);
-                Glob::new(&normalized)
+                path_glob(glob)
                     .map(|g| g.compile_matcher())
                     .map_err(|error| FilterError::Glob {
-                        pattern: normalized.clone(),
+                        pattern: normalize_glob_pattern(glob),
                         error,
                     })
             })
             .transpose()?;
-        entries.push(IgnoreEntry {
+        let mut entry = IgnoreEntry {
             fingerprint,
+            rule,
+            path,
             matcher,
-        });
+            reason: None,
+            by: None,
+            on: None,
+            expires: None,
+        };
+        for (key, value) in metadata {
+            match key.as_str() {
+                "reason" => entry.reason = Some(value),
+                "by" => entry.by = Some(value),
+                "on" => entry.on = Some(value),
+                "expires" if !is_valid_date(&value) => {
+                    return Err(FilterError::Invalid {
+                        key: "expires",
+                        message: format!("expected YYYY-MM-DD, got {value} at {}", location(idx)),
+                    });
+                }
+                "expires" => entry.expires = Some(value),
+                _ => {}
+            }
+        }
+        entries.push(entry);
     }
     Ok(entries)
 }
@@ -132,23 +751,7 @@ impl Filter {
         ignore_entries: Vec<IgnoreEntry>,
     ) -> Result<Self, FilterError> {
         let config = config.unwrap_or_default();
-        let ignore_paths = if config.ignore.paths.is_empty() {
-            None
-        } else {
-            let mut builder = GlobSetBuilder::new();
-            for pattern in &config.ignore.paths {
-                let normalized = normalize_glob_pattern(pattern);
-                let glob = Glob::new(&normalized).map_err(|error| FilterError::Glob {
-                    pattern: normalized.clone(),
-                    error,
-                })?;
-                builder.add(glob);
-            }
-            Some(builder.build().map_err(|error| FilterError::Glob {
-                pattern: "<globset>".to_string(),
-                error,
-            })?)
-        };
+        let ignore_paths = build_globset(&config.ignore.paths)?;
 
         let mut allow_patterns = Vec::new();
         for pattern in &config.allow.patterns {
@@ -159,61 +762,426 @@ impl Filter {
             allow_patterns.push(regex);
         }
         let allow_values = config.allow.values.into_iter().collect();
+        let today = today();
+        let mut allow_rules = Vec::new();
+        let mut expired_allow_rules = Vec::new();
+        for entry in &config.allow.rules {
+            if let Some(expires) = entry.expires.as_deref().filter(|date| !is_valid_date(date)) {
+                return Err(FilterError::Invalid {
+                    key: "allow.rules.expires",
+                    message: format!("expected YYYY-MM-DD, got {expires}"),
+                });
+            }
+            let allowed = AllowedRule {
+                entry: entry.clone(),
+                paths: build_globset(&entry.paths)?,
+            };
+            if allowed
+                .entry
+                .expires
+                .as_deref()
+                .is_some_and(|date| date < today.as_str())
+            {
+                expired_allow_rules.push(allowed);
+            } else {
+                allow_rules.push(allowed);
+            }
+        }
+        let (expired_entries, ignore_entries) = ignore_entries
+            .into_iter()
+            .partition(|entry| entry.is_expired(&today));
 
         Ok(Self {
             ignore_paths,
             allow_patterns,
             allow_values,
+            allow_rules,
             ignore_entries,
+            expired_allow_rules,
+            expired_entries,
+            nested: None,
+            protected: None,
         })
     }
 
+    // Exempts the rules `policy` protects from `[ignore] paths`, `[allow]`
+    // values and patterns, and `min_confidence`. Call before `with_nested`.
+    pub fn with_policy(mut self, policy: Option<&Policy>) -> Result<Self, PolicyError> {
+        self.protected = policy.map(Policy::protected_rules).transpose()?;
+        Ok(self)
+    }
+
+    pub fn is_protected(&self, rule_id: &str) -> bool {
+        self.protected
+            .as_ref()
+            .is_some_and(|protected| protected.contains(rule_id))
+    }
+
+    // Whether any rule is protected, so files in ignored paths still need
+    // scanning for them.
+    pub fn protects_rules(&self) -> bool {
+        self.protected
+            .as_ref()
+            .is_some_and(|protected| !protected.is_empty())
+    }
+
+    // Applies the `.nosecrets.toml` files and `.nosecrets-fixtures` markers
+    // of the subdirectories of `root` to the files beneath them. They are
+    // read as files are checked, from the directories above each file; one
+    // that breaks the policy fails the files beneath it.
+    pub fn with_nested(mut self, root: &Path) -> Self {
+        self.nested = Some(NestedIndex::new(root, self.protected.clone()));
+        self
+    }
+
+    fn scope(&self, normalized_path: &str) -> Arc<Scope> {
+        match &self.nested {
+            Some(index) => index.scope_of(normalized_path),
+            None => Arc::default(),
+        }
+    }
+
+    // Asks the filters of the subdirectory configs above `path`, each with
+    // the path relative to its directory. Found entries are cloned, as the
+    // configs are shared with other threads.
+    fn find_nested<T>(
+        &self,
+        normalized_path: &str,
+        find: impl Fn(&Filter, &Path) -> Option<T>,
+    ) -> Option<T> {
+        self.scope(normalized_path)
+            .configs
+            .iter()
+            .find_map(|nested| find(nested.filter(), nested.scope(normalized_path)?))
+    }
+
+    // Fails when a subdirectory config above `path` cannot be used.
+    pub fn check_scope(&self, path: &Path) -> Result<(), ScopeError> {
+        match &self.scope(&normalize_path(path)).error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    // Identifies the subdirectory configs and markers that apply to `path`,
+    // e.g. for a cache of findings.
+    pub fn scope_state(&self, path: &Path) -> String {
+        let scope = self.scope(&normalize_path(path));
+        format!("{:?} {:?} {}", scope.configs, scope.error, scope.fixtures)
+    }
+
     pub fn is_path_ignored(&self, path: &Path) -> bool {
-        let Some(globset) = &self.ignore_paths else {
-            return false;
-        };
         let normalized = normalize_path(path);
-        globset.is_match(normalized)
+        self.ignore_paths
+            .as_ref()
+            .is_some_and(|globset| globset.is_match(&normalized))
+            || self
+                .find_nested(&normalized, |filter, path| {
+                    filter.is_path_ignored(path).then_some(())
+                })
+                .is_some()
     }
 
-    pub fn is_value_allowed(&self, value: &str) -> bool {
-        if self.allow_values.contains(value) {
-            return true;
+    // Beneath a directory with a `.nosecrets-fixtures` marker.
+    pub fn is_fixture_path(&self, path: &Path) -> bool {
+        self.scope(&normalize_path(path)).fixtures
+    }
+
+    pub fn is_value_allowed(&self, value: &str, path: &Path) -> bool {
+        self.value_allowance(value, path).is_some()
+    }
+
+    // The `allow.values` entry or `allow.patterns` regex that allows `value`
+    // in the file at `path`.
+    pub fn value_allowance(&self, value: &str, path: &Path) -> Option<String> {
+        if let Some(allowed) = self.allow_values.get(value) {
+            return Some(allowed.clone());
         }
-        self.allow_patterns
+        if let Some(regex) = self
+            .allow_patterns
             .iter()
-            .any(|regex| regex.is_match(value))
+            .find(|regex| regex.is_match(value))
+        {
+            return Some(regex.as_str().to_string());
+        }
+        let normalized = normalize_path(path);
+        self.find_nested(&normalized, |filter, path| {
+            filter.value_allowance(value, path)
+        })
+    }
+
+    // A rule listed in `allow.rules` without paths is allowed everywhere.
+    pub fn is_rule_allowed(&self, rule_id: &str, path: &Path) -> bool {
+        self.rule_allowance(rule_id, path).is_some()
+            || self.rule_ignore_entry(rule_id, path).is_some()
+    }
+
+    // The `allow.rules` entry that allows the rule at `path`.
+    pub fn rule_allowance(&self, rule_id: &str, path: &Path) -> Option<AllowRule> {
+        let normalized = normalize_path(path);
+        self.allow_rules
+            .iter()
+            .find(|allowed| allowed.matches(rule_id, &normalized))
+            .map(|allowed| allowed.entry.clone())
+            .or_else(|| {
+                self.find_nested(&normalized, |filter, path| {
+                    filter.rule_allowance(rule_id, path)
+                })
+            })
+    }
+
+    // Entries of the subdirectory configs read so far follow those of the
+    // root config.
+    pub fn allow_rules(&self) -> Vec<AllowRule> {
+        self.collect_allow_rules(|filter| &filter.allow_rules)
+    }
+
+    // Entries past their `expires` date, which no longer suppress anything.
+    pub fn expired_allow_rules(&self) -> Vec<AllowRule> {
+        self.collect_allow_rules(|filter| &filter.expired_allow_rules)
+    }
+
+    fn collect_allow_rules(&self, list: impl Fn(&Filter) -> &[AllowedRule]) -> Vec<AllowRule> {
+        let mut entries: Vec<AllowRule> = list(self)
+            .iter()
+            .map(|allowed| allowed.entry.clone())
+            .collect();
+        if let Some(index) = &self.nested {
+            for nested in index.read_configs() {
+                entries.extend(
+                    list(nested.filter())
+                        .iter()
+                        .map(|allowed| allowed.entry.clone()),
+                );
+            }
+        }
+        entries
+    }
+
+    pub fn ignore_entries(&self) -> &[IgnoreEntry] {
+        &self.ignore_entries
+    }
+
+    pub fn expired_ignore_entries(&self) -> &[IgnoreEntry] {
+        &self.expired_entries
+    }
+
+    // The `rule:` entry in .nosecretsignore that ignores the rule at `path`.
+    pub fn rule_ignore_entry(&self, rule_id: &str, path: &Path) -> Option<&IgnoreEntry> {
+        let normalized = normalize_path(path);
+        self.ignore_entries
+            .iter()
+            .find(|entry| entry.matches_rule(rule_id, &normalized))
     }
 
     pub fn is_fingerprint_ignored(&self, fingerprint: &str, path: &Path) -> bool {
+        self.fingerprint_ignore_entry(fingerprint, path).is_some()
+    }
+
+    pub fn fingerprint_ignore_entry(&self, fingerprint: &str, path: &Path) -> Option<&IgnoreEntry> {
+        let normalized = normalize_path(path);
+        self.ignore_entries
+            .iter()
+            .find(|entry| entry.matches(fingerprint, &normalized))
+    }
+
+    // Expiry date of an expired `allow.rules` or `rule:` ignore entry that
+    // would have allowed the rule at `path`.
+    pub fn expired_rule_allowance(&self, rule_id: &str, path: &Path) -> Option<String> {
+        let normalized = normalize_path(path);
+        self.expired_allow_rules
+            .iter()
+            .find(|allowed| allowed.matches(rule_id, &normalized))
+            .and_then(|allowed| allowed.entry.expires.clone())
+            .or_else(|| {
+                self.find_nested(&normalized, |filter, path| {
+                    filter.expired_rule_allowance(rule_id, path)
+                })
+            })
+            .or_else(|| {
+                self.expired_entries
+                    .iter()
+                    .find(|entry| entry.matches_rule(rule_id, &normalized))
+                    .and_then(|entry| entry.expires.clone())
+            })
+    }
+
+    // Expiry date of an expired ignore entry for the fingerprint at `path`.
+    pub fn expired_fingerprint_ignore(&self, fingerprint: &str, path: &Path) -> Option<&str> {
         let normalized = normalize_path(path);
-        self.ignore_entries.iter().any(|entry| {
-            if entry.fingerprint != fingerprint {
-                return false;
+        self.expired_entries
+            .iter()
+            .find(|entry| entry.matches(fingerprint, &normalized))
+            .and_then(|entry| entry.expires.as_deref())
+    }
+
+    pub fn is_inline_ignored(line: &str) -> bool {
+        line.contains("@nosecrets-ignore") || line.contains("@nsi")
+    }
+
+    // 1-based line ranges from `@nosecrets-ignore-start` to the next
+    // `@nosecrets-ignore-end`, markers included. A start without an end
+    // suppresses nothing.
+    pub fn ignored_regions(text: &str) -> Vec<RangeInclusive<usize>> {
+        if !text.contains(IGNORE_START_MARKER) {
+            return Vec::new();
+        }
+        let mut regions = Vec::new();
+        let mut start = None;
+        for (idx, line) in text.lines().enumerate() {
+            if line.contains(IGNORE_START_MARKER) {
+                start.get_or_insert(idx + 1);
+            } else if line.contains(IGNORE_END_MARKER) {
+                if let Some(start) = start.take() {
+                    regions.push(start..=idx + 1);
+                }
             }
-            match &entry.matcher {
-                Some(matcher) => matcher.is_match(&normalized),
-                None => true,
+        }
+        regions
+    }
+
+    // Looks for `@nosecrets-ignore-file reason="..." [rules=a,b]` in the
+    // first lines of a file. Directives without a reason are not honored.
+    pub fn file_ignore(text: &str) -> Option<FileIgnore> {
+        text.lines().take(FILE_IGNORE_LINES).find_map(|line| {
+            let (_, directive) = line.split_once(FILE_IGNORE_DIRECTIVE)?;
+            let mut ignore = FileIgnore {
+                rules: Vec::new(),
+                reason: String::new(),
+            };
+            for (key, value) in parse_metadata(directive) {
+                match key.as_str() {
+                    "reason" => ignore.reason = value,
+                    "rules" => ignore.rules.extend(
+                        value
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|rule| !rule.is_empty())
+                            .map(str::to_string),
+                    ),
+                    _ => {}
+                }
             }
+            (!ignore.reason.trim().is_empty()).then_some(ignore)
         })
     }
+}
 
-    pub fn is_inline_ignored(line: &str) -> bool {
-        line.contains("@nosecrets-ignore") || line.contains("@nsi")
+// Parses `key=value key="quoted value"` pairs.
+pub fn parse_metadata(text: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = text.trim();
+    while let Some(eq) = rest.find('=') {
+        // Words without `=` before a key, e.g. a comment marker, are dropped.
+        let key = rest[..eq]
+            .split_whitespace()
+            .next_back()
+            .unwrap_or_default()
+            .to_string();
+        let after = &rest[eq + 1..];
+        let (value, remaining) = match after.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => match after.find(char::is_whitespace) {
+                Some(end) => (&after[..end], &after[end..]),
+                None => (after, ""),
+            },
+        };
+        pairs.push((key, value.to_string()));
+        rest = remaining.trim_start();
     }
+    pairs
 }
 
-pub fn normalize_path(path: &Path) -> String {
-    let raw = path.to_string_lossy().replace('\\', "/");
-    raw.trim_start_matches("./").to_string()
+// Today's UTC date as YYYY-MM-DD, which compares correctly as a string.
+pub fn today() -> String {
+    utc_date(unix_now() as i64)
 }
 
-fn normalize_glob_pattern(pattern: &str) -> String {
-    let mut normalized = pattern.replace('\\', "/");
-    if normalized.ends_with('/') {
-        normalized.push_str("**");
+// The UTC date of a Unix time as YYYY-MM-DD.
+pub fn utc_date(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// The current UTC time as YYYY-MM-DDTHH:MM:SSZ.
+pub fn timestamp() -> String {
+    let secs = unix_now();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+impl AllowedRule {
+    fn matches(&self, rule_id: &str, normalized_path: &str) -> bool {
+        self.entry.rule == rule_id
+            && self
+                .paths
+                .as_ref()
+                .is_none_or(|globset| globset.is_match(normalized_path))
+    }
+}
+
+pub fn is_valid_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    if !digits(year, 4) || !digits(month, 2) || !digits(day, 2) {
+        return false;
     }
-    normalized
+    let month: u32 = month.parse().unwrap_or(0);
+    let day: u32 = day.parse().unwrap_or(0);
+    (1..=12).contains(&month) && (1..=31).contains(&day)
+}
+
+// Howard Hinnant's days-to-civil conversion.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn build_globset(patterns: &[String]) -> Result<Option<GlobSet>, FilterError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = path_glob(pattern).map_err(|error| FilterError::Glob {
+            pattern: normalize_glob_pattern(pattern),
+            error,
+        })?;
+        builder.add(glob);
+    }
+    let globset = builder.build().map_err(|error| FilterError::Glob {
+        pattern: "<globset>".to_string(),
+        error,
+    })?;
+    Ok(Some(globset))
 }
 
 #[cfg(test)]
@@ -237,9 +1205,28 @@ mod tests {
         config.allow.values = vec!["ALLOW_ME".to_string()];
         config.allow.patterns = vec!["^test_.*$".to_string()];
         let filter = Filter::from_config(Some(config), Vec::new()).expect("build filter");
-        assert!(filter.is_value_allowed("ALLOW_ME"));
-        assert!(filter.is_value_allowed("test_value"));
-        assert!(!filter.is_value_allowed("deny"));
+        assert!(filter.is_value_allowed("ALLOW_ME", Path::new("src/app.py")));
+        assert!(filter.is_value_allowed("test_value", Path::new("src/app.py")));
+        assert!(!filter.is_value_allowed("deny", Path::new("src/app.py")));
+    }
+
+    #[test]
+    fn allow_rules_scoped_to_paths() {
+        let config: Config = toml::from_str(
+            r#"
+            [allow]
+            rules = [
+                { rule = "generic-api-key", paths = ["tests/**"] },
+                { rule = "jwt" },
+            ]
+            "#,
+        )
+        .expect("parse config");
+        let filter = Filter::from_config(Some(config), Vec::new()).expect("build filter");
+        assert!(filter.is_rule_allowed("generic-api-key", Path::new("tests/fixtures/a.env")));
+        assert!(!filter.is_rule_allowed("generic-api-key", Path::new("src/main.rs")));
+        assert!(!filter.is_rule_allowed("aws-access-key", Path::new("tests/a.env")));
+        assert!(filter.is_rule_allowed("jwt", Path::new("src/main.rs")));
     }
 
     #[test]
@@ -253,6 +1240,333 @@ mod tests {
         assert!(!filter.is_fingerprint_ignored("nsi_123", Path::new("tests/main.rs")));
     }
 
+    #[test]
+    fn ignore_file_rule_entries() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join(".nosecretsignore");
+        fs::write(
+            &path,
+            "rule:generic-api-key:tests/
+             rule:jwt # reason=\"sample tokens\"
+             rule:aws-access-key:docs/** # expires=2000-01-01
+",
+        )
+        .expect("write ignore");
+        let all = read_ignore_file(&path).expect("read ignore");
+        assert_eq!(all[0].fingerprint, "rule:generic-api-key");
+        assert_eq!(all[0].rule.as_deref(), Some("generic-api-key"));
+        assert_eq!(all[0].path.as_deref(), Some("tests/"));
+        assert_eq!(all[1].path, None);
+        let filter = Filter::from_config(None, all).expect("build filter");
+        assert!(filter.is_rule_allowed("generic-api-key", Path::new("tests/a.rs")));
+        assert!(!filter.is_rule_allowed("generic-api-key", Path::new("src/a.rs")));
+        assert!(filter.is_rule_allowed("jwt", Path::new("src/a.rs")));
+        assert!(!filter.is_fingerprint_ignored("rule:jwt", Path::new("src/a.rs")));
+        assert!(!filter.is_rule_allowed("aws-access-key", Path::new("docs/a.md")));
+        assert_eq!(
+            filter.expired_rule_allowance("aws-access-key", Path::new("docs/a.md")),
+            Some("2000-01-01".to_string())
+        );
+    }
+
+    #[test]
+    fn expired_suppressions_are_reported_instead_of_applied() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join(".nosecretsignore");
+        fs::write(
+            &path,
+            "nsi_old:src/** # expires=2000-01-31
+nsi_new # expires=2999-01-31
+",
+        )
+        .expect("write ignore");
+        let config: Config = toml::from_str(
+            r#"
+            [allow]
+            rules = [
+                { rule = "jwt", expires = "2000-01-31" },
+                { rule = "generic-api-key", expires = "2999-01-31" },
+            ]
+            "#,
+        )
+        .expect("parse config");
+        let entries = read_ignore_file(&path).expect("read ignore");
+        let filter = Filter::from_config(Some(config), entries).expect("build filter");
+        let src = Path::new("src/main.rs");
+        assert!(!filter.is_fingerprint_ignored("nsi_old", src));
+        assert_eq!(
+            filter.expired_fingerprint_ignore("nsi_old", src),
+            Some("2000-01-31")
+        );
+        assert_eq!(
+            filter.expired_fingerprint_ignore("nsi_old", Path::new("a.rs")),
+            None
+        );
+        assert!(filter.is_fingerprint_ignored("nsi_new", src));
+        assert!(!filter.is_rule_allowed("jwt", src));
+        assert_eq!(
+            filter.expired_rule_allowance("jwt", src),
+            Some("2000-01-31".to_string())
+        );
+        assert!(filter.is_rule_allowed("generic-api-key", src));
+
+        let invalid: Config =
+            toml::from_str("[allow]\nrules = [{ rule = \"jwt\", expires = \"soon\" }]\n")
+                .expect("parse config");
+        assert!(Filter::from_config(Some(invalid), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn severity_overrides_prefer_exact_ids_then_longer_globs() {
+        let mut rules = nosecrets_rules::parse_rules(
+            r#"
+            [[rule]]
+            id = "generic-api-key"
+            name = "Generic API Key"
+            severity = "high"
+            pattern = "(x)"
+
+            [[rule]]
+            id = "generic-password"
+            name = "Password"
+            severity = "high"
+            pattern = "(x)"
+
+            [[rule]]
+            id = "github-pat"
+            name = "GitHub PAT"
+            severity = "critical"
+            pattern = "(x)"
+            "#,
+            "test.toml",
+        )
+        .expect("parse rules");
+        let config: Config = toml::from_str(
+            r#"
+            [severity]
+            "generic-*" = "low"
+            "generic-pass*" = "medium"
+            "generic-api-key" = "critical"
+            "#,
+        )
+        .expect("parse config");
+        config.apply_severity(&mut rules).expect("apply");
+        let severities: Vec<Severity> = rules.iter().map(|rule| rule.severity).collect();
+        assert_eq!(
+            severities,
+            vec![Severity::Critical, Severity::Medium, Severity::Critical]
+        );
+    }
+
+    #[test]
+    fn ignore_regions_need_an_end_marker() {
+        let text = "a\n# @nosecrets-ignore-start\nb\n# @nosecrets-ignore-end\nc\n\
+                    // @nosecrets-ignore-start\nd\n";
+        assert_eq!(Filter::ignored_regions(text), vec![2..=4]);
+        assert!(Filter::ignored_regions("a\nb\n").is_empty());
+    }
+
+    #[test]
+    fn ignore_file_metadata_and_expiry() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join(".nosecretsignore");
+        fs::write(
+            &path,
+            "nsi_aaa:tests/** # reason=\"test fixture\" by=alice expires=2999-01-01\n\
+             nsi_bbb # expires=2000-01-01\n",
+        )
+        .expect("write ignore");
+        let all = read_ignore_file(&path).expect("read ignore");
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].path.as_deref(), Some("tests/**"));
+        assert_eq!(all[0].reason.as_deref(), Some("test fixture"));
+        assert_eq!(all[0].by.as_deref(), Some("alice"));
+        assert!(all[1].is_expired(&today()));
+        let entries = load_ignore_file(&path).expect("load ignore");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].expires.as_deref(), Some("2999-01-01"));
+        let filter = Filter::from_config(None, entries).expect("build filter");
+        assert!(filter.is_fingerprint_ignored("nsi_aaa", Path::new("tests/a.rs")));
+        assert!(!filter.is_fingerprint_ignored("nsi_bbb", Path::new("tests/a.rs")));
+
+        for date in ["tomorrow", "12/31/2030"] {
+            fs::write(&path, format!("nsi_aaa\nnsi_ccc # expires={date}\n")).expect("write");
+            let error = read_ignore_file(&path).expect_err("invalid date");
+            assert!(matches!(error, FilterError::Invalid { key: "expires", .. }));
+            assert!(error.to_string().contains(":2"), "{error}");
+        }
+    }
+
+    #[test]
+    fn repo_config_merges_over_user_config() {
+        let dir = tempdir().expect("tempdir");
+        let user = dir.path().join("config.toml");
+        let repo = dir.path().join(".nosecrets.toml");
+        fs::write(
+            &user,
+            "[allow]\nvalues = [\"MY_DUMMY\"]\n[scan]\nmax_file_size = 100\n[report]\nformat = \"json\"\n",
+        )
+        .expect("write user config");
+        fs::write(
+            &repo,
+            "[allow]\nvalues = [\"REPO_DUMMY\"]\n[scan]\nmax_file_size = 200\n",
+        )
+        .expect("write repo config");
+        let config = Config::load_layers(&[user, repo.clone()])
+            .expect("load")
+            .expect("config");
+        assert_eq!(config.allow.values, vec!["MY_DUMMY", "REPO_DUMMY"]);
+        assert_eq!(config.scan.max_file_size, Some(200));
+        assert_eq!(config.report.format, Some(ReportFormat::Json));
+
+        fs::write(&repo, "[report]\nformat = \"xml\"\n").expect("write repo config");
+        let error = Config::load_layers(std::slice::from_ref(&repo)).expect_err("invalid format");
+        assert!(matches!(error, FilterError::Parse { path, .. } if path == repo));
+        assert!(Config::load_layers(&[]).expect("load").is_none());
+    }
+
+    #[test]
+    fn nested_configs_apply_beneath_their_directory() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::create_dir_all(root.join("teams/web/fixtures")).expect("mkdir");
+        fs::create_dir_all(root.join("teams/api")).expect("mkdir");
+        fs::write(
+            root.join(".nosecrets.toml"),
+            "[allow]\nvalues = [\"ROOT_DUMMY\"]\n",
+        )
+        .expect("write root config");
+        fs::write(
+            root.join("teams/web/.nosecrets.toml"),
+            "[ignore]\npaths = [\"fixtures/\"]\n[allow]\npatterns = [\"^web_test_\"]\nrules = [{ rule = \"jwt\", paths = [\"src/**\"] }]\n",
+        )
+        .expect("write nested config");
+        let config = Config::load_from_dir(root).expect("load");
+        let filter = Filter::from_config(config, Vec::new())
+            .expect("filter")
+            .with_nested(root);
+
+        let web = Path::new("teams/web/src/app.ts");
+        let api = Path::new("teams/api/src/app.ts");
+        assert!(filter.is_path_ignored(Path::new("teams/web/fixtures/keys.json")));
+        assert!(!filter.is_path_ignored(Path::new("fixtures/keys.json")));
+        assert!(filter.is_value_allowed("web_test_token", web));
+        assert!(!filter.is_value_allowed("web_test_token", api));
+        assert!(filter.is_value_allowed("ROOT_DUMMY", api));
+        assert!(filter.is_rule_allowed("jwt", web));
+        assert!(!filter.is_rule_allowed("jwt", api));
+        assert!(!filter.is_rule_allowed("jwt", Path::new("src/app.ts")));
+        assert_eq!(filter.allow_rules().len(), 1);
+
+        // Without a root config the nested one still applies.
+        let filter = Filter::from_config(None, Vec::new())
+            .expect("filter")
+            .with_nested(root);
+        assert!(filter.is_value_allowed("web_test_token", web));
+
+        // Files beneath an invalid config cannot be checked.
+        fs::write(root.join("teams/api/.nosecrets.toml"), "[allow\n").expect("write");
+        assert!(filter.check_scope(web).is_ok());
+        assert!(matches!(
+            filter.check_scope(api),
+            Err(ScopeError::Config { dir, .. }) if dir == "teams/api"
+        ));
+    }
+
+    #[test]
+    fn repo_config_may_be_yaml_or_json() {
+        let dir = tempdir().expect("tempdir");
+        assert!(Config::load_from_dir(dir.path()).expect("load").is_none());
+        let yaml = dir.path().join(".nosecrets.yaml");
+        fs::write(
+            &yaml,
+            "ignore:\n  paths: [vendor/]\nscan:\n  max_file_size: 100\nseverity:\n  \"generic-*\": low\n",
+        )
+        .expect("write yaml config");
+        let config = Config::load_from_dir(dir.path())
+            .expect("load")
+            .expect("config");
+        assert_eq!(config.ignore.paths, vec!["vendor/"]);
+        assert_eq!(config.scan.max_file_size, Some(100));
+        assert_eq!(config.severity["generic-*"], Severity::Low);
+
+        let json = dir.path().join("user.json");
+        fs::write(
+            &json,
+            r#"{"allow": {"values": ["MY_DUMMY"]}, "scan": {"max_file_size": 50}}"#,
+        )
+        .expect("write json config");
+        let config = Config::load_layers(&[json, yaml.clone()])
+            .expect("load")
+            .expect("config");
+        assert_eq!(config.allow.values, vec!["MY_DUMMY"]);
+        assert_eq!(config.scan.max_file_size, Some(100));
+
+        // Empty YAML keys and JSON nulls are unset, not errors.
+        let empty = dir.path().join("empty.yaml");
+        fs::write(
+            &empty,
+            "ignore:\n  paths:\nallow:\n  values: [~, MY_DUMMY]\n",
+        )
+        .expect("write yaml config");
+        let nulls = dir.path().join("nulls.json");
+        fs::write(
+            &nulls,
+            r#"{"scan": {"max_file_size": null}, "report": null}"#,
+        )
+        .expect("write json config");
+        let config = Config::load_layers(&[yaml.clone(), empty, nulls])
+            .expect("load")
+            .expect("config");
+        assert_eq!(config.ignore.paths, vec!["vendor/"]);
+        assert_eq!(config.allow.values, vec!["MY_DUMMY"]);
+        assert_eq!(config.scan.max_file_size, Some(100));
+
+        // The TOML file wins over the YAML one.
+        fs::write(
+            dir.path().join(".nosecrets.toml"),
+            "[scan]\nmax_file_size = 7\n",
+        )
+        .expect("write toml config");
+        let config = Config::load_from_dir(dir.path())
+            .expect("load")
+            .expect("config");
+        assert_eq!(config.scan.max_file_size, Some(7));
+        fs::write(&yaml, "scan: [oops\n").expect("write yaml config");
+        fs::remove_file(dir.path().join(".nosecrets.toml")).expect("remove");
+        let error = Config::load_from_dir(dir.path()).expect_err("invalid yaml");
+        assert!(matches!(error, FilterError::Parse { path, .. } if path == yaml));
+    }
+
+    #[test]
+    fn dates_are_computed_and_validated() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert!(is_valid_date(&today()));
+        assert!(!is_valid_date("2024-13-01"));
+        assert!(!is_valid_date("tomorrow"));
+    }
+
+    #[test]
+    fn file_ignore_directive() {
+        let text = "#!/bin/sh\n# @nosecrets-ignore-file reason=\"recorded fixtures\" rules=jwt,aws-access-key\n";
+        let ignore = Filter::file_ignore(text).expect("directive");
+        assert_eq!(ignore.reason, "recorded fixtures");
+        assert_eq!(ignore.rules, vec!["jwt", "aws-access-key"]);
+
+        let html = "<!-- @nosecrets-ignore-file reason=generated -->\n";
+        let ignore = Filter::file_ignore(html).expect("directive");
+        assert_eq!(ignore.reason, "generated");
+        assert!(ignore.rules.is_empty());
+
+        assert!(Filter::file_ignore("// @nosecrets-ignore-file\n").is_none());
+        let late = format!(
+            "{}// @nosecrets-ignore-file reason=x\n",
+            "\n".repeat(FILE_IGNORE_LINES)
+        );
+        assert!(Filter::file_ignore(&late).is_none());
+    }
+
     #[test]
     fn inline_ignore_detection() {
         assert!(Filter::is_inline_ignored(
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use thiserror::Error;
+
+use crate::policy::ProtectedRules;
+use crate::{repo_config_path, Config, Filter, PolicyError};
+
+// Marks its directory as test data: findings beneath it are suppressed.
+pub const FIXTURES_MARKER: &str = ".nosecrets-fixtures";
+
+// A subdirectory config that cannot be used. Files beneath it fail to scan
+// rather than being scanned without it.
+#[derive(Debug, Clone, Error)]
+pub enum ScopeError {
+    #[error("invalid config in {dir}: {message}")]
+    Config { dir: String, message: String },
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+}
+
+// A config file in a subdirectory of the repository. Only its `[ignore]`
+// and `[allow]` tables are read; they apply to the files beneath the
+// directory, with globs relative to it, and add to those of the configs
+// above.
+#[derive(Debug)]
+pub(crate) struct NestedFilter {
+    // The directory with a trailing `/`.
+    prefix: String,
+    filter: Filter,
+}
+
+impl NestedFilter {
+    pub(crate) fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    // `path` relative to this directory, when the file is beneath it.
+    pub(crate) fn scope<'a>(&self, normalized_path: &'a str) -> Option<&'a Path> {
+        normalized_path.strip_prefix(&self.prefix).map(Path::new)
+    }
+}
+
+// What applies to the files of a directory besides the root config.
+#[derive(Debug, Default)]
+pub(crate) struct Scope {
+    // The configs of the directory and its ancestors, outermost first.
+    pub(crate) configs: Vec<Arc<NestedFilter>>,
+    pub(crate) fixtures: bool,
+    pub(crate) error: Option<ScopeError>,
+    // Beneath a nested repository, whose configs are its own.
+    nested_repo: bool,
+}
+
+// The subdirectory configs and fixtures markers of a repository. A
+// directory is read the first time a file beneath it is checked, so only the
+// ancestors of scanned files are visited, and each only once.
+pub(crate) struct NestedIndex {
+    root: PathBuf,
+    protected: Option<ProtectedRules>,
+    scopes: RwLock<HashMap<String, Arc<Scope>>>,
+}
+
+// Only the root identifies the index; what it has read so far depends on
+// the files checked.
+impl fmt::Debug for NestedIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NestedIndex")
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NestedIndex {
+    pub(crate) fn new(root: &Path, protected: Option<ProtectedRules>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            protected,
+            scopes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // The scope of the directory holding `normalized_path`. Paths outside
+    // the root have none.
+    pub(crate) fn scope_of(&self, normalized_path: &str) -> Arc<Scope> {
+        if normalized_path.starts_with('/')
+            || normalized_path.starts_with("../")
+            || Path::new(normalized_path).is_absolute()
+        {
+            return Arc::default();
+        }
+        let dir = normalized_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        self.scope(dir)
+    }
+
+    // The configs of every directory read so far, each once, by directory.
+    pub(crate) fn read_configs(&self) -> Vec<Arc<NestedFilter>> {
+        let scopes = self.scopes.read().expect("scope lock");
+        let mut configs: Vec<Arc<NestedFilter>> = Vec::new();
+        for scope in scopes.values() {
+            for config in &scope.configs {
+                if !configs.iter().any(|known| Arc::ptr_eq(known, config)) {
+                    configs.push(config.clone());
+                }
+            }
+        }
+        configs.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+        configs
+    }
+
+    // The root has no scope: its config is the root config, and a marker
+    // there would suppress the whole repository.
+    fn scope(&self, dir: &str) -> Arc<Scope> {
+        if dir.is_empty() {
+            return Arc::default();
+        }
+        if let Some(scope) = self.scopes.read().expect("scope lock").get(dir) {
+            return scope.clone();
+        }
+        let parent = self.scope(dir.rsplit_once('/').map_or("", |(parent, _)| parent));
+        let scope = Arc::new(self.load(dir, &parent));
+        self.scopes
+            .write()
+            .expect("scope lock")
+            .entry(dir.to_string())
+            .or_insert(scope)
+            .clone()
+    }
+
+    fn load(&self, dir: &str, parent: &Scope) -> Scope {
+        let path = self.root.join(dir);
+        let mut scope = Scope {
+            configs: parent.configs.clone(),
+            fixtures: parent.fixtures,
+            error: parent.error.clone(),
+            nested_repo: parent.nested_repo || path.join(".git").exists(),
+        };
+        if scope.nested_repo {
+            return scope;
+        }
+        if path.join(FIXTURES_MARKER).is_file() {
+            match &self.protected {
+                // A marker suppresses every rule, the protected ones too.
+                Some(protected) if !protected.is_empty() => {
+                    let violation = format!("{dir}/{FIXTURES_MARKER} suppresses protected rules");
+                    scope
+                        .error
+                        .get_or_insert(PolicyError::Violated(vec![violation]).into());
+                }
+                _ => scope.fixtures = true,
+            }
+        }
+        match self.load_filter(dir, &path) {
+            Ok(Some(filter)) => scope.configs.push(Arc::new(NestedFilter {
+                prefix: format!("{dir}/"),
+                filter,
+            })),
+            Ok(None) => {}
+            Err(error) => {
+                scope.error.get_or_insert(error);
+            }
+        }
+        scope
+    }
+
+    fn load_filter(&self, dir: &str, path: &Path) -> Result<Option<Filter>, ScopeError> {
+        let invalid = |message: String| ScopeError::Config {
+            dir: dir.to_string(),
+            message,
+        };
+        let Some(config_path) = repo_config_path(path) else {
+            return Ok(None);
+        };
+        let Some(loaded) = Config::load_layers(std::slice::from_ref(&config_path))
+            .map_err(|error| invalid(error.to_string()))?
+        else {
+            return Ok(None);
+        };
+        if let Some(protected) = &self.protected {
+            let violations = protected.allow_rule_violations(&loaded.allow.rules);
+            if !violations.is_empty() {
+                let name = config_path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let violations = violations
+                    .into_iter()
+                    .map(|violation| format!("{dir}/{name}: {violation}"))
+                    .collect();
+                return Err(PolicyError::Violated(violations).into());
+            }
+        }
+        let config = Config {
+            ignore: loaded.ignore,
+            allow: loaded.allow,
+            ..Config::default()
+        };
+        Filter::from_config(Some(config), Vec::new())
+            .map(Some)
+            .map_err(|error| invalid(error.to_string()))
+    }
+}
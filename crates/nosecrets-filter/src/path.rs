@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobBuilder};
+
+// Windows file systems are case-insensitive, so path globs are too.
+const CASE_INSENSITIVE: bool = cfg!(windows);
+
+// Forward slashes, no `./` prefix, verbatim (`\\?\`) prefixes removed and
+// drive letters upper-cased, so `c:\repo\a.rs`, `\\?\C:\repo\a.rs` and
+// `C:/repo/a.rs` compare equal. `\\?\UNC\server\share` becomes
+// `//server/share`.
+pub fn normalize_path(path: &Path) -> String {
+    normalize_path_str(&path.to_string_lossy())
+}
+
+fn normalize_path_str(raw: &str) -> String {
+    let mut normalized = raw.replace('\\', "/");
+    if let Some(rest) = normalized.strip_prefix("//?/UNC/") {
+        normalized = format!("//{rest}");
+    } else if let Some(rest) = normalized
+        .strip_prefix("//?/")
+        .or_else(|| normalized.strip_prefix("//./"))
+    {
+        normalized = rest.to_string();
+    }
+    if let [drive, b':', ..] = normalized.as_bytes() {
+        if drive.is_ascii_lowercase() {
+            normalized[..1].make_ascii_uppercase();
+        }
+    }
+    let mut trimmed = normalized.as_str();
+    while let Some(rest) = trimmed.strip_prefix("./") {
+        trimmed = rest;
+    }
+    trimmed.to_string()
+}
+
+// Patterns take the same separators and prefixes as paths; a trailing `/`
+// matches everything below the directory.
+pub fn normalize_glob_pattern(pattern: &str) -> String {
+    let mut normalized = normalize_path_str(pattern);
+    if normalized.ends_with('/') {
+        normalized.push_str("**");
+    }
+    normalized
+}
+
+// Compiles a path glob from config, rules or .nosecretsignore.
+pub fn path_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(&normalize_glob_pattern(pattern))
+        .case_insensitive(CASE_INSENSITIVE)
+        .build()
+}
+
+// `path` relative to `root`. Falls back to comparing normalized paths, so a
+// root found through git (`C:/repo`) still strips an input such as
+// `c:\repo\src\a.rs` or `\\?\C:\repo\src\a.rs`. Paths outside the root are
+// returned unchanged.
+pub fn relative_path(path: &Path, root: &Path) -> PathBuf {
+    if let Ok(relative) = path.strip_prefix(root) {
+        return relative.to_path_buf();
+    }
+    let normalized = normalize_path(path);
+    let root = normalize_path(root);
+    let root = root.trim_end_matches('/');
+    let relative = normalized
+        .get(..root.len())
+        .filter(|prefix| {
+            !root.is_empty()
+                && if CASE_INSENSITIVE {
+                    prefix.eq_ignore_ascii_case(root)
+                } else {
+                    *prefix == root
+                }
+        })
+        .and_then(|_| normalized[root.len()..].strip_prefix('/'));
+    match relative {
+        Some(relative) => PathBuf::from(relative),
+        None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_windows_separators_and_prefixes() {
+        assert_eq!(
+            normalize_path_str(r"tests\fixtures\a.env"),
+            "tests/fixtures/a.env"
+        );
+        assert_eq!(normalize_path_str(r".\src\main.rs"), "src/main.rs");
+        assert_eq!(normalize_path_str(r"c:\repo\a.rs"), "C:/repo/a.rs");
+        assert_eq!(normalize_path_str(r"\\?\C:\repo\a.rs"), "C:/repo/a.rs");
+        assert_eq!(
+            normalize_path_str(r"\\?\UNC\server\share\a.rs"),
+            "//server/share/a.rs"
+        );
+        assert_eq!(
+            normalize_path_str(r"\\server\share\a.rs"),
+            "//server/share/a.rs"
+        );
+        assert_eq!(normalize_glob_pattern(r"tests\"), "tests/**");
+        assert_eq!(normalize_glob_pattern(r".\vendor\"), "vendor/**");
+    }
+
+    #[test]
+    fn backslash_patterns_match_backslash_paths() {
+        let matcher = path_glob(r"tests\").expect("glob").compile_matcher();
+        assert!(matcher.is_match(normalize_path_str(r"tests\unit\a.rs")));
+        assert!(!matcher.is_match(normalize_path_str(r"src\tests.rs")));
+    }
+
+    #[test]
+    fn relative_path_strips_equivalent_roots() {
+        assert_eq!(
+            relative_path(Path::new("/repo/src/a.rs"), Path::new("/repo/")),
+            PathBuf::from("src/a.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/other/a.rs"), Path::new("/repo")),
+            PathBuf::from("/other/a.rs")
+        );
+        assert_eq!(
+            relative_path(Path::new("/repository/a.rs"), Path::new("/repo")),
+            PathBuf::from("/repository/a.rs")
+        );
+    }
+
+    #[cfg(windows)]
+    mod windows {
+        use super::*;
+
+        #[test]
+        fn relative_path_ignores_drive_case_and_verbatim_prefix() {
+            let root = Path::new(r"C:\Work\Repo");
+            assert_eq!(
+                relative_path(Path::new(r"c:\work\repo\tests\a.env"), root),
+                PathBuf::from("tests/a.env")
+            );
+            assert_eq!(
+                relative_path(Path::new(r"\\?\C:\Work\Repo\src\a.rs"), root),
+                PathBuf::from("src/a.rs")
+            );
+            assert_eq!(
+                relative_path(
+                    Path::new(r"\\?\UNC\server\share\repo\a.rs"),
+                    Path::new(r"\\server\share\repo")
+                ),
+                PathBuf::from("a.rs")
+            );
+        }
+
+        #[test]
+        fn path_globs_ignore_case() {
+            let matcher = path_glob("tests/").expect("glob").compile_matcher();
+            assert!(matcher.is_match(normalize_path(Path::new(r"Tests\Unit\a.rs"))));
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+use nosecrets_rules::{parse_rules, Rule, Severity, BUILTIN_RULE_FILES};
+
+use crate::{today, AllowRule, Config, FilterError, IgnoreEntry};
+
+pub const POLICY_FILE: &str = ".nosecrets.policy.toml";
+
+// Settings a team commits next to .nosecrets.toml. Only the repository's
+// policy file is read, so neither the user config nor flags can loosen it.
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    // Scans fail at this severity or a lower one; `--fail-on` may only be
+    // stricter.
+    pub fail_on: Option<Severity>,
+    // Rule ids or globs of rule ids that may not be disabled.
+    #[serde(default)]
+    pub forbid_disable: Vec<String>,
+    // Built-in rule files by name (`cloud`, `payment`, ...) whose rules may
+    // not be disabled.
+    #[serde(default)]
+    pub required_packs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum PolicyError {
+    #[error("invalid {POLICY_FILE}: {0}")]
+    Invalid(String),
+    #[error("{POLICY_FILE} is violated: {}", .0.join("; "))]
+    Violated(Vec<String>),
+}
+
+impl Policy {
+    pub fn load(dir: &Path) -> Result<Option<Self>, FilterError> {
+        let path = dir.join(POLICY_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).map_err(|error| FilterError::Read {
+            path: path.clone(),
+            error,
+        })?;
+        let policy = toml::from_str(&content).map_err(|error| FilterError::Parse {
+            path,
+            error: error.into(),
+        })?;
+        Ok(Some(policy))
+    }
+
+    // The severity a scan fails at: the requested one, or the policy's when
+    // none was requested. A less strict request is a violation.
+    pub fn fail_on(
+        policy: Option<&Self>,
+        requested: Option<Severity>,
+        dry_run: bool,
+    ) -> Result<Severity, PolicyError> {
+        let required = policy.and_then(|policy| policy.fail_on);
+        let Some(required) = required else {
+            return Ok(requested.unwrap_or(Severity::Medium));
+        };
+        let mut violations = Vec::new();
+        if dry_run {
+            violations.push("--dry-run ignores the required fail_on".to_string());
+        }
+        let fail_on = requested.unwrap_or(required);
+        if !required.blocks_at(fail_on) {
+            violations.push(format!(
+                "--fail-on {} is less strict than the required {}",
+                fail_on.as_str(),
+                required.as_str()
+            ));
+        }
+        if violations.is_empty() {
+            Ok(fail_on)
+        } else {
+            Err(PolicyError::Violated(violations))
+        }
+    }
+
+    // Disables of protected rules: unexpired `allow.rules` entries and
+    // `rule:` ignore entries, also those scoped to paths, and `[severity]`
+    // overrides that lower a rule.
+    pub fn check(
+        &self,
+        config: Option<&Config>,
+        ignore_entries: &[IgnoreEntry],
+        rules: &[Rule],
+    ) -> Result<(), PolicyError> {
+        let protected = self.protected_rules()?;
+        let today = today();
+        let mut violations = Vec::new();
+        if let Some(config) = config {
+            violations.extend(protected.allow_rule_violations(&config.allow.rules));
+            let mut lowered = rules.to_vec();
+            config
+                .apply_severity(&mut lowered)
+                .map_err(|error| PolicyError::Invalid(error.to_string()))?;
+            for (rule, lowered) in rules.iter().zip(&lowered) {
+                if lowered.severity > rule.severity && protected.contains(&rule.id) {
+                    violations.push(format!(
+                        "[severity] lowers {} from {} to {}",
+                        rule.id,
+                        rule.severity.as_str(),
+                        lowered.severity.as_str()
+                    ));
+                }
+            }
+        }
+        for entry in ignore_entries {
+            if let Some(rule) = entry.rule.as_deref() {
+                if !entry.is_expired(&today) && protected.contains(rule) {
+                    violations.push(format!(
+                        ".nosecretsignore disables {rule}{}",
+                        scope(entry.path.as_deref().unwrap_or_default())
+                    ));
+                }
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(PolicyError::Violated(violations))
+        }
+    }
+
+    pub(crate) fn protected_rules(&self) -> Result<ProtectedRules, PolicyError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.forbid_disable {
+            let glob = Glob::new(pattern).map_err(|error| {
+                PolicyError::Invalid(format!("forbid_disable pattern {pattern}: {error}"))
+            })?;
+            builder.add(glob);
+        }
+        for name in &self.required_packs {
+            let Some((source, content)) = BUILTIN_RULE_FILES
+                .iter()
+                .find(|(source, _)| pack_name(source) == name)
+            else {
+                let known: Vec<&str> = BUILTIN_RULE_FILES
+                    .iter()
+                    .map(|(source, _)| pack_name(source))
+                    .collect();
+                return Err(PolicyError::Invalid(format!(
+                    "unknown rule pack {name}; built-in packs: {}",
+                    known.join(", ")
+                )));
+            };
+            let rules = parse_rules(content, source)
+                .map_err(|error| PolicyError::Invalid(error.to_string()))?;
+            for rule in rules {
+                builder.add(Glob::new(&globset::escape(&rule.id)).expect("escaped rule id"));
+            }
+        }
+        builder
+            .build()
+            .map(ProtectedRules)
+            .map_err(|error| PolicyError::Invalid(error.to_string()))
+    }
+}
+
+// The rules a policy forbids to disable, also by a variant id such as
+// `aws-access-key.env`.
+#[derive(Debug, Clone)]
+pub(crate) struct ProtectedRules(GlobSet);
+
+impl ProtectedRules {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn contains(&self, rule_id: &str) -> bool {
+        let base = rule_id.split_once('.').map_or(rule_id, |(base, _)| base);
+        self.0.is_match(rule_id) || self.0.is_match(base)
+    }
+
+    // Unexpired `allow.rules` entries of protected rules.
+    pub(crate) fn allow_rule_violations(&self, entries: &[AllowRule]) -> Vec<String> {
+        let today = today();
+        entries
+            .iter()
+            .filter(|allowed| {
+                let expired = allowed
+                    .expires
+                    .as_deref()
+                    .is_some_and(|date| date < today.as_str());
+                !expired && self.contains(&allowed.rule)
+            })
+            .map(|allowed| {
+                format!(
+                    "allow.rules disables {}{}",
+                    allowed.rule,
+                    scope(&allowed.paths.join(", "))
+                )
+            })
+            .collect()
+    }
+}
+
+// ` in <paths>` for a violation limited to paths.
+fn scope(paths: &str) -> String {
+    if paths.is_empty() {
+        String::new()
+    } else {
+        format!(" in {paths}")
+    }
+}
+
+// `rules/cloud.toml` is the `cloud` pack.
+fn pack_name(source: &str) -> &str {
+    let name = source.rsplit('/').next().unwrap_or(source);
+    name.strip_suffix(".toml").unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nosecrets_rules::load_builtin_rules;
+
+    #[test]
+    fn fail_on_may_only_be_stricter_than_the_policy() {
+        let policy = Policy {
+            fail_on: Some(Severity::High),
+            ..Policy::default()
+        };
+        assert_eq!(
+            Policy::fail_on(Some(&policy), None, false).unwrap(),
+            Severity::High
+        );
+        assert_eq!(
+            Policy::fail_on(Some(&policy), Some(Severity::Low), false).unwrap(),
+            Severity::Low
+        );
+        assert!(Policy::fail_on(Some(&policy), Some(Severity::Critical), false).is_err());
+        assert!(Policy::fail_on(Some(&policy), None, true).is_err());
+        assert_eq!(Policy::fail_on(None, None, true).unwrap(), Severity::Medium);
+    }
+
+    #[test]
+    fn protected_rules_cannot_be_disabled() {
+        let policy = Policy {
+            forbid_disable: vec!["aws-*".to_string()],
+            required_packs: vec!["payment".to_string()],
+            ..Policy::default()
+        };
+        let rules = load_builtin_rules().unwrap();
+        let mut config = Config::default();
+        config.allow.rules = vec![
+            AllowRule {
+                rule: "aws-access-key".to_string(),
+                ..AllowRule::default()
+            },
+            AllowRule {
+                rule: "aws-access-key".to_string(),
+                paths: vec!["tests/**".to_string()],
+                ..AllowRule::default()
+            },
+            AllowRule {
+                rule: "generic-api-key".to_string(),
+                ..AllowRule::default()
+            },
+        ];
+        config
+            .severity
+            .insert("stripe-*".to_string(), Severity::Low);
+        let Err(PolicyError::Violated(violations)) = policy.check(Some(&config), &[], &rules)
+        else {
+            panic!("expected violations");
+        };
+        assert_eq!(violations[0], "allow.rules disables aws-access-key");
+        assert_eq!(
+            violations[1],
+            "allow.rules disables aws-access-key in tests/**"
+        );
+        assert!(violations[2..]
+            .iter()
+            .all(|violation| violation.starts_with("[severity] lowers stripe-")));
+        assert!(violations.len() > 2);
+
+        // A catch-all path is no less a disable than no path.
+        config.allow.rules = vec![AllowRule {
+            rule: "aws-access-key".to_string(),
+            paths: vec!["**".to_string()],
+            ..AllowRule::default()
+        }];
+        config.severity.clear();
+        let dir = tempfile::tempdir().expect("tempdir");
+        let ignore_path = dir.path().join(".nosecretsignore");
+        std::fs::write(&ignore_path, "rule:aws-access-key:**\n").expect("write");
+        let ignore_entries = crate::read_ignore_file(&ignore_path).expect("read");
+        let Err(PolicyError::Violated(violations)) =
+            policy.check(Some(&config), &ignore_entries, &rules)
+        else {
+            panic!("expected violations");
+        };
+        assert_eq!(
+            violations,
+            [
+                "allow.rules disables aws-access-key in **",
+                ".nosecretsignore disables aws-access-key in **",
+            ]
+        );
+
+        let unknown = Policy {
+            required_packs: vec!["nope".to_string()],
+            ..Policy::default()
+        };
+        assert!(matches!(
+            unknown.check(None, &[], &rules),
+            Err(PolicyError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn nested_configs_cannot_disable_protected_rules() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("teams/web/src")).expect("mkdir");
+        std::fs::create_dir_all(root.join("teams/api")).expect("mkdir");
+        std::fs::write(
+            root.join("teams/web/.nosecrets.toml"),
+            "[allow]\nrules = [{ rule = \"aws-access-key\", paths = [\"src/**\"] }]\n",
+        )
+        .expect("write nested config");
+        let policy = Policy {
+            forbid_disable: vec!["aws-*".to_string()],
+            ..Policy::default()
+        };
+        let filter = crate::Filter::from_config(None, Vec::new())
+            .expect("filter")
+            .with_policy(Some(&policy))
+            .expect("policy")
+            .with_nested(root);
+
+        let web = Path::new("teams/web/src/app.ts");
+        let Err(crate::ScopeError::Policy(PolicyError::Violated(violations))) =
+            filter.check_scope(web)
+        else {
+            panic!("expected violations");
+        };
+        assert_eq!(
+            violations,
+            ["teams/web/.nosecrets.toml: allow.rules disables aws-access-key in src/**"]
+        );
+        assert!(!filter.is_rule_allowed("aws-access-key", web));
+        assert!(filter.check_scope(Path::new("teams/api/app.ts")).is_ok());
+
+        // Fixtures markers suppress protected rules as well.
+        std::fs::write(root.join("teams/api").join(crate::FIXTURES_MARKER), "").expect("write");
+        let filter = crate::Filter::from_config(None, Vec::new())
+            .expect("filter")
+            .with_policy(Some(&policy))
+            .expect("policy")
+            .with_nested(root);
+        let api = Path::new("teams/api/app.ts");
+        assert!(matches!(
+            filter.check_scope(api),
+            Err(crate::ScopeError::Policy(PolicyError::Violated(violations)))
+                if violations == ["teams/api/.nosecrets-fixtures suppresses protected rules"]
+        ));
+        assert!(!filter.is_fixture_path(api));
+        let filter = crate::Filter::from_config(None, Vec::new())
+            .expect("filter")
+            .with_policy(Some(&Policy::default()))
+            .expect("policy")
+            .with_nested(root);
+        assert!(filter.is_fixture_path(api));
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use console::style;
+use serde::Serialize;
+
+use crate::{location, write_terminal_findings, Finding, ReportError, TerminalOptions};
+
+// Findings of two scans matched by fingerprint and path, so a secret keeps
+// its identity when lines above it move.
+#[derive(Debug, Default, Serialize)]
+pub struct ReportDiff {
+    pub new: Vec<Finding>,
+    pub resolved: Vec<Finding>,
+    pub persisting: Vec<Finding>,
+}
+
+// Reads a `--format json` report, or the finding records of a
+// `--format ndjson` report.
+pub fn parse_findings(text: &str) -> Result<Vec<Finding>, ReportError> {
+    if text.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(text)?);
+    }
+    let mut findings = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let record: serde_json::Value = serde_json::from_str(line)?;
+        if record.get("type").and_then(|kind| kind.as_str()) == Some("finding") {
+            findings.push(serde_json::from_value(record)?);
+        }
+    }
+    Ok(findings)
+}
+
+fn key(finding: &Finding) -> (String, String) {
+    (finding.fingerprint.clone(), finding.path.clone())
+}
+
+impl ReportDiff {
+    // A secret found twice in the same file counts twice: one more copy in
+    // `new` is a new finding.
+    pub fn new(old: Vec<Finding>, new: Vec<Finding>) -> Self {
+        let mut unmatched: HashMap<(String, String), usize> = HashMap::new();
+        for finding in &old {
+            *unmatched.entry(key(finding)).or_default() += 1;
+        }
+        let mut diff = Self::default();
+        for finding in new {
+            match unmatched.get_mut(&key(&finding)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    diff.persisting.push(finding);
+                }
+                _ => diff.new.push(finding),
+            }
+        }
+        for finding in old {
+            if let Some(count) = unmatched
+                .get_mut(&key(&finding))
+                .filter(|count| **count > 0)
+            {
+                *count -= 1;
+                diff.resolved.push(finding);
+            }
+        }
+        diff
+    }
+
+    // Only new findings fail, so a gate can require "no new secrets" without
+    // a baseline of the existing ones.
+    pub fn exit_code(&self) -> i32 {
+        if self.new.is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+
+    pub fn print_json(&self) -> Result<(), ReportError> {
+        let mut out = io::stdout();
+        let json = serde_json::to_string_pretty(self)?;
+        writeln!(out, "{}", json)?;
+        Ok(())
+    }
+
+    pub fn print_terminal(&self) -> Result<(), ReportError> {
+        let mut out = io::stdout();
+        let sections = [
+            ("New", &self.new),
+            ("Resolved", &self.resolved),
+            ("Persisting", &self.persisting),
+        ];
+        let width = sections
+            .iter()
+            .flat_map(|(_, findings)| findings.iter())
+            .map(|finding| location(finding).chars().count())
+            .max()
+            .unwrap_or(0);
+        for (idx, (title, findings)) in sections.into_iter().enumerate() {
+            if idx > 0 {
+                writeln!(out)?;
+            }
+            let heading = format!("{title} ({})", findings.len());
+            let heading = match title {
+                "New" if !findings.is_empty() => style(heading).red().bold(),
+                _ => style(heading).bold(),
+            };
+            writeln!(out, "{heading}")?;
+            let findings: Vec<&Finding> = findings.iter().collect();
+            write_terminal_findings(&mut out, &findings, width, &TerminalOptions::default())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(entries: &[(&str, &str, usize)]) -> String {
+        let findings: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(fingerprint, path, line)| {
+                serde_json::json!({
+                    "path": path,
+                    "line": line,
+                    "column": 1,
+                    "rule_id": "aws-access-key",
+                    "rule_name": "AWS Access Key",
+                    "severity": "critical",
+                    "fingerprint": fingerprint,
+                    "preview": "AKIA...",
+                })
+            })
+            .collect();
+        serde_json::to_string(&findings).expect("json")
+    }
+
+    #[test]
+    fn matches_findings_by_fingerprint_and_path() {
+        let old = report(&[
+            ("nsi_a", "src/a.rs", 3),
+            ("nsi_b", "src/b.rs", 1),
+            ("nsi_c", "src/c.rs", 1),
+        ]);
+        let new = report(&[
+            ("nsi_a", "src/a.rs", 10),
+            ("nsi_a", "src/a.rs", 12),
+            ("nsi_b", "src/moved.rs", 1),
+            ("nsi_c", "src/c.rs", 1),
+        ]);
+        let diff = ReportDiff::new(
+            parse_findings(&old).expect("old"),
+            parse_findings(&new).expect("new"),
+        );
+        let keys = |findings: &[Finding]| -> Vec<(String, usize)> {
+            findings
+                .iter()
+                .map(|finding| (finding.path.clone(), finding.line))
+                .collect()
+        };
+        assert_eq!(
+            keys(&diff.new),
+            vec![("src/a.rs".into(), 12), ("src/moved.rs".into(), 1)]
+        );
+        assert_eq!(keys(&diff.resolved), vec![("src/b.rs".into(), 1)]);
+        assert_eq!(
+            keys(&diff.persisting),
+            vec![("src/a.rs".into(), 10), ("src/c.rs".into(), 1)]
+        );
+        assert_eq!(diff.exit_code(), 1);
+        assert_eq!(ReportDiff::new(Vec::new(), Vec::new()).exit_code(), 0);
+    }
+
+    #[test]
+    fn reads_ndjson_finding_records() {
+        let finding = report(&[("nsi_a", "src/a.rs", 3)]);
+        let record = finding
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .replacen('{', "{\"type\":\"finding\",", 1);
+        let ndjson = format!("{record}\n{{\"type\":\"summary\",\"files_scanned\":1}}\n");
+        let findings = parse_findings(&ndjson).expect("ndjson");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].end_line, 0);
+    }
+}
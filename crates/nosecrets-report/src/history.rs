@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::{Finding, ReportError, SeverityCounts, Summary};
+
+pub const HISTORY_FILE: &str = ".nosecrets/history.jsonl";
+
+// One `nosecrets scan --record` run. Only fingerprints are kept, so the
+// history shows no paths or previews of the secrets.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HistoryRecord {
+    pub recorded_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub files_scanned: usize,
+    pub findings: usize,
+    pub severity: SeverityCounts,
+    pub fingerprints: Vec<String>,
+}
+
+impl HistoryRecord {
+    pub fn new(
+        recorded_at: String,
+        commit: Option<String>,
+        summary: &Summary,
+        findings: &[Finding],
+    ) -> Self {
+        let fingerprints: BTreeSet<&str> = findings
+            .iter()
+            .map(|finding| finding.fingerprint.as_str())
+            .collect();
+        Self {
+            recorded_at,
+            commit,
+            files_scanned: summary.files_scanned,
+            findings: summary.findings,
+            severity: summary.severity.clone(),
+            fingerprints: fingerprints.into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+pub fn append_history(path: &Path, record: &HistoryRecord) -> Result<(), ReportError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+// Records in the order they were written; a missing file is an empty history.
+pub fn read_history(path: &Path) -> Result<Vec<HistoryRecord>, ReportError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct TrendPoint {
+    pub recorded_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub findings: usize,
+    pub severity: SeverityCounts,
+    // Fingerprints not in the previous record, and those that left it.
+    pub new: usize,
+    pub resolved: usize,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct FirstSeen {
+    pub fingerprint: String,
+    pub first_seen: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct Trends {
+    pub scans: Vec<TrendPoint>,
+    // Findings of the latest record, oldest first.
+    pub open: Vec<FirstSeen>,
+    // Fingerprints seen in any record but not in the latest one.
+    pub resolved: usize,
+}
+
+impl Trends {
+    pub fn new(history: &[HistoryRecord]) -> Self {
+        let mut trends = Self::default();
+        let mut first_seen: HashMap<&str, &HistoryRecord> = HashMap::new();
+        let mut previous: BTreeSet<&str> = BTreeSet::new();
+        for record in history {
+            let current: BTreeSet<&str> = record.fingerprints.iter().map(String::as_str).collect();
+            for fingerprint in &current {
+                first_seen.entry(fingerprint).or_insert(record);
+            }
+            trends.scans.push(TrendPoint {
+                recorded_at: record.recorded_at.clone(),
+                commit: record.commit.clone(),
+                findings: record.findings,
+                severity: record.severity.clone(),
+                new: current.difference(&previous).count(),
+                resolved: previous.difference(&current).count(),
+            });
+            previous = current;
+        }
+        trends.open = previous
+            .iter()
+            .map(|fingerprint| {
+                let record = first_seen[fingerprint];
+                FirstSeen {
+                    fingerprint: fingerprint.to_string(),
+                    first_seen: record.recorded_at.clone(),
+                    commit: record.commit.clone(),
+                }
+            })
+            .collect();
+        trends
+            .open
+            .sort_by(|a, b| (&a.first_seen, &a.fingerprint).cmp(&(&b.first_seen, &b.fingerprint)));
+        trends.resolved = first_seen.len() - previous.len();
+        trends
+    }
+
+    pub fn print_json(&self) -> Result<(), ReportError> {
+        let mut out = io::stdout();
+        writeln!(out, "{}", serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn print_text(&self) -> Result<(), ReportError> {
+        let mut out = io::stdout();
+        if self.scans.is_empty() {
+            writeln!(out, "No scans recorded; run `nosecrets scan --record`.")?;
+            return Ok(());
+        }
+        writeln!(
+            out,
+            "{:<20}  {:<12}  {:>8}  {:>8}  {:>4}  {:>6}  {:>3}  {:>4}  {:>8}",
+            "recorded",
+            "commit",
+            "findings",
+            "critical",
+            "high",
+            "medium",
+            "low",
+            "new",
+            "resolved"
+        )?;
+        for scan in &self.scans {
+            writeln!(
+                out,
+                "{:<20}  {:<12}  {:>8}  {:>8}  {:>4}  {:>6}  {:>3}  {:>4}  {:>8}",
+                scan.recorded_at,
+                scan.commit.as_deref().unwrap_or("-"),
+                scan.findings,
+                scan.severity.critical,
+                scan.severity.high,
+                scan.severity.medium,
+                scan.severity.low,
+                scan.new,
+                scan.resolved
+            )?;
+        }
+        writeln!(
+            out,
+            "\nOpen: {}, resolved since the first record: {}",
+            self.open.len(),
+            self.resolved
+        )?;
+        for open in &self.open {
+            writeln!(
+                out,
+                "  {}  first seen {}{}",
+                open.fingerprint,
+                open.first_seen,
+                open.commit
+                    .as_deref()
+                    .map(|commit| format!(" ({commit})"))
+                    .unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(recorded_at: &str, fingerprints: &[&str]) -> HistoryRecord {
+        HistoryRecord {
+            recorded_at: recorded_at.to_string(),
+            commit: None,
+            files_scanned: 1,
+            findings: fingerprints.len(),
+            severity: SeverityCounts::default(),
+            fingerprints: fingerprints.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn trends_track_new_resolved_and_first_seen() {
+        let history = [
+            record("2026-01-01T00:00:00Z", &["nsi_a", "nsi_b"]),
+            record("2026-01-02T00:00:00Z", &["nsi_a", "nsi_c"]),
+            record("2026-01-03T00:00:00Z", &["nsi_c"]),
+        ];
+        let trends = Trends::new(&history);
+        let changes: Vec<(usize, usize)> = trends
+            .scans
+            .iter()
+            .map(|scan| (scan.new, scan.resolved))
+            .collect();
+        assert_eq!(changes, vec![(2, 0), (1, 1), (0, 1)]);
+        assert_eq!(trends.resolved, 2);
+        assert_eq!(trends.open.len(), 1);
+        assert_eq!(trends.open[0].fingerprint, "nsi_c");
+        assert_eq!(trends.open[0].first_seen, "2026-01-02T00:00:00Z");
+    }
+
+    #[test]
+    fn history_round_trips_through_jsonl() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(HISTORY_FILE);
+        assert!(read_history(&path).expect("missing file").is_empty());
+        let first = record("2026-01-01T00:00:00Z", &["nsi_a"]);
+        let second = record("2026-01-02T00:00:00Z", &[]);
+        append_history(&path, &first).expect("append");
+        append_history(&path, &second).expect("append");
+        assert_eq!(read_history(&path).expect("read"), vec![first, second]);
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::ReportError;
+
+// Everything that influences the findings of a scan. Two runs with equal
+// manifests scanned the same files with the same rules and settings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub version: String,
+    pub rules_hash: String,
+    #[serde(default)]
+    pub config_hash: Option<String>,
+    #[serde(default)]
+    pub ignore_hashes: BTreeMap<String, String>,
+    pub files_hash: String,
+    pub files: usize,
+    #[serde(default)]
+    pub flags: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, ReportError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ReportError> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    // Human readable list of the inputs that differ from `previous`.
+    pub fn changes(&self, previous: &Manifest) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.version != previous.version {
+            changes.push(format!(
+                "nosecrets version changed ({} -> {})",
+                previous.version, self.version
+            ));
+        }
+        if self.rules_hash != previous.rules_hash {
+            changes.push("rules changed".to_string());
+        }
+        if self.config_hash != previous.config_hash {
+            changes.push("config changed".to_string());
+        }
+        let names = self
+            .ignore_hashes
+            .keys()
+            .chain(previous.ignore_hashes.keys())
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            if self.ignore_hashes.get(name) != previous.ignore_hashes.get(name) {
+                changes.push(format!("{name} changed"));
+            }
+        }
+        if self.files_hash != previous.files_hash {
+            changes.push(format!(
+                "file list changed ({} -> {} files)",
+                previous.files, self.files
+            ));
+        }
+        let flags = self
+            .flags
+            .keys()
+            .chain(previous.flags.keys())
+            .collect::<BTreeSet<_>>();
+        for flag in flags {
+            let before = previous.flags.get(flag);
+            let after = self.flags.get(flag);
+            if before != after {
+                changes.push(format!(
+                    "flag {flag} changed ({} -> {})",
+                    before.map(String::as_str).unwrap_or("unset"),
+                    after.map(String::as_str).unwrap_or("unset")
+                ));
+            }
+        }
+        changes
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("sha256:{}", hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_changed_inputs() {
+        let previous = Manifest {
+            version: "0.1.7".to_string(),
+            rules_hash: sha256_hex(b"rules"),
+            files_hash: sha256_hex(b"a.rs"),
+            files: 1,
+            flags: BTreeMap::from([("staged".to_string(), "false".to_string())]),
+            ..Manifest::default()
+        };
+        assert!(previous.changes(&previous).is_empty());
+
+        let mut current = previous.clone();
+        current.rules_hash = sha256_hex(b"rules v2");
+        current
+            .ignore_hashes
+            .insert(".nosecretsignore".to_string(), sha256_hex(b""));
+        current
+            .flags
+            .insert("staged".to_string(), "true".to_string());
+        assert_eq!(
+            current.changes(&previous),
+            vec![
+                "rules changed",
+                ".nosecretsignore changed",
+                "flag staged changed (false -> true)",
+            ]
+        );
+    }
+}
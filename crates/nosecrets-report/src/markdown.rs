@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use nosecrets_rules::Severity;
+
+use crate::{location, Finding, Report, ReportError};
+
+// GitHub rejects issue and pull request comments above 65536 characters.
+pub const DEFAULT_COMMENT_LIMIT: usize = 65_536;
+
+// Room kept for the note that lists omitted groups.
+const OMITTED_NOTE_RESERVE: usize = 256;
+
+impl Report {
+    pub fn print_markdown(&self, limit: usize) -> Result<(), ReportError> {
+        let mut out = io::stdout();
+        write!(out, "{}", self.render_markdown(limit))?;
+        Ok(())
+    }
+
+    // Renders a PR comment with one collapsible section per severity and
+    // rule. When the comment would exceed `limit` bytes, the lowest-severity
+    // groups are dropped first and replaced by a note.
+    pub fn render_markdown(&self, limit: usize) -> String {
+        let mut out = String::from("### nosecrets\n\n");
+        if self.findings.is_empty() {
+            out.push_str("No secrets found.\n");
+            return out;
+        }
+
+        let mut groups: BTreeMap<(Severity, &str), Vec<&Finding>> = BTreeMap::new();
+        for finding in &self.findings {
+            groups
+                .entry((finding.severity, &*finding.rule_id))
+                .or_default()
+                .push(finding);
+        }
+        out.push_str(&headline(&self.findings));
+        out.push('\n');
+
+        let budget = limit.saturating_sub(OMITTED_NOTE_RESERVE);
+        let mut omitted_groups = 0;
+        let mut omitted_findings = 0;
+        for findings in groups.values() {
+            if omitted_groups == 0 {
+                if let Some(section) = section(findings, budget.saturating_sub(out.len())) {
+                    out.push_str(&section);
+                    continue;
+                }
+            }
+            omitted_groups += 1;
+            omitted_findings += findings.len();
+        }
+        if omitted_groups > 0 {
+            out.push_str(&format!(
+                "_{omitted_findings} finding(s) in {omitted_groups} lower-severity group(s) \
+                 omitted to fit the comment size limit. Run `nosecrets scan --format json` \
+                 for the full report._\n"
+            ));
+        }
+        out
+    }
+}
+
+fn headline(findings: &[Finding]) -> String {
+    let mut counts: BTreeMap<Severity, usize> = BTreeMap::new();
+    for finding in findings {
+        *counts.entry(finding.severity).or_default() += 1;
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(severity, count)| format!("{count} {}", severity.as_str()))
+        .collect();
+    format!(
+        "**{} potential secret(s)** ({})\n",
+        findings.len(),
+        counts.join(", ")
+    )
+}
+
+// Renders a group in at most `budget` bytes, leaving out rows that do not
+// fit. Returns None when not even a single row fits.
+fn section(findings: &[&Finding], budget: usize) -> Option<String> {
+    let first = findings[0];
+    let head = format!(
+        "<details>\n<summary><b>{}</b> · {} (<code>{}</code>) · {} finding(s)</summary>\n\n{}\
+         | Location | Fingerprint | Preview |\n| --- | --- | --- |\n",
+        label(first.severity),
+        escape(&first.rule_name),
+        escape(&first.rule_id),
+        findings.len(),
+        guidance(first)
+    );
+    let tail = "\n</details>\n\n";
+    let mut rows = String::new();
+    for (shown, finding) in findings.iter().enumerate() {
+        let row = row(finding);
+        let more = findings.len() - shown - 1;
+        let note = if more > 0 {
+            format!("\n_… and {more} more_\n")
+        } else {
+            String::new()
+        };
+        if head.len() + rows.len() + row.len() + note.len() + tail.len() > budget {
+            if shown == 0 {
+                return None;
+            }
+            rows.push_str(&format!("\n_… and {} more_\n", findings.len() - shown));
+            break;
+        }
+        rows.push_str(&row);
+    }
+    Some(format!("{head}{rows}{tail}"))
+}
+
+// The rule's remediation and documentation link, above its table.
+fn guidance(finding: &Finding) -> String {
+    let mut parts = Vec::new();
+    if let Some(remediation) = &finding.remediation {
+        parts.push(escape(remediation));
+    }
+    if let Some(url) = &finding.docs_url {
+        parts.push(format!(
+            "<a href=\"{}\">Documentation</a>",
+            escape(url).replace('"', "&quot;")
+        ));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n\n", parts.join(" "))
+    }
+}
+
+fn row(finding: &Finding) -> String {
+    let expired = finding
+        .expired_ignore
+        .as_ref()
+        .map(|expires| format!(" (expired ignore, {})", escape(expires)))
+        .unwrap_or_default();
+    format!(
+        "| <code>{}</code>{} | <code>{}</code> | <code>{}</code> |\n",
+        escape(&location(finding)),
+        expired,
+        escape(&finding.fingerprint),
+        escape(&finding.preview)
+    )
+}
+
+fn label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "Critical",
+        Severity::High => "High",
+        Severity::Medium => "Medium",
+        Severity::Low => "Low",
+    }
+}
+
+// Previews are attacker-controlled text; keep them from breaking out of the
+// table cell or injecting HTML into the comment.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '|' => escaped.push_str("&#124;"),
+            '`' => escaped.push_str("&#96;"),
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(rule_id: &str, severity: Severity, line: usize) -> Finding {
+        Finding {
+            path: "src/app.rs".to_string(),
+            line,
+            end_line: line,
+            column: 1,
+            end_column: 21,
+            byte_offset: 0,
+            match_length: 20,
+            rule_id: rule_id.into(),
+            rule_name: rule_id.to_uppercase().into(),
+            severity,
+            fingerprint: format!("nsi_{line:012}"),
+            preview: "ab|c...<x>".to_string(),
+            entropy: 3.5,
+            confidence: 0.8,
+            sampled: false,
+            cell: None,
+            message: None,
+            package: None,
+            owners: Vec::new(),
+            expired_ignore: None,
+            suppressed: None,
+            suppressed_by: None,
+            suggestion: None,
+            store_command: None,
+            blame: None,
+            jwt: None,
+            description: None,
+            docs_url: None,
+            remediation: None,
+        }
+    }
+
+    #[test]
+    fn groups_by_severity_and_truncates_lowest_first() {
+        let mut findings: Vec<Finding> = (1..=40)
+            .map(|line| finding("noise", Severity::Low, line))
+            .collect();
+        findings.push(finding("cloud-key", Severity::Critical, 100));
+        findings.push(finding("db-url", Severity::High, 101));
+        let report = Report::new(findings);
+
+        let full = report.render_markdown(DEFAULT_COMMENT_LIMIT);
+        assert!(full.contains("**42 potential secret(s)** (1 critical, 1 high, 40 low)"));
+        let critical = full.find("<b>Critical</b>").expect("critical group");
+        let low = full.find("<b>Low</b> · NOISE").expect("low group");
+        assert!(critical < low);
+        assert!(full.contains("<code>ab&#124;c...&lt;x&gt;</code>"));
+        assert!(!full.contains("omitted"));
+
+        let trimmed = report.render_markdown(1500);
+        assert!(trimmed.len() <= 1500);
+        assert!(trimmed.contains("_… and 36 more_"));
+
+        let limited = report.render_markdown(900);
+        assert!(limited.contains("<b>Critical</b>"));
+        assert!(limited.contains("<b>High</b>"));
+        assert!(!limited.contains("<b>Low</b>"));
+        assert!(limited.contains("_40 finding(s) in 1 lower-severity group(s) omitted"));
+
+        let mut documented = finding("cloud-key", Severity::Critical, 1);
+        documented.remediation = Some("Rotate <now>".into());
+        documented.docs_url = Some("https://docs.example/\"key".into());
+        let markdown = Report::new(vec![documented]).render_markdown(DEFAULT_COMMENT_LIMIT);
+        assert!(markdown.contains(
+            "Rotate &lt;now&gt; <a href=\"https://docs.example/&quot;key\">Documentation</a>\n\n| Location"
+        ));
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::{ReportError, Summary};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetricsFormat {
+    // The Prometheus text format read by node_exporter's textfile collector.
+    #[default]
+    Prometheus,
+    OpenMetrics,
+}
+
+impl Summary {
+    // Aggregate numbers of the scan as gauges. Paths, fingerprints and
+    // previews are left out so the file can be shipped to shared dashboards.
+    pub fn render_metrics(&self, format: MetricsFormat) -> String {
+        let mut out = String::new();
+        let family = |out: &mut String, name: &str, help: &str, unit: Option<&str>| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            if let (MetricsFormat::OpenMetrics, Some(unit)) = (format, unit) {
+                let _ = writeln!(out, "# UNIT {name} {unit}");
+            }
+        };
+
+        family(&mut out, "nosecrets_files_scanned", "Files scanned.", None);
+        let _ = writeln!(out, "nosecrets_files_scanned {}", self.files_scanned);
+        family(
+            &mut out,
+            "nosecrets_files_failed",
+            "Files that failed to scan.",
+            None,
+        );
+        let _ = writeln!(out, "nosecrets_files_failed {}", self.files_failed);
+        family(
+            &mut out,
+            "nosecrets_scan_duration_seconds",
+            "Duration of the scan.",
+            Some("seconds"),
+        );
+        let _ = writeln!(
+            out,
+            "nosecrets_scan_duration_seconds {:.3}",
+            self.duration_ms as f64 / 1000.0
+        );
+        family(
+            &mut out,
+            "nosecrets_findings",
+            "Findings by severity.",
+            None,
+        );
+        for (severity, count) in [
+            ("critical", self.severity.critical),
+            ("high", self.severity.high),
+            ("medium", self.severity.medium),
+            ("low", self.severity.low),
+        ] {
+            let _ = writeln!(out, "nosecrets_findings{{severity=\"{severity}\"}} {count}");
+        }
+        family(
+            &mut out,
+            "nosecrets_rule_findings",
+            "Findings by rule.",
+            None,
+        );
+        for (rule, count) in &self.rules_triggered {
+            let _ = writeln!(
+                out,
+                "nosecrets_rule_findings{{rule=\"{}\"}} {count}",
+                escape_label(rule)
+            );
+        }
+        if format == MetricsFormat::OpenMetrics {
+            out.push_str("# EOF\n");
+        }
+        out
+    }
+
+    // Written next to `path` and renamed into place, so a collector reading
+    // the file never sees it half written.
+    pub fn write_metrics(&self, path: &Path, format: MetricsFormat) -> Result<(), ReportError> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".tmp");
+        fs::write(&partial, self.render_metrics(format))?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_hold_only_aggregates() {
+        let mut summary = Summary {
+            files_scanned: 12,
+            duration_ms: 1500,
+            findings: 3,
+            ..Summary::default()
+        };
+        summary.severity.critical = 2;
+        summary.severity.low = 1;
+        summary
+            .rules_triggered
+            .insert("aws-access-key".to_string(), 2);
+        summary.rules_triggered.insert("odd\"rule".to_string(), 1);
+
+        let prometheus = summary.render_metrics(MetricsFormat::Prometheus);
+        assert!(prometheus
+            .contains("# TYPE nosecrets_files_scanned gauge\nnosecrets_files_scanned 12\n"));
+        assert!(prometheus.contains("nosecrets_scan_duration_seconds 1.500\n"));
+        assert!(prometheus.contains("nosecrets_findings{severity=\"critical\"} 2\n"));
+        assert!(prometheus.contains("nosecrets_findings{severity=\"high\"} 0\n"));
+        assert!(prometheus.contains("nosecrets_rule_findings{rule=\"aws-access-key\"} 2\n"));
+        assert!(prometheus.contains("nosecrets_rule_findings{rule=\"odd\\\"rule\"} 1\n"));
+        assert!(!prometheus.contains("# UNIT") && !prometheus.contains("# EOF"));
+
+        let openmetrics = summary.render_metrics(MetricsFormat::OpenMetrics);
+        assert!(openmetrics.contains("# UNIT nosecrets_scan_duration_seconds seconds\n"));
+        assert!(openmetrics.ends_with("# EOF\n"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+use crate::{location, Finding};
+
+// Placeholders the finding line may use.
+const PLACEHOLDERS: &[&str] = &[
+    "location",
+    "path",
+    "line",
+    "column",
+    "severity",
+    "rule",
+    "rule_name",
+    "fingerprint",
+    "preview",
+    "owners",
+    "package",
+    "link",
+];
+
+// Messages a catalog may replace, with their English text.
+const MESSAGES: &[(&str, &str)] = &[
+    ("no-findings", "No secrets found"),
+    ("critical", "CRITICAL"),
+    ("high", "HIGH"),
+    ("medium", "MEDIUM"),
+    ("low", "LOW"),
+    ("preview", "preview"),
+    ("suggestion", "suggestion"),
+    ("store", "store"),
+    ("jwt", "jwt"),
+    ("introduced", "introduced"),
+    ("runbook", "runbook"),
+    ("sampled", "found in a sampled region of a large file"),
+    (
+        "expired-ignore",
+        "expired ignore: suppression expired on {date}",
+    ),
+    (
+        "fixture-findings",
+        "{count} finding(s) in fixtures directories not reported (.nosecrets-fixtures)",
+    ),
+];
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{placeholder}}} in {template:?}")]
+    Placeholder {
+        template: String,
+        placeholder: String,
+    },
+    #[error("unclosed {{ in {0:?}; write {{{{ for a literal brace")]
+    Unclosed(String),
+    #[error("unknown message {0}")]
+    Message(String),
+}
+
+// Wording of the terminal report: the first line of each finding, messages
+// in another language and a runbook link per rule.
+#[derive(Debug, Clone, Default)]
+pub struct Template {
+    line: Option<String>,
+    messages: BTreeMap<String, String>,
+    links: BTreeMap<String, String>,
+}
+
+impl Template {
+    pub fn new(
+        line: Option<String>,
+        messages: BTreeMap<String, String>,
+        links: BTreeMap<String, String>,
+    ) -> Result<Self, TemplateError> {
+        if let Some(line) = &line {
+            for placeholder in placeholders(line)? {
+                if !PLACEHOLDERS.contains(&placeholder) {
+                    return Err(TemplateError::Placeholder {
+                        template: line.clone(),
+                        placeholder: placeholder.to_string(),
+                    });
+                }
+            }
+        }
+        for (key, message) in &messages {
+            if !MESSAGES.iter().any(|(known, _)| known == key) {
+                return Err(TemplateError::Message(key.clone()));
+            }
+            placeholders(message)?;
+        }
+        Ok(Self {
+            line,
+            messages,
+            links,
+        })
+    }
+
+    pub(crate) fn message<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| {
+                MESSAGES
+                    .iter()
+                    .find(|(known, _)| *known == key)
+                    .map(|(_, text)| *text)
+            })
+            .unwrap_or(key)
+    }
+
+    pub(crate) fn link(&self, rule_id: &str) -> Option<&str> {
+        self.links.get(rule_id).map(String::as_str)
+    }
+
+    // The custom first line of `finding`, with the severity and fingerprint
+    // as the report shows them.
+    pub(crate) fn render_line(
+        &self,
+        finding: &Finding,
+        severity: &str,
+        fingerprint: &str,
+    ) -> Option<String> {
+        let line = self.line.as_deref()?;
+        Some(fill(line, |placeholder| match placeholder {
+            "location" => location(finding),
+            "path" => finding.path.clone(),
+            "line" => finding.line.to_string(),
+            "column" => finding.column.to_string(),
+            "severity" => severity.to_string(),
+            "rule" => finding.rule_id.to_string(),
+            "rule_name" => finding.rule_name.to_string(),
+            "fingerprint" => fingerprint.to_string(),
+            "preview" => finding.preview.clone(),
+            "owners" => finding.owners.join(" "),
+            "package" => finding.package.clone().unwrap_or_default(),
+            "link" => self.link(&finding.rule_id).unwrap_or_default().to_string(),
+            _ => String::new(),
+        }))
+    }
+}
+
+fn placeholders(template: &str) -> Result<Vec<&str>, TemplateError> {
+    let mut found = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if rest[start + 1..].starts_with('{') {
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start + 1..].find('}') else {
+            return Err(TemplateError::Unclosed(template.to_string()));
+        };
+        found.push(&rest[start + 1..start + 1 + len]);
+        rest = &rest[start + len + 2..];
+    }
+    Ok(found)
+}
+
+// Replaces each `{name}` in `template`; `{{` and `}}` stand for braces.
+pub(crate) fn fill(template: &str, value: impl Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(idx) = rest.find(['{', '}']) {
+        out.push_str(&rest[..idx]);
+        let brace = &rest[idx..idx + 1];
+        if rest[idx + 1..].starts_with(brace) {
+            out.push_str(brace);
+            rest = &rest[idx + 2..];
+            continue;
+        }
+        match (brace, rest[idx + 1..].find('}')) {
+            ("{", Some(len)) => {
+                out.push_str(&value(&rest[idx + 1..idx + 1 + len]));
+                rest = &rest[idx + len + 2..];
+            }
+            _ => {
+                out.push_str(brace);
+                rest = &rest[idx + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_and_messages_are_checked() {
+        let template = Template::new(
+            Some("{{{severity}}} {path}:{line} {rule} -> {link}".to_string()),
+            BTreeMap::from([("high".to_string(), "HOCH".to_string())]),
+            BTreeMap::from([(
+                "aws-access-key".to_string(),
+                "https://wiki.example.org/aws".to_string(),
+            )]),
+        )
+        .expect("template");
+        assert_eq!(template.message("high"), "HOCH");
+        assert_eq!(template.message("preview"), "preview");
+
+        assert!(matches!(
+            Template::new(Some("{file}".to_string()), BTreeMap::new(), BTreeMap::new()),
+            Err(TemplateError::Placeholder { .. })
+        ));
+        assert!(matches!(
+            Template::new(Some("{path".to_string()), BTreeMap::new(), BTreeMap::new()),
+            Err(TemplateError::Unclosed(_))
+        ));
+        assert!(matches!(
+            Template::new(
+                None,
+                BTreeMap::from([("summary".to_string(), "x".to_string())]),
+                BTreeMap::new()
+            ),
+            Err(TemplateError::Message(_))
+        ));
+        assert_eq!(fill("{{{a}}} {b}", |name| name.to_uppercase()), "{A} B");
+    }
+}
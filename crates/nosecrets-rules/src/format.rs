@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+// The syntax of a config or rule file, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, Error)]
+pub enum FormatError {
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl FileFormat {
+    // `.yaml`/`.yml` and `.json` files; anything else is read as TOML.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("yaml" | "yml") => FileFormat::Yaml,
+            Some("json") => FileFormat::Json,
+            _ => FileFormat::Toml,
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T, FormatError> {
+        Ok(match self {
+            FileFormat::Toml => toml::from_str(content)?,
+            FileFormat::Yaml => serde_yaml::from_str(content)?,
+            FileFormat::Json => serde_json::from_str(content)?,
+        })
+    }
+}
@@ -1,7 +1,13 @@
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+mod format;
+
+pub use format::{FileFormat, FormatError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Critical,
@@ -12,7 +18,13 @@ pub enum Severity {
 
 impl Severity {
     pub fn blocks(self) -> bool {
-        matches!(self, Severity::Critical | Severity::High | Severity::Medium)
+        self.blocks_at(Severity::Medium)
+    }
+
+    // Whether a finding of this severity fails a scan that fails on
+    // `threshold` and above.
+    pub fn blocks_at(self, threshold: Severity) -> bool {
+        self <= threshold
     }
 
     pub fn as_str(self) -> &'static str {
@@ -33,18 +45,92 @@ pub struct Rule {
     pub pattern: String,
     #[serde(default)]
     pub keywords: Vec<String>,
-    #[serde(default = "default_capture")]
-    pub capture: usize,
+    #[serde(default)]
+    pub capture: Capture,
+    // Groups reported as separate findings with the ids `<id>.<group>`;
+    // replaces `capture`.
+    #[serde(default)]
+    pub captures: Vec<Capture>,
     #[serde(default)]
     pub validate: Option<RuleValidate>,
     #[serde(default)]
     pub paths: Option<RulePaths>,
     #[serde(default)]
     pub allow: Option<RuleAllow>,
+    #[serde(default)]
+    pub analyzer: Option<String>,
+    #[serde(default)]
+    pub context: Option<RuleContext>,
+    // `.` also matches newlines, so a match can span several lines.
+    #[serde(default)]
+    pub multiline: bool,
+    // Characters trimmed from both ends of a match before it is validated,
+    // fingerprinted and masked; DEFAULT_TRIM when unset, "" keeps matches
+    // as they are.
+    #[serde(default)]
+    pub trim: Option<String>,
+    // What the rule finds, where it is documented and what to do about a
+    // finding; shown by `rules show` and copied into findings.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub docs_url: Option<String>,
+    #[serde(default)]
+    pub remediation: Option<String>,
+    #[serde(default)]
+    pub tests: Vec<RuleTest>,
+}
+
+// Whitespace and quotes, so `"abc"`, `'abc'` and `abc` are the same secret.
+pub const DEFAULT_TRIM: &str = " \t\r\n\"'`";
+
+impl Rule {
+    pub fn trim_chars(&self) -> &str {
+        self.trim.as_deref().unwrap_or(DEFAULT_TRIM)
+    }
+
+    // Provider rules pin a prefix or an exact length; generic rules only
+    // describe the context around a value.
+    pub fn specificity(&self) -> u8 {
+        let Some(validate) = &self.validate else {
+            return 0;
+        };
+        2 * u8::from(!validate.prefix.is_empty()) + u8::from(validate.length.is_some())
+    }
+}
+
+// A capture group by index or by name (`(?P<secret>...)`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Capture {
+    Index(usize),
+    Name(String),
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Capture::Index(1)
+    }
 }
 
-fn default_capture() -> usize {
-    1
+impl std::fmt::Display for Capture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capture::Index(index) => write!(f, "{index}"),
+            Capture::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+// Samples checked by `nosecrets rules test`.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RuleTest {
+    #[serde(default, rename = "match")]
+    pub matches: Vec<String>,
+    #[serde(default)]
+    pub no_match: Vec<String>,
+    // Path the samples are scanned as, for rules limited by `paths`.
+    pub path: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -55,6 +141,27 @@ pub struct RuleValidate {
     pub length: Option<usize>,
     pub min_length: Option<usize>,
     pub max_length: Option<usize>,
+    // Shannon entropy of the match in bits per character, so words and
+    // repeated characters are not reported.
+    pub min_entropy: Option<f64>,
+    // Checked after the other constraints.
+    pub algorithm: Option<ValidateAlgorithm>,
+}
+
+// Structure or checksum a match must have to be reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidateAlgorithm {
+    // Payment card numbers; spaces and dashes between digits are skipped.
+    Luhn,
+    // The last 6 characters are the base62 CRC32 of the characters between
+    // the `prefix` and them, as in GitHub tokens.
+    Crc32,
+    // Decodes as standard or URL-safe base64, padded or not.
+    Base64,
+    // Three base64url segments whose header and payload are JSON objects
+    // and whose header names an `alg`.
+    Jwt,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -65,6 +172,18 @@ pub struct RulePaths {
     pub exclude: Vec<String>,
 }
 
+// Where in source code a match may occur. Only applies to files whose
+// language is known from the extension.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct RuleContext {
+    // The secret has to be inside a string literal.
+    #[serde(default)]
+    pub require_string: bool,
+    // Matches inside comments are dropped.
+    #[serde(default)]
+    pub skip_comments: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct RuleAllow {
     #[serde(default)]
@@ -85,51 +204,102 @@ pub enum RulesError {
     Parse {
         source: String,
         #[source]
-        error: toml::de::Error,
+        error: FormatError,
     },
 }
 
-pub fn load_builtin_rules() -> Result<Vec<Rule>, RulesError> {
-    let mut rules = Vec::new();
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/cloud.toml")),
+pub const BUILTIN_RULE_FILES: &[(&str, &str)] = &[
+    (
         "rules/cloud.toml",
-    )?);
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/deploy.toml")),
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/cloud.toml")),
+    ),
+    (
         "rules/deploy.toml",
-    )?);
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/code.toml")),
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/deploy.toml")),
+    ),
+    (
         "rules/code.toml",
-    )?);
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/database.toml")),
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/code.toml")),
+    ),
+    (
         "rules/database.toml",
-    )?);
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/generic.toml")),
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/database.toml")),
+    ),
+    (
         "rules/generic.toml",
-    )?);
-    rules.extend(parse_rules(
-        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/payment.toml")),
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/generic.toml")),
+    ),
+    (
         "rules/payment.toml",
-    )?);
-    rules.extend(parse_rules(
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/payment.toml")),
+    ),
+    (
+        "rules/communication.toml",
         include_str!(concat!(
             env!("CARGO_MANIFEST_DIR"),
             "/rules/communication.toml"
         )),
-        "rules/communication.toml",
-    )?);
+    ),
+    (
+        "rules/webhook.toml",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/webhook.toml")),
+    ),
+    (
+        "rules/exposure.toml",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/exposure.toml")),
+    ),
+    (
+        "rules/url.toml",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/url.toml")),
+    ),
+    (
+        "rules/kubernetes.toml",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/rules/kubernetes.toml"
+        )),
+    ),
+    (
+        "rules/cicd.toml",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/cicd.toml")),
+    ),
+    (
+        "rules/saas.toml",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/rules/saas.toml")),
+    ),
+    (
+        "rules/filesystem.toml",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/rules/filesystem.toml"
+        )),
+    ),
+    (
+        "rules/local-creds.toml",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/rules/local-creds.toml"
+        )),
+    ),
+];
+
+pub fn load_builtin_rules() -> Result<Vec<Rule>, RulesError> {
+    let mut rules = Vec::new();
+    for (source, content) in BUILTIN_RULE_FILES {
+        rules.extend(parse_rules(content, source)?);
+    }
     Ok(rules)
 }
 
+// Parses the rule file `source` as TOML, YAML or JSON by its extension. Each
+// format holds the rules in a top-level `rule` list.
 pub fn parse_rules(content: &str, source: &str) -> Result<Vec<Rule>, RulesError> {
-    let parsed: RulesFile = toml::from_str(content).map_err(|error| RulesError::Parse {
-        source: source.to_string(),
-        error,
-    })?;
+    let parsed: RulesFile = FileFormat::from_path(Path::new(source))
+        .parse(content)
+        .map_err(|error| RulesError::Parse {
+            source: source.to_string(),
+            error,
+        })?;
     Ok(parsed.rule)
 }
 
@@ -152,7 +322,56 @@ mod tests {
         assert_eq!(rule.id, "test");
         assert_eq!(rule.name, "Test Rule");
         assert_eq!(rule.severity, Severity::High);
-        assert_eq!(rule.capture, 1);
+        assert_eq!(rule.capture, Capture::Index(1));
         assert_eq!(rule.keywords.len(), 0);
+        assert!(rule.tests.is_empty());
+    }
+
+    #[test]
+    fn parse_rule_tests() {
+        let toml = r#"
+            [[rule]]
+            id = "test"
+            name = "Test Rule"
+            severity = "high"
+            pattern = '''(test_[A-Za-z0-9]+)'''
+
+            [[rule.tests]]
+            match = ["token = test_abc"]
+            no_match = ["token = prod_abc"]
+            path = "config/.env"
+        "#;
+        let rules = parse_rules(toml, "inline").expect("parse rules");
+        let test = &rules[0].tests[0];
+        assert_eq!(test.matches, vec!["token = test_abc"]);
+        assert_eq!(test.no_match, vec!["token = prod_abc"]);
+        assert_eq!(test.path.as_deref(), Some("config/.env"));
+    }
+
+    #[test]
+    fn parse_rules_by_extension() {
+        let yaml = r#"
+rule:
+  - id: test
+    name: Test Rule
+    severity: high
+    pattern: '(test_[A-Za-z0-9]+)'
+    keywords: [test_]
+    capture: 1
+"#;
+        let rules = parse_rules(yaml, "rules/team.yaml").expect("parse yaml");
+        assert_eq!(rules[0].keywords, vec!["test_"]);
+        assert_eq!(rules[0].capture, Capture::Index(1));
+
+        let json = r#"{"rule": [{"id": "test", "name": "Test Rule", "severity": "low",
+            "pattern": "(?P<secret>test_[A-Za-z0-9]+)", "capture": "secret"}]}"#;
+        let rules = parse_rules(json, "generated.json").expect("parse json");
+        assert_eq!(rules[0].severity, Severity::Low);
+        assert_eq!(rules[0].capture, Capture::Name("secret".to_string()));
+
+        let error = parse_rules(json, "generated.toml").expect_err("json is not toml");
+        assert!(error
+            .to_string()
+            .starts_with("failed to parse rules from generated.toml"));
     }
 }